@@ -0,0 +1,82 @@
+//! Compares the search of an automaton with compressed fail links with that of the automaton
+//! without them, in terms of the search time and the average number of fail links followed per
+//! byte of the haystack.
+//!
+//! Run with `cargo bench --bench compress_fail`.
+
+use std::time::{Duration, Instant};
+
+use daachorse::{DoubleArrayAhoCorasick, DoubleArrayAhoCorasickBuilder};
+
+const HAYSTACK_LEN: usize = 20 << 20;
+const NUM_PATTERNS: usize = 200;
+const MAX_PATTERN_LEN: u32 = 16;
+// A small alphabet makes long chains of fail links.
+const ALPHABET_LEN: u32 = 4;
+const NUM_TRIALS: usize = 5;
+
+fn random_u32(seed: &mut u32) -> u32 {
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 17;
+    *seed ^= *seed << 5;
+    *seed
+}
+
+fn random_bytes(seed: &mut u32, len: usize) -> Vec<u8> {
+    (0..len)
+        .map(|_| b'a' + u8::try_from(random_u32(seed) % ALPHABET_LEN).unwrap())
+        .collect()
+}
+
+/// Returns the shortest time of the trials and the result of the last one.
+fn measure<F>(mut f: F) -> (Duration, usize)
+where
+    F: FnMut() -> usize,
+{
+    let mut best = Duration::MAX;
+    let mut result = 0;
+    for _ in 0..NUM_TRIALS {
+        let start = Instant::now();
+        result = f();
+        best = best.min(start.elapsed());
+    }
+    (best, result)
+}
+
+fn main() {
+    let mut seed = 2463534242;
+    let mut patterns = vec![];
+    for _ in 0..NUM_PATTERNS {
+        let len = usize::try_from(random_u32(&mut seed) % MAX_PATTERN_LEN + 1).unwrap();
+        patterns.push(random_bytes(&mut seed, len));
+    }
+    patterns.sort_unstable();
+    patterns.dedup();
+    let haystack = random_bytes(&mut seed, HAYSTACK_LEN);
+
+    let pma = DoubleArrayAhoCorasick::<u32>::new(&patterns).unwrap();
+    let pma_compressed: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+        .compress_fail(true)
+        .build(&patterns)
+        .unwrap();
+
+    let (time, expected) = measure(|| pma.find_overlapping_iter(&haystack).count());
+    let (time_compressed, result) =
+        measure(|| pma_compressed.find_overlapping_iter(&haystack).count());
+    assert_eq!(expected, result);
+    println!("find_overlapping_iter (compress_fail = false): {time:?}");
+    println!("find_overlapping_iter (compress_fail = true):  {time_compressed:?}");
+
+    #[allow(clippy::cast_precision_loss)]
+    let average_hops = |pma: &DoubleArrayAhoCorasick<u32>| {
+        pma.count_fail_hops(&haystack) as f64 / HAYSTACK_LEN as f64
+    };
+    println!(
+        "average fail hops per byte (compress_fail = false): {:.4}",
+        average_hops(&pma),
+    );
+    println!(
+        "average fail hops per byte (compress_fail = true):  {:.4}",
+        average_hops(&pma_compressed),
+    );
+}
@@ -0,0 +1,63 @@
+//! Compares the search of an automaton with a single pattern, which locates occurrences by the
+//! two-way algorithm, with the general search by the transitions of the same automaton.
+//!
+//! Run with `cargo bench --bench single_pattern`.
+
+use std::time::{Duration, Instant};
+
+use daachorse::DoubleArrayAhoCorasick;
+
+const HAYSTACK_LEN: usize = 20 << 20;
+const PATTERN_LEN: usize = 70;
+const NUM_TRIALS: usize = 5;
+
+fn random_bytes(seed: &mut u32, len: usize) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            *seed ^= *seed << 13;
+            *seed ^= *seed >> 17;
+            *seed ^= *seed << 5;
+            b'a' + u8::try_from(*seed % 26).unwrap()
+        })
+        .collect()
+}
+
+/// Returns the shortest time of the trials and the result of the last one.
+fn measure<F>(mut f: F) -> (Duration, usize)
+where
+    F: FnMut() -> usize,
+{
+    let mut best = Duration::MAX;
+    let mut result = 0;
+    for _ in 0..NUM_TRIALS {
+        let start = Instant::now();
+        result = f();
+        best = best.min(start.elapsed());
+    }
+    (best, result)
+}
+
+fn main() {
+    let mut seed = 2463534242;
+    let pattern = random_bytes(&mut seed, PATTERN_LEN);
+    let mut haystack = random_bytes(&mut seed, HAYSTACK_LEN);
+    for i in 1..10 {
+        let pos = HAYSTACK_LEN / 10 * i;
+        haystack[pos..pos + PATTERN_LEN].copy_from_slice(&pattern);
+    }
+    let pma = DoubleArrayAhoCorasick::<u32>::new([&pattern]).unwrap();
+
+    let (general, expected) = measure(|| {
+        pma.find_iter_from_iter(haystack.iter().copied())
+            .map(|m| m.end())
+            .sum()
+    });
+    let (single, result) = measure(|| pma.find_iter(&haystack).map(|m| m.end()).sum());
+    assert_eq!(expected, result);
+    println!("find_iter (general path):       {general:?}");
+    println!("find_iter (single pattern):     {single:?}");
+
+    let (single, result) = measure(|| usize::from(pma.is_match(&haystack[..HAYSTACK_LEN / 10])));
+    assert_eq!(0, result);
+    println!("is_match (single pattern, miss): {single:?}");
+}
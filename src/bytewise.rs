@@ -1,29 +1,56 @@
 //! A byte-wise version of the Double-Array Aho-Corasick.
 
 mod builder;
+pub mod grouped;
 pub mod iter;
+mod memmem;
+pub mod narrow;
+pub mod sorted;
+mod state;
+mod wildcard;
 
+use core::iter::Take;
 use core::mem;
-use core::num::NonZeroU32;
+use core::ops::{ControlFlow, Range};
 
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
 use alloc::vec::Vec;
 
 use crate::build_helper::BuildHelper;
 use crate::errors::{DaachorseError, Result};
-use crate::intpack::{U24nU8, U24};
-use crate::serializer::{Serializable, SerializableVec};
-use crate::utils::FromU32;
-use crate::{MatchKind, Output};
-pub use builder::DoubleArrayAhoCorasickBuilder;
+use crate::serializer::{skip_serialized, skip_serialized_vec, Serializable, SerializableVec};
+use crate::utils::{self, FromU32};
+use crate::{Match, MatchKind, MatchSink, Output, SuffixPolicy};
+use builder::BLOCK_LEN;
+pub use builder::{BuildStats, DoubleArrayAhoCorasickBuilder};
+pub use grouped::GroupedDoubleArrayAhoCorasick;
 use iter::{
-    FindIterator, FindOverlappingIterator, FindOverlappingNoSuffixIterator, LestmostFindIterator,
+    FindAnchoredIterator, FindFilteredIterator, FindIterator, FindManyIterator,
+    FindOverlappingDedupIterator, FindOverlappingFastIterator, FindOverlappingIterator,
+    FindOverlappingNoSuffixIterator, FindSkippingIterator, FindState, LeftmostFindIterator,
     U8SliceIterator,
 };
+pub use narrow::{AdaptiveDoubleArrayAhoCorasick, DoubleArrayAhoCorasick16};
+pub use sorted::SortedDoubleArrayAhoCorasick;
+use state::{DoubleArrayState, State};
+
+#[cfg(feature = "unicode")]
+use iter::FindGraphemesIterator;
 
 // The root index position.
 const ROOT_STATE_IDX: u32 = 0;
 // The dead index position.
 const DEAD_STATE_IDX: u32 = 1;
+// The number of bytes read at once in stream_replace_all().
+#[cfg(feature = "std")]
+const STREAM_CHUNK_LEN: usize = 8192;
+// The bit set in the serialized match kind if the extension fields follow the 1.0.0 layout.
+const EXTENDED_FORMAT_FLAG: u8 = 0x80;
+// The version of the extension fields.
+const EXTENDED_FORMAT_VERSION: u32 = 2;
+// The bits of the serialized build options added in version 2 of the extension fields.
+const PREFIX_ONLY_FLAG: u8 = 0x01;
+const MINIMIZE_SUFFIXES_FLAG: u8 = 0x02;
 
 /// A fast multiple pattern match automaton implemented with the Aho-Corasick algorithm and compact
 /// double-array data structure.
@@ -49,12 +76,37 @@ const DEAD_STATE_IDX: u32 = 1;
 ///
 /// The maximum number of patterns is limited to 2^24-1. If a larger number of patterns is given,
 /// [`DaachorseError`] will be reported.
+///
+/// # Thread safety
+///
+/// The automaton is immutable after the construction, and all search methods take `&self`.
+/// Thus, [`DoubleArrayAhoCorasick`] is [`Send`] and [`Sync`] if `V` is, and an automaton can be
+/// shared among threads, e.g., with [`Arc`](alloc::sync::Arc), without any locking.
+///
+/// # Representation of states
+///
+/// The type parameter `S` is the crate-internal representation of states, which uses 32-bit
+/// indices by default. See [`DoubleArrayAhoCorasick16`] for the variant with 16-bit indices.
 #[derive(Clone, Eq, Hash, PartialEq)]
-pub struct DoubleArrayAhoCorasick<V> {
-    states: Vec<State>,
+pub struct DoubleArrayAhoCorasick<V, S = State> {
+    states: Vec<S>,
     outputs: Vec<Output<V>>,
     match_kind: MatchKind,
     num_states: u32,
+    // Sorted IDs of states whose transitions are precomputed in `fail_table`.
+    fail_ids: Vec<u32>,
+    // Next state IDs for all 256 labels of each state in `fail_ids`.
+    fail_table: Vec<u32>,
+    // Position of each state in `fail_ids` plus one, or zero if the state is not compressed.
+    // Empty if no state is compressed.
+    fail_slots: Vec<u16>,
+    // Sorted bytes ignored by `find_iter_skipping()`.
+    skip_bytes: Vec<u8>,
+    // Searcher of the pattern if only one is registered.
+    single_pattern: Option<memmem::TwoWay>,
+    // Build options not otherwise recoverable from the arrays, carried over by `merge()`.
+    prefix_only: bool,
+    minimize_suffixes: bool,
 }
 
 impl<V> DoubleArrayAhoCorasick<V> {
@@ -147,36 +199,54 @@ impl<V> DoubleArrayAhoCorasick<V> {
         DoubleArrayAhoCorasickBuilder::new().build_with_values(patvals)
     }
 
-    /// Returns an iterator of non-overlapping matches in the given haystack.
+    /// Returns an iterator of non-overlapping matches in the given haystack, starting from the
+    /// given state.
+    ///
+    /// This continues a search of [`DoubleArrayAhoCorasick::find_iter()`] over a document split
+    /// into chunks: pass the state returned by [`FindIterator::into_state()`] for the previous
+    /// chunk, or [`FindState::default()`] for the first one. Matches crossing chunk boundaries
+    /// are reported by the iterator of the chunk in which they end, and the positions of all
+    /// matches are relative to the document, so the result is the same as searching the
+    /// concatenated chunks.
+    ///
+    /// Unlike [`DoubleArrayAhoCorasick::find_iter_from_iter()`] or
+    /// [`DoubleArrayAhoCorasick::stream_replace_all()`], the chunks do not have to be available
+    /// through a single iterator or reader; the caller threads the state between calls.
     ///
     /// # Arguments
     ///
     /// * `haystack` - String to search for.
+    /// * `state` - State where the search of the previous chunk stopped.
     ///
     /// # Panics
     ///
     /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
-    /// supported and the function will panic.
+    /// supported and the function will panic. It also panics if `state` is not created by an
+    /// iterator of this automaton and is out of range.
     ///
     /// # Examples
     ///
     /// ```
+    /// use daachorse::bytewise::iter::FindState;
     /// use daachorse::DoubleArrayAhoCorasick;
     ///
-    /// let patterns = vec!["bcd", "ab", "a"];
-    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
-    ///
-    /// let mut it = pma.find_iter("abcd");
-    ///
-    /// let m = it.next().unwrap();
-    /// assert_eq!((0, 1, 2), (m.start(), m.end(), m.value()));
-    ///
-    /// let m = it.next().unwrap();
-    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    /// let patterns = vec!["abcd", "ef"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
     ///
-    /// assert_eq!(None, it.next());
+    /// let mut state = FindState::default();
+    /// let mut matches = vec![];
+    /// for chunk in ["xab", "cdef", "gh"] {
+    ///     let mut it = pma.find_iter_with_state(chunk, state);
+    ///     matches.extend(it.by_ref().map(|m| (m.start(), m.end(), m.value())));
+    ///     state = it.into_state();
+    /// }
+    /// assert_eq!(vec![(1, 5, 0), (5, 7, 1)], matches);
     /// ```
-    pub fn find_iter<P>(&self, haystack: P) -> FindIterator<'_, U8SliceIterator<P>, V>
+    pub fn find_iter_with_state<P>(
+        &self,
+        haystack: P,
+        state: FindState,
+    ) -> FindIterator<'_, U8SliceIterator<P>, V>
     where
         P: AsRef<[u8]>,
     {
@@ -184,17 +254,25 @@ impl<V> DoubleArrayAhoCorasick<V> {
             self.match_kind.is_standard(),
             "Error: match_kind must be standard."
         );
-        FindIterator {
-            pma: self,
-            haystack: U8SliceIterator::new(haystack).enumerate(),
-        }
+        assert!(
+            usize::from_u32(state.state_id) < self.states.len(),
+            "Error: state must be created by this automaton."
+        );
+        FindIterator::with_slice(self, haystack, state.state_id, state.offset)
     }
 
-    /// Returns an iterator of non-overlapping matches in the given haystack iterator.
+    /// Returns an iterator of non-overlapping matches in the given haystacks, searched one after
+    /// another.
+    ///
+    /// Each match is reported together with the index of its haystack, and its positions are
+    /// relative to the haystack. The search restarts from the root for each haystack, so no
+    /// match spans two haystacks. This is equivalent to calling
+    /// [`DoubleArrayAhoCorasick::find_iter()`] for each haystack, but the haystacks are consumed
+    /// lazily through a single iterator.
     ///
     /// # Arguments
     ///
-    /// * `haystack` - [`u8`] iterator to search for.
+    /// * `haystacks` - Strings to search for.
     ///
     /// # Panics
     ///
@@ -209,33 +287,43 @@ impl<V> DoubleArrayAhoCorasick<V> {
     /// let patterns = vec!["bcd", "ab", "a"];
     /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
     ///
-    /// let haystack = "ab".as_bytes().iter().chain("cd".as_bytes()).copied();
-    ///
-    /// let mut it = pma.find_iter_from_iter(haystack);
+    /// let mut it = pma.find_iter_many(["abc", "xyz", "bcd"]);
     ///
-    /// let m = it.next().unwrap();
-    /// assert_eq!((0, 1, 2), (m.start(), m.end(), m.value()));
+    /// let (i, m) = it.next().unwrap();
+    /// assert_eq!((0, 0, 1, 2), (i, m.start(), m.end(), m.value()));
     ///
-    /// let m = it.next().unwrap();
-    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    /// let (i, m) = it.next().unwrap();
+    /// assert_eq!((2, 0, 3, 0), (i, m.start(), m.end(), m.value()));
     ///
     /// assert_eq!(None, it.next());
     /// ```
-    pub fn find_iter_from_iter<P>(&self, haystack: P) -> FindIterator<'_, P, V>
+    pub fn find_iter_many<I, P>(&self, haystacks: I) -> FindManyIterator<'_, I::IntoIter, V>
     where
-        P: Iterator<Item = u8>,
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u8]>,
     {
         assert!(
             self.match_kind.is_standard(),
             "Error: match_kind must be standard."
         );
-        FindIterator {
+        FindManyIterator {
             pma: self,
-            haystack: haystack.enumerate(),
+            haystacks: haystacks.into_iter().enumerate(),
+            index: 0,
+            current: None,
         }
     }
 
-    /// Returns an iterator of overlapping matches in the given haystack.
+    /// Returns an iterator of non-overlapping matches in the given string whose both ends are on
+    /// boundaries of extended grapheme clusters.
+    ///
+    /// This iterator filters the matches of [`DoubleArrayAhoCorasick::find_iter()`], so a match
+    /// splitting a grapheme cluster, such as a character followed by a combining mark, is skipped
+    /// without searching for other candidates overlapping with it.
+    ///
+    /// The boundary check is performed only for reported matches, and it usually looks at a few
+    /// characters around each end. However, it can scan a long run of regional indicators
+    /// backward, so it can be costly if such sequences are frequent in the haystack.
     ///
     /// # Arguments
     ///
@@ -251,43 +339,192 @@ impl<V> DoubleArrayAhoCorasick<V> {
     /// ```
     /// use daachorse::DoubleArrayAhoCorasick;
     ///
-    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let patterns = vec!["cafe", "e"];
     /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
     ///
-    /// let mut it = pma.find_overlapping_iter("abcd");
+    /// // "cafe" is followed by U+0301 (combining acute accent).
+    /// let mut it = pma.find_iter_graphemes("cafe\u{301} e");
     ///
     /// let m = it.next().unwrap();
-    /// assert_eq!((0, 1, 2), (m.start(), m.end(), m.value()));
+    /// assert_eq!((7, 8, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    #[cfg(feature = "unicode")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode")))]
+    pub fn find_iter_graphemes<'h>(&self, haystack: &'h str) -> FindGraphemesIterator<'_, 'h, V> {
+        FindGraphemesIterator {
+            inner: self.find_iter(haystack),
+            haystack,
+        }
+    }
+
+    /// Returns an iterator of non-overlapping matches in the given OS string, such as a file
+    /// path.
+    ///
+    /// On Unix, [`OsStr`](std::ffi::OsStr) is an arbitrary byte sequence, which is searched as is
+    /// without any conversion. This method is not available on other platforms because their OS
+    /// strings have no lossless byte representation.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - OS string to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    ///
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec![".rs", "src/"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let path = Path::new("/src/lib.rs");
+    /// let mut it = pma.find_iter_os(path.as_os_str());
     ///
     /// let m = it.next().unwrap();
-    /// assert_eq!((0, 2, 1), (m.start(), m.end(), m.value()));
+    /// assert_eq!((1, 5, 1), (m.start(), m.end(), m.value()));
     ///
     /// let m = it.next().unwrap();
-    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    /// assert_eq!((8, 11, 0), (m.start(), m.end(), m.value()));
     ///
     /// assert_eq!(None, it.next());
     /// ```
-    pub fn find_overlapping_iter<P>(
+    #[cfg(all(unix, feature = "std"))]
+    #[cfg_attr(docsrs, doc(cfg(all(unix, feature = "std"))))]
+    pub fn find_iter_os<'h>(
         &self,
-        haystack: P,
-    ) -> FindOverlappingIterator<'_, U8SliceIterator<P>, V>
+        haystack: &'h std::ffi::OsStr,
+    ) -> FindIterator<'_, U8SliceIterator<&'h [u8]>, V> {
+        use std::os::unix::ffi::OsStrExt;
+
+        self.find_iter(haystack.as_bytes())
+    }
+
+    /// Copies bytes from `reader` to `writer` while replacing non-overlapping matches with
+    /// `replacements[m.value()]`.
+    ///
+    /// The matches are the same as those of [`DoubleArrayAhoCorasick::find_iter()`] over the
+    /// whole input. The input is read in chunks, and the automaton state is carried across chunk
+    /// boundaries. Since a match reported later starts at most `L - 1` bytes before the current
+    /// position, where `L` is the length of the longest pattern, only the last `L - 1` bytes
+    /// since the last match are held back at the end of each chunk, and the preceding bytes are
+    /// written through. Thus, the memory usage is bounded by the chunk size and `L`, regardless
+    /// of the input length.
+    ///
+    /// `writer` is not flushed by this function.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Source of the input.
+    /// * `writer` - Destination of the output.
+    /// * `replacements` - Replacements indexed by the values of matches.
+    ///
+    /// # Errors
+    ///
+    /// [`std::io::Error`] is returned when reading from `reader` or writing to `writer` fails.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the function will
+    /// panic. It also panics if the value of a match is out of the bounds of `replacements`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let mut output = vec![];
+    /// pma.stream_replace_all("xabcdabx".as_bytes(), &mut output, &["B", "AB", "A"])
+    ///     .unwrap();
+    ///
+    /// assert_eq!(b"xABAbx", output.as_slice());
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn stream_replace_all<R, W, B>(
+        &self,
+        mut reader: R,
+        mut writer: W,
+        replacements: &[B],
+    ) -> std::io::Result<()>
     where
-        P: AsRef<[u8]>,
+        R: std::io::Read,
+        W: std::io::Write,
+        B: AsRef<[u8]>,
+        V: Copy + TryInto<usize>,
     {
         assert!(
             self.match_kind.is_standard(),
             "Error: match_kind must be standard."
         );
-        FindOverlappingIterator {
-            pma: self,
-            haystack: U8SliceIterator::new(haystack).enumerate(),
-            state_id: ROOT_STATE_IDX,
-            output_pos: None,
-            pos: 0,
+        let max_len = self
+            .outputs
+            .iter()
+            .map(|out| usize::from_u32(out.length()))
+            .max()
+            .unwrap_or(0);
+
+        let mut buf = vec![0; STREAM_CHUNK_LEN];
+        // Bytes read but not written yet.
+        let mut pending = vec![];
+        let mut state_id = ROOT_STATE_IDX;
+        loop {
+            let len = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            for &c in &buf[..len] {
+                pending.push(c);
+                // state_id is always smaller than self.states.len() because
+                // self.next_state_id_unchecked() ensures to return such a value.
+                state_id = unsafe { self.next_state_id_unchecked(state_id, c) };
+                if let Some(output_pos) = unsafe {
+                    self.states
+                        .get_unchecked(usize::from_u32(state_id))
+                        .output_pos()
+                } {
+                    let out = self.outputs[usize::from_u32(output_pos.get() - 1)];
+                    let replacement = out
+                        .value()
+                        .try_into()
+                        .ok()
+                        .and_then(|i| replacements.get(i))
+                        .unwrap_or_else(|| {
+                            panic!(
+                                "Error: value of a match is out of bounds of the replacements of length {}.",
+                                replacements.len()
+                            )
+                        });
+                    let start = pending.len() - usize::from_u32(out.length());
+                    writer.write_all(&pending[..start])?;
+                    writer.write_all(replacement.as_ref())?;
+                    pending.clear();
+                    state_id = ROOT_STATE_IDX;
+                }
+            }
+            // Bytes before the last max_len - 1 bytes can never be a part of a match.
+            if pending.len() >= max_len {
+                let flushed = pending.len() + 1 - max_len;
+                writer.write_all(&pending[..flushed])?;
+                pending.drain(..flushed);
+            }
         }
+        writer.write_all(&pending)
     }
 
-    /// Returns an iterator of overlapping matches in the given haystack iterator.
+    /// Returns an iterator of non-overlapping matches in the given haystack iterator.
     ///
     /// # Arguments
     ///
@@ -308,23 +545,17 @@ impl<V> DoubleArrayAhoCorasick<V> {
     ///
     /// let haystack = "ab".as_bytes().iter().chain("cd".as_bytes()).copied();
     ///
-    /// let mut it = pma.find_overlapping_iter_from_iter(haystack);
+    /// let mut it = pma.find_iter_from_iter(haystack);
     ///
     /// let m = it.next().unwrap();
     /// assert_eq!((0, 1, 2), (m.start(), m.end(), m.value()));
     ///
     /// let m = it.next().unwrap();
-    /// assert_eq!((0, 2, 1), (m.start(), m.end(), m.value()));
-    ///
-    /// let m = it.next().unwrap();
     /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
     ///
     /// assert_eq!(None, it.next());
     /// ```
-    pub fn find_overlapping_iter_from_iter<P>(
-        &self,
-        haystack: P,
-    ) -> FindOverlappingIterator<'_, P, V>
+    pub fn find_iter_from_iter<P>(&self, haystack: P) -> FindIterator<'_, P, V>
     where
         P: Iterator<Item = u8>,
     {
@@ -332,26 +563,25 @@ impl<V> DoubleArrayAhoCorasick<V> {
             self.match_kind.is_standard(),
             "Error: match_kind must be standard."
         );
-        FindOverlappingIterator {
+        FindIterator {
             pma: self,
-            haystack: haystack.enumerate(),
-            state_id: ROOT_STATE_IDX,
-            output_pos: None,
+            haystack,
             pos: 0,
+            state_id: ROOT_STATE_IDX,
+            offset: 0,
+            single_pattern: None,
         }
     }
 
-    /// Returns an iterator of overlapping matches without suffixes in the given haystack iterator.
-    ///
-    /// The Aho-Corasick algorithm reads through the haystack from left to right and reports
-    /// matches when it reaches the end of each pattern. In the overlapping match, more than one
-    /// pattern can be returned per report.
+    /// Returns an iterator of at most `max` non-overlapping matches in the given haystack.
     ///
-    /// This iterator returns the first match on each report.
+    /// The iterator scans the haystack lazily, so no byte is read after the `max`-th match is
+    /// reported.
     ///
     /// # Arguments
     ///
     /// * `haystack` - String to search for.
+    /// * `max` - The maximum number of matches to report.
     ///
     /// # Panics
     ///
@@ -363,48 +593,44 @@ impl<V> DoubleArrayAhoCorasick<V> {
     /// ```
     /// use daachorse::DoubleArrayAhoCorasick;
     ///
-    /// let patterns = vec!["bcd", "cd", "abc"];
+    /// let patterns = vec!["bcd", "ab", "a"];
     /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
     ///
-    /// let mut it = pma.find_overlapping_no_suffix_iter("abcd");
+    /// let mut it = pma.find_iter_limited("abcdabcd", 2);
     ///
     /// let m = it.next().unwrap();
-    /// assert_eq!((0, 3, 2), (m.start(), m.end(), m.value()));
+    /// assert_eq!((0, 1, 2), (m.start(), m.end(), m.value()));
     ///
     /// let m = it.next().unwrap();
     /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
     ///
     /// assert_eq!(None, it.next());
     /// ```
-    pub fn find_overlapping_no_suffix_iter<P>(
+    pub fn find_iter_limited<P>(
         &self,
         haystack: P,
-    ) -> FindOverlappingNoSuffixIterator<'_, U8SliceIterator<P>, V>
+        max: usize,
+    ) -> Take<FindIterator<'_, U8SliceIterator<P>, V>>
     where
         P: AsRef<[u8]>,
+        V: Copy,
     {
-        assert!(
-            self.match_kind.is_standard(),
-            "Error: match_kind must be standard."
-        );
-        FindOverlappingNoSuffixIterator {
-            pma: self,
-            haystack: U8SliceIterator::new(haystack).enumerate(),
-            state_id: ROOT_STATE_IDX,
-        }
+        self.find_iter(haystack).take(max)
     }
 
-    /// Returns an iterator of overlapping matches without suffixes in the given haystack iterator.
-    ///
-    /// The Aho-Corasick algorithm reads through the haystack from left to right and reports
-    /// matches when it reaches the end of each pattern. In the overlapping match, more than one
-    /// pattern can be returned per report.
+    /// Returns an iterator of non-overlapping matches in the given haystack, skipping matches
+    /// whose values do not satisfy the given predicate.
     ///
-    /// This iterator returns the first match on each report.
+    /// A state is regarded as matching only if one of its outputs satisfies `keep`, and the
+    /// longest such output is reported. Otherwise, the scan continues from the state without
+    /// restarting, so a longer pattern containing a rejected one can still be found. The results
+    /// are the same as those of [`DoubleArrayAhoCorasick::find_iter()`] with an automaton built
+    /// only from the patterns satisfying `keep`.
     ///
     /// # Arguments
     ///
-    /// * `haystack` - [`u8`] to search for.
+    /// * `haystack` - String to search for.
+    /// * `keep` - Predicate called with the values of candidate matches.
     ///
     /// # Panics
     ///
@@ -416,118 +642,127 @@ impl<V> DoubleArrayAhoCorasick<V> {
     /// ```
     /// use daachorse::DoubleArrayAhoCorasick;
     ///
-    /// let patterns = vec!["bcd", "cd", "abc"];
+    /// let patterns = vec!["bcd", "ab", "a"];
     /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
     ///
-    /// let haystack = "ab".as_bytes().iter().chain("cd".as_bytes()).copied();
-    ///
-    /// let mut it = pma.find_overlapping_no_suffix_iter_from_iter(haystack);
-    ///
-    /// let m = it.next().unwrap();
-    /// assert_eq!((0, 3, 2), (m.start(), m.end(), m.value()));
+    /// let mut it = pma.find_iter_filtered("abcd", |v: usize| v != 2);
     ///
     /// let m = it.next().unwrap();
-    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    /// assert_eq!((0, 2, 1), (m.start(), m.end(), m.value()));
     ///
     /// assert_eq!(None, it.next());
     /// ```
-    pub fn find_overlapping_no_suffix_iter_from_iter<P>(
+    pub fn find_iter_filtered<P, F>(
         &self,
         haystack: P,
-    ) -> FindOverlappingNoSuffixIterator<'_, P, V>
+        keep: F,
+    ) -> FindFilteredIterator<'_, U8SliceIterator<P>, V, F>
     where
-        P: Iterator<Item = u8>,
+        P: AsRef<[u8]>,
+        F: FnMut(V) -> bool,
     {
         assert!(
             self.match_kind.is_standard(),
             "Error: match_kind must be standard."
         );
-        FindOverlappingNoSuffixIterator {
+        FindFilteredIterator {
             pma: self,
-            haystack: haystack.enumerate(),
-            state_id: ROOT_STATE_IDX,
+            haystack: U8SliceIterator::new(haystack).enumerate(),
+            keep,
         }
     }
 
-    /// Returns an iterator of leftmost matches in the given haystack.
-    ///
-    /// The leftmost match greedily searches the longest possible match at each iteration, and
-    /// the match results do not overlap positionally such as
-    /// [`DoubleArrayAhoCorasick::find_iter()`].
+    /// Returns a vector of at most `max` non-overlapping matches in the given haystack.
     ///
-    /// According to the [`MatchKind`] option you specified in the construction, the behavior is
-    /// changed for multiple possible matches, as follows.
-    ///
-    ///  - If you set [`MatchKind::LeftmostLongest`], it reports the match
-    ///    corresponding to the longest pattern.
-    ///
-    ///  - If you set [`MatchKind::LeftmostFirst`], it reports the match
-    ///    corresponding to the pattern earlier registered to the automaton.
+    /// The scan stops as soon as `max` matches are collected, so the running time is bounded by
+    /// the position of the `max`-th match rather than the length of the haystack.
     ///
     /// # Arguments
     ///
     /// * `haystack` - String to search for.
+    /// * `max` - The maximum number of matches to collect.
     ///
     /// # Panics
     ///
-    /// If you do not specify [`MatchKind::LeftmostFirst`] or [`MatchKind::LeftmostLongest`] in
-    /// the construction, the iterator is not supported and the function will panic.
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the function will
+    /// panic.
     ///
     /// # Examples
     ///
-    /// ## LeftmostLongest
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
     ///
+    /// let matches = pma.find_at_most("abcdabcd", 3);
+    ///
+    /// assert_eq!(3, matches.len());
+    /// assert_eq!((4, 5, 2), (matches[2].start(), matches[2].end(), matches[2].value()));
     /// ```
-    /// use daachorse::{DoubleArrayAhoCorasickBuilder, MatchKind};
+    #[must_use]
+    pub fn find_at_most<P>(&self, haystack: P, max: usize) -> Vec<Match<V>>
+    where
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        self.find_iter_limited(haystack, max).collect()
+    }
+
+    /// Clears the given buffer and fills it with non-overlapping matches in the given haystack.
     ///
-    /// let patterns = vec!["ab", "a", "abcd"];
-    /// let pma = DoubleArrayAhoCorasickBuilder::new()
-    ///     .match_kind(MatchKind::LeftmostLongest)
-    ///     .build(&patterns)
-    ///     .unwrap();
+    /// The capacity of `out` is reused, so calling this repeatedly with the same buffer avoids
+    /// allocating a vector for each search.
     ///
-    /// let mut it = pma.leftmost_find_iter("abcd");
+    /// # Arguments
     ///
-    /// let m = it.next().unwrap();
-    /// assert_eq!((0, 4, 2), (m.start(), m.end(), m.value()));
+    /// * `haystack` - String to search for.
+    /// * `out` - Buffer to store the matches.
     ///
-    /// assert_eq!(None, it.next());
-    /// ```
+    /// # Panics
     ///
-    /// ## LeftmostFirst
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the function will
+    /// panic.
     ///
-    /// ```
-    /// use daachorse::{DoubleArrayAhoCorasickBuilder, MatchKind};
+    /// # Examples
     ///
-    /// let patterns = vec!["ab", "a", "abcd"];
-    /// let pma = DoubleArrayAhoCorasickBuilder::new()
-    ///     .match_kind(MatchKind::LeftmostFirst)
-    ///     .build(&patterns)
-    ///     .unwrap();
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
     ///
-    /// let mut it = pma.leftmost_find_iter("abcd");
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
     ///
-    /// let m = it.next().unwrap();
-    /// assert_eq!((0, 2, 0), (m.start(), m.end(), m.value()));
+    /// let mut matches = vec![];
+    /// pma.find_all_into("abcd", &mut matches);
+    /// assert_eq!(2, matches.len());
     ///
-    /// assert_eq!(None, it.next());
+    /// pma.find_all_into("bcd", &mut matches);
+    /// assert_eq!(1, matches.len());
+    /// assert_eq!((0, 3, 0), (matches[0].start(), matches[0].end(), matches[0].value()));
     /// ```
-    pub fn leftmost_find_iter<P>(&self, haystack: P) -> LestmostFindIterator<'_, P, V>
+    pub fn find_all_into<P>(&self, haystack: P, out: &mut Vec<Match<V>>)
     where
         P: AsRef<[u8]>,
+        V: Copy,
     {
-        assert!(
-            self.match_kind.is_leftmost(),
-            "Error: match_kind must be leftmost."
-        );
-        LestmostFindIterator {
-            pma: self,
-            haystack,
-            pos: 0,
-        }
+        out.clear();
+        out.extend(self.find_iter(haystack));
     }
 
-    /// Returns the total amount of heap used by this automaton in bytes.
+    /// Clears the given buffer and fills it with overlapping matches in the given haystack.
+    ///
+    /// The capacity of `out` is reused, so calling this repeatedly with the same buffer avoids
+    /// allocating a vector for each search.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    /// * `out` - Buffer to store the matches.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the function will
+    /// panic.
     ///
     /// # Examples
     ///
@@ -535,17 +770,115 @@ impl<V> DoubleArrayAhoCorasick<V> {
     /// use daachorse::DoubleArrayAhoCorasick;
     ///
     /// let patterns = vec!["bcd", "ab", "a"];
-    /// let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
     ///
-    /// assert_eq!(3108, pma.heap_bytes());
+    /// let mut matches = vec![];
+    /// pma.find_overlapping_all_into("abcd", &mut matches);
+    /// assert_eq!(3, matches.len());
+    /// ```
+    pub fn find_overlapping_all_into<P>(&self, haystack: P, out: &mut Vec<Match<V>>)
+    where
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        out.clear();
+        out.extend(self.find_overlapping_iter(haystack));
+    }
+
+    /// Returns a vector of overlapping matches in the given haystack, sorted by their start and
+    /// end positions.
+    ///
+    /// [`DoubleArrayAhoCorasick::find_overlapping_iter()`] reports matches in order of their end
+    /// positions, so this function necessarily buffers all matches before sorting them. Since
+    /// registered patterns are distinct, no two matches have the same span.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the function will
+    /// panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a", "abcd"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let matches = pma.find_overlapping_sorted("abcd");
+    /// let spans: Vec<_> = matches.iter().map(|m| (m.start(), m.end())).collect();
+    /// assert_eq!(vec![(0, 1), (0, 2), (0, 4), (1, 4)], spans);
     /// ```
     #[must_use]
-    pub fn heap_bytes(&self) -> usize {
-        self.states.len() * mem::size_of::<State>()
-            + self.outputs.len() * mem::size_of::<Output<V>>()
+    pub fn find_overlapping_sorted<P>(&self, haystack: P) -> Vec<Match<V>>
+    where
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        let mut matches: Vec<_> = self.find_overlapping_iter(haystack).collect();
+        matches.sort_unstable_by_key(|m| (m.start(), m.end()));
+        matches
     }
 
-    /// Returns the total number of states this automaton has.
+    /// Clears the given buffer and fills it with leftmost matches in the given haystack.
+    ///
+    /// The capacity of `out` is reused, so calling this repeatedly with the same buffer avoids
+    /// allocating a vector for each search.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    /// * `out` - Buffer to store the matches.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::LeftmostFirst`] or [`MatchKind::LeftmostLongest`] in
+    /// the construction, the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasickBuilder, MatchKind};
+    ///
+    /// let patterns = vec!["ab", "a", "abcd"];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .match_kind(MatchKind::LeftmostLongest)
+    ///     .build(&patterns)
+    ///     .unwrap();
+    ///
+    /// let mut matches = vec![];
+    /// pma.leftmost_find_all_into("abcd", &mut matches);
+    /// assert_eq!(1, matches.len());
+    /// assert_eq!((0, 4, 2), (matches[0].start(), matches[0].end(), matches[0].value()));
+    /// ```
+    pub fn leftmost_find_all_into<P>(&self, haystack: P, out: &mut Vec<Match<V>>)
+    where
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        out.clear();
+        out.extend(self.leftmost_find_iter(haystack));
+    }
+
+    /// Returns non-overlapping matches in the given haystack as three parallel vectors of start
+    /// positions, end positions, and values.
+    ///
+    /// The `i`-th elements of the vectors correspond to the `i`-th match reported by
+    /// [`DoubleArrayAhoCorasick::find_iter()`]. This layout is convenient for passing matches
+    /// across a C ABI or into columnar consumers.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the function will
+    /// panic.
     ///
     /// # Examples
     ///
@@ -555,14 +888,34 @@ impl<V> DoubleArrayAhoCorasick<V> {
     /// let patterns = vec!["bcd", "ab", "a"];
     /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
     ///
-    /// assert_eq!(pma.num_states(), 6);
+    /// let (starts, ends, values) = pma.find_all_columns("abcdab");
+    /// assert_eq!(vec![0, 1, 4], starts);
+    /// assert_eq!(vec![1, 4, 5], ends);
+    /// assert_eq!(vec![2, 0, 2], values);
     /// ```
     #[must_use]
-    pub fn num_states(&self) -> usize {
-        usize::from_u32(self.num_states)
+    pub fn find_all_columns<P>(&self, haystack: P) -> (Vec<usize>, Vec<usize>, Vec<V>)
+    where
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        matches_to_columns(self.find_iter(haystack))
     }
 
-    /// Serializes the automaton into a [`Vec`].
+    /// Returns overlapping matches in the given haystack as three parallel vectors of start
+    /// positions, end positions, and values.
+    ///
+    /// The `i`-th elements of the vectors correspond to the `i`-th match reported by
+    /// [`DoubleArrayAhoCorasick::find_overlapping_iter()`].
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the function will
+    /// panic.
     ///
     /// # Examples
     ///
@@ -570,41 +923,78 @@ impl<V> DoubleArrayAhoCorasick<V> {
     /// use daachorse::DoubleArrayAhoCorasick;
     ///
     /// let patterns = vec!["bcd", "ab", "a"];
-    /// let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
-    /// let bytes = pma.serialize();
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let (starts, ends, values) = pma.find_overlapping_all_columns("abcd");
+    /// assert_eq!(vec![0, 0, 1], starts);
+    /// assert_eq!(vec![1, 2, 4], ends);
+    /// assert_eq!(vec![2, 1, 0], values);
     /// ```
     #[must_use]
-    pub fn serialize(&self) -> Vec<u8>
+    pub fn find_overlapping_all_columns<P>(&self, haystack: P) -> (Vec<usize>, Vec<usize>, Vec<V>)
     where
-        V: Serializable,
+        P: AsRef<[u8]>,
+        V: Copy,
     {
-        let mut result = Vec::with_capacity(
-            self.states.serialized_bytes()
-                + self.outputs.serialized_bytes()
-                + MatchKind::serialized_bytes()
-                + u32::serialized_bytes(),
-        );
-        self.states.serialize_to_vec(&mut result);
-        self.outputs.serialize_to_vec(&mut result);
-        self.match_kind.serialize_to_vec(&mut result);
-        self.num_states.serialize_to_vec(&mut result);
-        result
+        matches_to_columns(self.find_overlapping_iter(haystack))
     }
 
-    /// Deserializes the automaton from a given slice.
+    /// Returns leftmost matches in the given haystack as three parallel vectors of start
+    /// positions, end positions, and values.
+    ///
+    /// The `i`-th elements of the vectors correspond to the `i`-th match reported by
+    /// [`DoubleArrayAhoCorasick::leftmost_find_iter()`].
     ///
     /// # Arguments
     ///
-    /// * `source` - A source slice.
+    /// * `haystack` - String to search for.
     ///
-    /// # Returns
+    /// # Panics
     ///
-    /// A tuple of the automaton and the slice not used for the deserialization.
+    /// If you do not specify [`MatchKind::LeftmostFirst`] or [`MatchKind::LeftmostLongest`] in
+    /// the construction, the function will panic.
     ///
-    /// # Safety
+    /// # Examples
     ///
-    /// The given data must be a correct automaton exported by
-    /// [`DoubleArrayAhoCorasick::serialize()`] function.
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasick, DoubleArrayAhoCorasickBuilder, MatchKind};
+    ///
+    /// let patterns = vec!["ab", "a", "abcd"];
+    /// let pma: DoubleArrayAhoCorasick<usize> = DoubleArrayAhoCorasickBuilder::new()
+    ///     .match_kind(MatchKind::LeftmostLongest)
+    ///     .build(&patterns)
+    ///     .unwrap();
+    ///
+    /// let (starts, ends, values) = pma.leftmost_find_all_columns("abcdab");
+    /// assert_eq!(vec![0, 4], starts);
+    /// assert_eq!(vec![4, 6], ends);
+    /// assert_eq!(vec![2, 0], values);
+    /// ```
+    #[must_use]
+    pub fn leftmost_find_all_columns<P>(&self, haystack: P) -> (Vec<usize>, Vec<usize>, Vec<V>)
+    where
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        matches_to_columns(self.leftmost_find_iter(haystack))
+    }
+
+    /// Returns an iterator of non-overlapping matches within the given range of the haystack.
+    ///
+    /// The search starts from the root state at `range.start`, so no context before the window
+    /// is carried over; a pattern beginning before `range.start` is never reported. Likewise,
+    /// only matches ending at or before `range.end` are reported. The positions of the reported
+    /// matches are offsets in the whole haystack, not in the window.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    /// * `range` - Byte range of the haystack to search in.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, or `range` is out of
+    /// the bounds of `haystack`, the function will panic.
     ///
     /// # Examples
     ///
@@ -612,397 +1002,3790 @@ impl<V> DoubleArrayAhoCorasick<V> {
     /// use daachorse::DoubleArrayAhoCorasick;
     ///
     /// let patterns = vec!["bcd", "ab", "a"];
-    /// let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
-    /// let bytes = pma.serialize();
-    ///
-    /// let (pma, _) = unsafe { DoubleArrayAhoCorasick::<u32>::deserialize_unchecked(&bytes) };
-    ///
-    /// let mut it = pma.find_overlapping_iter("abcd");
+    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
     ///
-    /// let m = it.next().unwrap();
-    /// assert_eq!((0, 1, 2), (m.start(), m.end(), m.value()));
+    /// let mut it = pma.find_in_range("abcdabcd", 1..7);
     ///
     /// let m = it.next().unwrap();
-    /// assert_eq!((0, 2, 1), (m.start(), m.end(), m.value()));
+    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
     ///
     /// let m = it.next().unwrap();
-    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    /// assert_eq!((4, 5, 2), (m.start(), m.end(), m.value()));
     ///
     /// assert_eq!(None, it.next());
     /// ```
-    #[must_use]
-    pub unsafe fn deserialize_unchecked(source: &[u8]) -> (Self, &[u8])
+    pub fn find_in_range<'a, P>(
+        &'a self,
+        haystack: P,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = Match<V>> + 'a
     where
-        V: Serializable,
+        P: AsRef<[u8]> + 'a,
+        V: Copy,
     {
-        let (states, source) = Vec::<State>::deserialize_from_slice(source);
-        let (outputs, source) = Vec::<Output<V>>::deserialize_from_slice(source);
-        let (match_kind, source) = MatchKind::deserialize_from_slice(source);
-        let (num_states, source) = u32::deserialize_from_slice(source);
-        (
-            Self {
-                states,
-                outputs,
-                match_kind,
-                num_states,
-            },
-            source,
-        )
+        assert!(
+            range.start <= range.end && range.end <= haystack.as_ref().len(),
+            "Error: range must be within the haystack."
+        );
+        let offset = range.start;
+        let window = U8SliceIterator::new(haystack)
+            .skip(range.start)
+            .take(range.len());
+        self.find_iter_from_iter(window).map(move |m| Match {
+            length: m.length,
+            end: m.end + offset,
+            value: m.value,
+        })
     }
 
-    /// # Safety
+    /// Returns an iterator of overlapping matches in the given haystack, skipping runs of bytes
+    /// that cannot start any pattern.
     ///
-    /// `state_id` must be smaller than the length of states.
-    #[inline(always)]
-    unsafe fn child_index_unchecked(&self, state_id: u32, c: u8) -> Option<u32> {
-        // child_idx is always smaller than states.len() because
-        //  - states.len() is 256 * k for some integer k, and
-        //  - base() returns smaller than states.len() when it is Some.
-        self.states
-            .get_unchecked(usize::from_u32(state_id))
-            .base()
-            .and_then(|base| {
-                let child_idx = base.get() ^ u32::from(c);
-                Some(child_idx)
-                    .filter(|&x| self.states.get_unchecked(usize::from_u32(x)).check() == c)
-            })
-    }
-
-    /// # Safety
+    /// While the automaton stays at the root, every byte without an outgoing transition from the
+    /// root leads back to the root, so such bytes are skipped by a table lookup without any
+    /// traversal. The table is computed with 256 probes when the iterator is created. The
+    /// results are exactly the same as those of
+    /// [`DoubleArrayAhoCorasick::find_overlapping_iter()`], and this iterator is faster when
+    /// matches are sparse in long haystacks.
     ///
-    /// `state_id` must be smaller than the length of states.
-    #[inline(always)]
-    unsafe fn next_state_id_unchecked(&self, mut state_id: u32, c: u8) -> u32 {
-        // In the loop, state_id is always set to values smaller than states.len(),
-        // because child_index_unchecked() and fail() return such values.
-        loop {
-            if let Some(state_id) = self.child_index_unchecked(state_id, c) {
-                return state_id;
-            }
-            if state_id == ROOT_STATE_IDX {
-                return ROOT_STATE_IDX;
-            }
-            state_id = self.states.get_unchecked(usize::from_u32(state_id)).fail();
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_overlapping_iter_fast("xxabcdxx");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((2, 3, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((2, 4, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((3, 6, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_overlapping_iter_fast<P>(
+        &self,
+        haystack: P,
+    ) -> FindOverlappingFastIterator<'_, P, V>
+    where
+        P: AsRef<[u8]>,
+    {
+        assert!(
+            self.match_kind.is_standard(),
+            "Error: match_kind must be standard."
+        );
+        let mut root_labels = [false; 256];
+        for (c, is_label) in (0..=u8::MAX).zip(root_labels.iter_mut()) {
+            // ROOT_STATE_IDX is always smaller than self.states.len() because the root state is
+            // always allocated.
+            *is_label = unsafe { self.child_index_unchecked(ROOT_STATE_IDX, c) }.is_some();
+        }
+        FindOverlappingFastIterator {
+            pma: self,
+            haystack,
+            root_labels,
+            state_id: ROOT_STATE_IDX,
+            pos: 0,
+            output_pos: None,
         }
     }
 
-    /// # Safety
+    /// Returns an iterator of overlapping matches in the given haystack, in which repeated
+    /// reports of the same value are suppressed.
     ///
-    /// `state_id` must be smaller than the length of states.
-    #[inline(always)]
-    unsafe fn next_state_id_leftmost_unchecked(&self, mut state_id: u32, c: u8) -> u32 {
-        // In the loop, state_id is always set to values smaller than states.len(),
-        // because child_index_unchecked() and fail() return such values.
-        loop {
-            if let Some(state_id) = self.child_index_unchecked(state_id, c) {
-                return state_id;
-            }
-            if state_id == ROOT_STATE_IDX {
-                return ROOT_STATE_IDX;
-            }
-            let fail_id = self.states.get_unchecked(usize::from_u32(state_id)).fail();
+    /// A match is skipped if a match with the same value has already been reported and their
+    /// starting positions differ by less than `window` bytes. Reported matches are remembered
+    /// only until the search proceeds `window + L` bytes past their starting positions, where `L`
+    /// is the length of the longest pattern, so the memory usage is bounded by the number of
+    /// matches reported in the last `window + L` bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    /// * `window` - Distance in bytes within which matches of the same value are suppressed.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["a", "aa", "aaa"];
+    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_overlapping_dedup_iter("aaaa", 2);
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 1, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 2, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 3, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((2, 3, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((2, 4, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_overlapping_dedup_iter<P>(
+        &self,
+        haystack: P,
+        window: usize,
+    ) -> FindOverlappingDedupIterator<'_, U8SliceIterator<P>, V>
+    where
+        P: AsRef<[u8]>,
+    {
+        let max_len = self
+            .outputs
+            .iter()
+            .map(|out| usize::from_u32(out.length))
+            .max()
+            .unwrap_or(0);
+        FindOverlappingDedupIterator {
+            inner: self.find_overlapping_iter(haystack),
+            window,
+            max_len,
+            reported: BTreeSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Returns an iterator of overlapping matches in the given haystack iterator.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - [`u8`] iterator to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
+    ///
+    /// let haystack = "ab".as_bytes().iter().chain("cd".as_bytes()).copied();
+    ///
+    /// let mut it = pma.find_overlapping_iter_from_iter(haystack);
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 1, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 2, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_overlapping_iter_from_iter<P>(
+        &self,
+        haystack: P,
+    ) -> FindOverlappingIterator<'_, P, V>
+    where
+        P: Iterator<Item = u8>,
+    {
+        assert!(
+            self.match_kind.is_standard(),
+            "Error: match_kind must be standard."
+        );
+        FindOverlappingIterator {
+            pma: self,
+            haystack: haystack.enumerate(),
+            state_id: ROOT_STATE_IDX,
+            output_pos: None,
+            pos: 0,
+        }
+    }
+
+    /// Returns an iterator of overlapping matches without suffixes in the given haystack iterator.
+    ///
+    /// The Aho-Corasick algorithm reads through the haystack from left to right and reports
+    /// matches when it reaches the end of each pattern. In the overlapping match, more than one
+    /// pattern can be returned per report.
+    ///
+    /// This iterator returns the first match on each report, which corresponds to the longest
+    /// pattern. Use [`Self::find_overlapping_no_suffix_iter_with_policy()`] to select another one.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "cd", "abc"];
+    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_overlapping_no_suffix_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 3, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_overlapping_no_suffix_iter<P>(
+        &self,
+        haystack: P,
+    ) -> FindOverlappingNoSuffixIterator<'_, U8SliceIterator<P>, V>
+    where
+        P: AsRef<[u8]>,
+    {
+        assert!(
+            self.match_kind.is_standard(),
+            "Error: match_kind must be standard."
+        );
+        FindOverlappingNoSuffixIterator {
+            pma: self,
+            haystack: U8SliceIterator::new(haystack).enumerate(),
+            state_id: ROOT_STATE_IDX,
+            pos: 0,
+            select: iter::OutputSelector::Longest,
+        }
+    }
+
+    /// Returns an iterator of overlapping matches without suffixes in the given haystack,
+    /// choosing the reported match at each end position according to the given policy.
+    ///
+    /// This is the same as [`Self::find_overlapping_no_suffix_iter()`] except that, when
+    /// several patterns end at the same position, the reported one is selected by `policy`
+    /// rather than always being the longest.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    /// * `policy` - Policy for choosing the match reported at each end position.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasick, SuffixPolicy};
+    ///
+    /// let patterns = vec!["bc", "abc", "c"];
+    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_overlapping_no_suffix_iter_with_policy("abc", SuffixPolicy::Longest);
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 3, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// let mut it = pma.find_overlapping_no_suffix_iter_with_policy("abc", SuffixPolicy::Shortest);
+    /// let m = it.next().unwrap();
+    /// assert_eq!((2, 3, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// let mut it =
+    ///     pma.find_overlapping_no_suffix_iter_with_policy("abc", SuffixPolicy::SmallestValue);
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 3, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_overlapping_no_suffix_iter_with_policy<P>(
+        &self,
+        haystack: P,
+        policy: SuffixPolicy,
+    ) -> FindOverlappingNoSuffixIterator<'_, U8SliceIterator<P>, V>
+    where
+        P: AsRef<[u8]>,
+        V: Copy + Ord,
+    {
+        assert!(
+            self.match_kind.is_standard(),
+            "Error: match_kind must be standard."
+        );
+        let select = match policy {
+            SuffixPolicy::Longest => iter::OutputSelector::Longest,
+            SuffixPolicy::Shortest => iter::OutputSelector::Shortest,
+            SuffixPolicy::SmallestValue => {
+                iter::OutputSelector::SmallestValue(iter::select_smallest_value)
+            }
+        };
+        FindOverlappingNoSuffixIterator {
+            pma: self,
+            haystack: U8SliceIterator::new(haystack).enumerate(),
+            state_id: ROOT_STATE_IDX,
+            pos: 0,
+            select,
+        }
+    }
+
+    /// Returns an iterator of overlapping matches without suffixes in the given haystack iterator.
+    ///
+    /// The Aho-Corasick algorithm reads through the haystack from left to right and reports
+    /// matches when it reaches the end of each pattern. In the overlapping match, more than one
+    /// pattern can be returned per report.
+    ///
+    /// This iterator returns the first match on each report.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - [`u8`] to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "cd", "abc"];
+    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
+    ///
+    /// let haystack = "ab".as_bytes().iter().chain("cd".as_bytes()).copied();
+    ///
+    /// let mut it = pma.find_overlapping_no_suffix_iter_from_iter(haystack);
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 3, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_overlapping_no_suffix_iter_from_iter<P>(
+        &self,
+        haystack: P,
+    ) -> FindOverlappingNoSuffixIterator<'_, P, V>
+    where
+        P: Iterator<Item = u8>,
+    {
+        assert!(
+            self.match_kind.is_standard(),
+            "Error: match_kind must be standard."
+        );
+        FindOverlappingNoSuffixIterator {
+            pma: self,
+            haystack: haystack.enumerate(),
+            state_id: ROOT_STATE_IDX,
+            pos: 0,
+            select: iter::OutputSelector::Longest,
+        }
+    }
+
+    /// Calls the closure on each non-overlapping match in the given haystack.
+    ///
+    /// This function reports the same matches as [`DoubleArrayAhoCorasick::find_iter()`] in a
+    /// single loop without the overhead of the iterator. The scan stops immediately if the closure
+    /// returns [`ControlFlow::Break`].
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    /// * `f` - Closure called on each match.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the function will
+    /// panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::ops::ControlFlow;
+    ///
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
+    ///
+    /// let mut matches = vec![];
+    /// pma.for_each_match("abcdabcd", |m| {
+    ///     matches.push((m.start(), m.end(), m.value()));
+    ///     if matches.len() == 3 {
+    ///         ControlFlow::Break(())
+    ///     } else {
+    ///         ControlFlow::Continue(())
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(vec![(0, 1, 2), (1, 4, 0), (4, 5, 2)], matches);
+    /// ```
+    pub fn for_each_match<P, F>(&self, haystack: P, mut f: F)
+    where
+        P: AsRef<[u8]>,
+        F: FnMut(Match<V>) -> ControlFlow<()>,
+        V: Copy,
+    {
+        assert!(
+            self.match_kind.is_standard(),
+            "Error: match_kind must be standard."
+        );
+        let mut state_id = ROOT_STATE_IDX;
+        for (pos, &c) in haystack.as_ref().iter().enumerate() {
+            // state_id is always smaller than self.states.len() because
+            // self.next_state_id_unchecked() ensures to return such a value.
+            state_id = unsafe { self.next_state_id_unchecked(state_id, c) };
+            if let Some(output_pos) = unsafe {
+                self.states
+                    .get_unchecked(usize::from_u32(state_id))
+                    .output_pos()
+            } {
+                // output_pos is always smaller than self.outputs.len() because
+                // State::output_pos() ensures to return such a value when it is Some.
+                let out = unsafe {
+                    self.outputs
+                        .get_unchecked(usize::from_u32(output_pos.get() - 1))
+                };
+                let m = Match {
+                    length: usize::from_u32(out.length()),
+                    end: pos + 1,
+                    value: out.value(),
+                };
+                if f(m).is_break() {
+                    return;
+                }
+                state_id = ROOT_STATE_IDX;
+            }
+        }
+    }
+
+    /// Pushes each non-overlapping match in the given haystack to the sink.
+    ///
+    /// This function reports the same matches as [`DoubleArrayAhoCorasick::find_iter()`]. The
+    /// scan stops immediately if [`MatchSink::push()`] returns [`ControlFlow::Break`].
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    /// * `sink` - Sink receiving the matches.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the function will
+    /// panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let mut matches = vec![];
+    /// pma.drive("abcd", &mut matches);
+    ///
+    /// let spans: Vec<_> = matches.iter().map(|m| (m.start(), m.end(), m.value())).collect();
+    /// assert_eq!(vec![(0, 1, 2), (1, 4, 0)], spans);
+    /// ```
+    pub fn drive<P, K>(&self, haystack: P, sink: &mut K)
+    where
+        P: AsRef<[u8]>,
+        K: MatchSink<V> + ?Sized,
+        V: Copy,
+    {
+        self.for_each_match(haystack, |m| sink.push(m));
+    }
+
+    /// Calls the closure on each overlapping match in the given haystack.
+    ///
+    /// This function reports the same matches as
+    /// [`DoubleArrayAhoCorasick::find_overlapping_iter()`] in a single loop without the overhead
+    /// of the iterator. The scan stops immediately if the closure returns [`ControlFlow::Break`].
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    /// * `f` - Closure called on each match.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the function will
+    /// panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::ops::ControlFlow;
+    ///
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
+    ///
+    /// let mut matches = vec![];
+    /// pma.for_each_overlapping_match("abcd", |m| {
+    ///     matches.push((m.start(), m.end(), m.value()));
+    ///     ControlFlow::Continue(())
+    /// });
+    ///
+    /// assert_eq!(vec![(0, 1, 2), (0, 2, 1), (1, 4, 0)], matches);
+    /// ```
+    pub fn for_each_overlapping_match<P, F>(&self, haystack: P, mut f: F)
+    where
+        P: AsRef<[u8]>,
+        F: FnMut(Match<V>) -> ControlFlow<()>,
+        V: Copy,
+    {
+        assert!(
+            self.match_kind.is_standard(),
+            "Error: match_kind must be standard."
+        );
+        let mut state_id = ROOT_STATE_IDX;
+        for (pos, &c) in haystack.as_ref().iter().enumerate() {
+            // state_id is always smaller than self.states.len() because
+            // self.next_state_id_unchecked() ensures to return such a value.
+            state_id = unsafe { self.next_state_id_unchecked(state_id, c) };
+            let mut output_pos = unsafe {
+                self.states
+                    .get_unchecked(usize::from_u32(state_id))
+                    .output_pos()
+            };
+            while let Some(pos_in_outputs) = output_pos {
+                // pos_in_outputs is always smaller than self.outputs.len() because
+                // State::output_pos() and Output::parent() ensure to return such a value when
+                // it is Some.
+                let out = unsafe {
+                    self.outputs
+                        .get_unchecked(usize::from_u32(pos_in_outputs.get() - 1))
+                };
+                let m = Match {
+                    length: usize::from_u32(out.length()),
+                    end: pos + 1,
+                    value: out.value(),
+                };
+                if f(m).is_break() {
+                    return;
+                }
+                output_pos = out.parent();
+            }
+        }
+    }
+
+    /// Returns the number of bytes in the haystack covered by at least one overlapping match.
+    ///
+    /// The matches are swept from left to right while merging overlapping or adjacent ranges,
+    /// so each byte is counted only once.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the function will
+    /// panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a", "f"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// // "abcd" and "f" are covered.
+    /// assert_eq!(5, pma.coverage("abcdef"));
+    /// ```
+    #[must_use]
+    pub fn coverage<P>(&self, haystack: P) -> usize
+    where
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        // Each report of the no-suffix iterator is the longest match ending at the position,
+        // and the other matches in the report are contained in it. The ending positions are
+        // strictly increasing, so a new range can be merged with only the last merged ranges.
+        let mut merged: Vec<(usize, usize)> = vec![];
+        let mut covered = 0;
+        for m in self.find_overlapping_no_suffix_iter(haystack) {
+            let mut start = m.start();
+            while let Some(&(last_start, last_end)) = merged.last() {
+                if last_end < start {
+                    break;
+                }
+                merged.pop();
+                covered -= last_end - last_start;
+                start = start.min(last_start);
+            }
+            covered += m.end() - start;
+            merged.push((start, m.end()));
+        }
+        covered
+    }
+
+    /// Returns `true` if any pattern occurs in the given haystack.
+    ///
+    /// The result does not depend on the match kind. If only one pattern is registered, the
+    /// haystack is scanned by the two-way substring search algorithm instead of the automaton,
+    /// which is faster especially for a long pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// assert!(pma.is_match("xxbcdxx"));
+    /// assert!(!pma.is_match("xxbcxx"));
+    ///
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(["needle"]).unwrap();
+    ///
+    /// assert!(pma.is_match("haystack with a needle"));
+    /// assert!(!pma.is_match("haystack with a needl"));
+    /// ```
+    #[must_use]
+    pub fn is_match<P>(&self, haystack: P) -> bool
+    where
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        let haystack = haystack.as_ref();
+        if let Some(single_pattern) = &self.single_pattern {
+            return single_pattern.find(haystack).is_some();
+        }
+        if self.match_kind.is_standard() {
+            self.find_iter(haystack).next().is_some()
+        } else {
+            self.leftmost_find_iter(haystack).next().is_some()
+        }
+    }
+
+    /// Returns the value associated with the given pattern if it is registered.
+    ///
+    /// This function traverses the trie from the root state along the bytes of `pattern` without
+    /// following fail links, and reports the value only if a pattern of exactly the same bytes is
+    /// registered. Prefixes of registered patterns are not reported.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - Pattern to look up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patvals = vec![("bcd", 0), ("ab", 10), ("a", 20)];
+    /// let pma = DoubleArrayAhoCorasick::with_values(patvals).unwrap();
+    ///
+    /// assert_eq!(Some(10), pma.contains_pattern("ab"));
+    /// assert_eq!(Some(0), pma.contains_pattern("bcd"));
+    /// assert_eq!(None, pma.contains_pattern("bc"));
+    /// assert_eq!(None, pma.contains_pattern("abcd"));
+    /// ```
+    pub fn contains_pattern<P>(&self, pattern: P) -> Option<V>
+    where
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        let pattern = pattern.as_ref();
+        let mut state_id = ROOT_STATE_IDX;
+        for &c in pattern {
+            // state_id is always smaller than self.states.len() because
+            // self.child_index_unchecked() ensures to return such a value.
+            state_id = unsafe { self.child_index_unchecked(state_id, c) }?;
+        }
+        let output_pos = self.states[usize::from_u32(state_id)].output_pos()?;
+        let out = self.outputs[usize::from_u32(output_pos.get() - 1)];
+        // If the state has no output of its own, output_pos points to an output of a suffix,
+        // whose length is shorter than the pattern.
+        (usize::from_u32(out.length()) == pattern.len()).then(|| out.value())
+    }
+
+    /// Returns the IDs of the states visited by traversing goto edges from the root with the
+    /// given byte string.
+    ///
+    /// The resulting vector starts with the ID of the root, and its `i`-th element is the state
+    /// reached after consuming the first `i` bytes, so its length is `pattern.len() + 1`. `None`
+    /// is returned if `pattern` is not a path in the trie, i.e., not a prefix of any registered
+    /// pattern. The IDs are stable for an automaton, including after serialization.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - Byte string to traverse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let path = pma.state_path("bc").unwrap();
+    /// assert_eq!(3, path.len());
+    /// assert_eq!(path, pma.state_path("bcd").unwrap()[..3]);
+    ///
+    /// assert_eq!(None, pma.state_path("ac"));
+    /// ```
+    #[must_use]
+    pub fn state_path<P>(&self, pattern: P) -> Option<Vec<u32>>
+    where
+        P: AsRef<[u8]>,
+    {
+        let pattern = pattern.as_ref();
+        let mut path = Vec::with_capacity(pattern.len() + 1);
+        let mut state_id = ROOT_STATE_IDX;
+        path.push(state_id);
+        for &c in pattern {
+            state_id = self.child_index(state_id, c)?;
+            path.push(state_id);
+        }
+        Some(path)
+    }
+
+    /// Returns the goto edges of the trie as `(parent, byte, child)` triples of state IDs.
+    ///
+    /// The trie is reconstructed from the double array by enumerating the valid transitions of
+    /// each state, excluding fail links. The edges are listed in the breadth-first order from
+    /// the root, and the edges of each state are sorted by their bytes. The state IDs are the
+    /// same as ones returned by [`DoubleArrayAhoCorasick::state_path()`]. If the automaton is
+    /// built with
+    /// [`DoubleArrayAhoCorasickBuilder::minimize_suffixes()`](crate::DoubleArrayAhoCorasickBuilder::minimize_suffixes),
+    /// a state can have several parents, and the edges to it are listed for each of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let edges = pma.trie_edges();
+    /// assert_eq!(pma.num_states() - 1, edges.len());
+    ///
+    /// let labels: Vec<_> = edges.iter().map(|&(_, c, _)| c).collect();
+    /// assert_eq!(b"abbcd".to_vec(), labels);
+    ///
+    /// let path = pma.state_path("ab").unwrap();
+    /// assert!(edges.contains(&(path[1], b'b', path[2])));
+    /// ```
+    #[must_use]
+    pub fn trie_edges(&self) -> Vec<(u32, u8, u32)> {
+        let mut edges = vec![];
+        let mut visited = vec![false; self.states.len()];
+        visited[usize::from_u32(ROOT_STATE_IDX)] = true;
+        let mut q = vec![ROOT_STATE_IDX];
+        let mut qi = 0;
+        while let Some(&state_id) = q.get(qi) {
+            qi += 1;
+            self.for_each_child(state_id, |c, child_id| {
+                edges.push((state_id, c, child_id));
+                if !visited[usize::from_u32(child_id)] {
+                    visited[usize::from_u32(child_id)] = true;
+                    q.push(child_id);
+                }
+            });
+        }
+        edges
+    }
+
+    /// Calls the closure on each goto edge from the given state in ascending order of bytes.
+    ///
+    /// # Panics
+    ///
+    /// `state_id` must be smaller than the length of states.
+    fn for_each_child<F>(&self, state_id: u32, mut f: F)
+    where
+        F: FnMut(u8, u32),
+    {
+        assert!(usize::from_u32(state_id) < self.states.len());
+        for c in u8::MIN..=u8::MAX {
+            // state_id is always smaller than self.states.len() because of the above assertion.
+            if let Some(child_id) = unsafe { self.child_index_unchecked(state_id, c) } {
+                f(c, child_id);
+            }
+        }
+    }
+
+    /// Returns an iterator of matches starting at the beginning of the given haystack, i.e.,
+    /// registered patterns that are prefixes of the haystack, in ascending order of length.
+    ///
+    /// The search walks the trie from the root and never follows fail links, so it stops as soon
+    /// as the haystack leaves the trie. To search at another position `i`, pass `&haystack[i..]`.
+    /// It can be used with any [`MatchKind`], but patterns discarded in the construction with
+    /// [`MatchKind::LeftmostFirst`] are not reported.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasickBuilder;
+    ///
+    /// let patterns = vec!["a", "ab", "abcd", "bc"];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .prefix_only(true)
+    ///     .build(&patterns)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_anchored("abcx");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 1, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 2, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_anchored<P>(&self, haystack: P) -> FindAnchoredIterator<'_, U8SliceIterator<P>, V>
+    where
+        P: AsRef<[u8]>,
+    {
+        FindAnchoredIterator {
+            pma: self,
+            haystack: U8SliceIterator::new(haystack).enumerate(),
+            state_id: Some(ROOT_STATE_IDX),
+        }
+    }
+
+    /// Returns the longest registered pattern that is a prefix of the given haystack.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasickBuilder;
+    ///
+    /// let patterns = vec!["a", "ab", "abcd", "bc"];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .prefix_only(true)
+    ///     .build(&patterns)
+    ///     .unwrap();
+    ///
+    /// let m = pma.longest_prefix_match("abcdef").unwrap();
+    /// assert_eq!((0, 4, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, pma.longest_prefix_match("cd"));
+    /// ```
+    #[must_use]
+    pub fn longest_prefix_match<P>(&self, haystack: P) -> Option<Match<V>>
+    where
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        self.find_anchored(haystack).last()
+    }
+
+    /// Returns the distribution of the lengths of registered patterns.
+    ///
+    /// The resulting map associates each pattern length in bytes with the number of patterns of
+    /// that length. Patterns that are never reported, such as ones discarded in the construction
+    /// with [`MatchKind::LeftmostFirst`], are not counted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a", "cd"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let histogram = pma.pattern_length_histogram();
+    /// assert_eq!(vec![(1, 1), (2, 2), (3, 1)], histogram.into_iter().collect::<Vec<_>>());
+    /// ```
+    #[must_use]
+    pub fn pattern_length_histogram(&self) -> BTreeMap<usize, usize>
+    where
+        V: Copy,
+    {
+        // Each element of outputs corresponds to a distinct pattern.
+        let mut histogram = BTreeMap::new();
+        for out in &self.outputs {
+            *histogram.entry(usize::from_u32(out.length())).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// Returns the set of distinct values that can be reported by this automaton.
+    ///
+    /// The set is collected from the outputs without scanning any haystack. As with
+    /// [`DoubleArrayAhoCorasick::pattern_length_histogram()`], values of patterns that are never
+    /// reported, such as ones discarded in the construction with [`MatchKind::LeftmostFirst`], are
+    /// not included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patvals = vec![("bcd", 0), ("ab", 10), ("a", 20), ("cd", 10)];
+    /// let pma = DoubleArrayAhoCorasick::with_values(patvals).unwrap();
+    ///
+    /// let values = pma.distinct_values();
+    /// assert_eq!(vec![0, 10, 20], values.into_iter().collect::<Vec<_>>());
+    /// ```
+    #[must_use]
+    pub fn distinct_values(&self) -> BTreeSet<V>
+    where
+        V: Copy + Ord,
+    {
+        self.outputs.iter().map(|out| out.value()).collect()
+    }
+
+    /// Creates a new automaton matching the union of the patterns of `self` and `other`.
+    ///
+    /// Since a double array cannot be merged in place, the patterns are recovered from the tries
+    /// of both automata and a new automaton is built from them. The values of `other` are shifted
+    /// by `value_offset` to keep them distinct from the values of `self`. Both automata must be
+    /// built with the same [`MatchKind`] and options of [`DoubleArrayAhoCorasickBuilder`], which
+    /// the resulting automaton is also built with. With [`MatchKind::LeftmostFirst`], patterns
+    /// of `self` take precedence over patterns of `other`.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Automaton to be merged.
+    /// * `value_offset` - Offset added to the values of `other`.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned when
+    ///   - the automata are built with different match kinds or options,
+    ///   - both automata contain the same pattern,
+    ///   - a shifted value of `other` cannot be represented by `V`, or
+    ///   - the scale of the resulting automaton exceeds the expected one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let pma1 = DoubleArrayAhoCorasick::<u32>::new(vec!["bcd", "ab"]).unwrap();
+    /// let pma2 = DoubleArrayAhoCorasick::<u32>::new(vec!["a", "cde"]).unwrap();
+    /// let pma = pma1.merge(&pma2, 10).unwrap();
+    ///
+    /// let mut it = pma.find_overlapping_iter("abcde");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 1, 10), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 2, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((2, 5, 11), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn merge(&self, other: &Self, value_offset: usize) -> Result<Self>
+    where
+        V: Copy + Ord + TryFrom<usize> + TryInto<usize>,
+    {
+        if self.match_kind != other.match_kind {
+            return Err(DaachorseError::invalid_argument(
+                "other.match_kind()",
+                "==",
+                u32::from(u8::from(self.match_kind)),
+            ));
+        }
+        let compress_fail = !self.fail_ids.is_empty();
+        for (arg, x, y) in [
+            (
+                "other.compress_fail",
+                compress_fail,
+                !other.fail_ids.is_empty(),
+            ),
+            ("other.prefix_only", self.prefix_only, other.prefix_only),
+            (
+                "other.minimize_suffixes",
+                self.minimize_suffixes,
+                other.minimize_suffixes,
+            ),
+        ] {
+            if x != y {
+                return Err(DaachorseError::invalid_argument(arg, "==", u32::from(x)));
+            }
+        }
+        // Skip bytes are already removed from the patterns recovered from the tries.
+        if let Some(&c) = self
+            .skip_bytes
+            .iter()
+            .find(|c| other.skip_bytes.binary_search(c).is_err())
+            .or_else(|| {
+                other
+                    .skip_bytes
+                    .iter()
+                    .find(|c| self.skip_bytes.binary_search(c).is_err())
+            })
+        {
+            return Err(DaachorseError::invalid_argument(
+                "byte skipped by only one of the automata",
+                "!=",
+                u32::from(c),
+            ));
+        }
+
+        let mut patvals = self.pattern_values();
+        for (pattern, value) in other.pattern_values() {
+            let value = value
+                .try_into()
+                .ok()
+                .and_then(|value: usize| value.checked_add(value_offset))
+                .and_then(|value| V::try_from(value).ok())
+                .ok_or_else(|| DaachorseError::invalid_conversion("value + value_offset", "V"))?;
+            patvals.push((pattern, value));
+        }
+        DoubleArrayAhoCorasickBuilder::new()
+            .match_kind(self.match_kind)
+            .compress_fail(compress_fail)
+            .prefix_only(self.prefix_only)
+            .minimize_suffixes(self.minimize_suffixes)
+            .skip_bytes(&self.skip_bytes)
+            .build_with_values_and_stats(patvals)
+            .map(|(pma, _)| pma)
+    }
+
+    /// Recovers the pattern-value pairs registered in the trie.
+    ///
+    /// The pairs are sorted in descending order of pattern length, which is an insertion order
+    /// reproducing the same automaton even with [`MatchKind::LeftmostFirst`], under which a
+    /// pattern survives only if it is inserted before its prefixes.
+    fn pattern_values(&self) -> Vec<(Vec<u8>, V)>
+    where
+        V: Copy,
+    {
+        let mut patvals = vec![];
+        let mut stack = vec![(ROOT_STATE_IDX, vec![])];
+        while let Some((state_id, pattern)) = stack.pop() {
+            if let Some(output_pos) = self.states[usize::from_u32(state_id)].output_pos() {
+                let out = self.outputs[usize::from_u32(output_pos.get() - 1)];
+                // The output may be of a suffix if the state has no output of its own.
+                if usize::from_u32(out.length()) == pattern.len() {
+                    patvals.push((pattern.clone(), out.value()));
+                }
+            }
+            self.for_each_child(state_id, |c, child_id| {
+                let mut child_pattern = pattern.clone();
+                child_pattern.push(c);
+                stack.push((child_id, child_pattern));
+            });
+        }
+        patvals.sort_by_key(|(pattern, _)| core::cmp::Reverse(pattern.len()));
+        patvals
+    }
+
+    /// Returns the sizes in bytes of the state array and the output array, which form the
+    /// working set of the search.
+    ///
+    /// The search reads the state array at every byte of the haystack and the output array at
+    /// every match. The other arrays are small or only used by specific searches.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+    ///
+    /// assert_eq!((3072, 36), pma.memory_layout());
+    /// ```
+    #[must_use]
+    pub fn memory_layout(&self) -> (usize, usize) {
+        (
+            self.states.len() * mem::size_of::<State>(),
+            self.outputs.len() * mem::size_of::<Output<V>>(),
+        )
+    }
+
+    /// Reads through the state array and the output array once.
+    ///
+    /// This faults in the pages of the arrays and loads them into caches, e.g., right after
+    /// construction or deserialization, so that the first searches do not pay for it.
+    /// The reads are volatile and are never optimized away.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+    ///
+    /// pma.prefault();
+    /// assert!(pma.is_match("abcd"));
+    /// ```
+    pub fn prefault(&self) {
+        utils::touch_slice(&self.states);
+        utils::touch_slice(&self.outputs);
+    }
+
+    /// Returns `true` if the automaton supports the searches requiring
+    /// [`MatchKind::Standard`], such as [`DoubleArrayAhoCorasick::find_iter()`] and
+    /// [`DoubleArrayAhoCorasick::find_overlapping_iter()`], which panic otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasickBuilder, MatchKind};
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .match_kind(MatchKind::LeftmostFirst)
+    ///     .build::<_, _, usize>(patterns)
+    ///     .unwrap();
+    ///
+    /// assert!(!pma.supports_overlapping());
+    /// assert!(pma.supports_leftmost());
+    /// ```
+    #[inline(always)]
+    #[must_use]
+    pub fn supports_overlapping(&self) -> bool {
+        self.match_kind.is_standard()
+    }
+
+    /// Returns `true` if the automaton supports the searches requiring
+    /// [`MatchKind::LeftmostFirst`] or [`MatchKind::LeftmostLongest`], such as
+    /// [`DoubleArrayAhoCorasick::leftmost_find_iter()`], which panic otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// assert!(pma.supports_overlapping());
+    /// assert!(!pma.supports_leftmost());
+    /// ```
+    #[inline(always)]
+    #[must_use]
+    pub fn supports_leftmost(&self) -> bool {
+        self.match_kind.is_leftmost()
+    }
+
+    /// Checks the internal invariants of the automaton.
+    ///
+    /// The search functions rely on the invariants to skip bounds checks. This function is useful
+    /// to validate an automaton obtained by [`DoubleArrayAhoCorasick::deserialize_unchecked()`]
+    /// from untrusted data before searching with it. It takes time linear in the size of the
+    /// automaton.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned when
+    ///   - a BASE value, a fail link, or an output position is out of bounds,
+    ///   - the trie is not well-formed,
+    ///   - a fail link does not point to a shallower state,
+    ///   - a chain of outputs does not terminate, or
+    ///   - the number of states is inconsistent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+    /// let bytes = pma.serialize();
+    ///
+    /// let (pma, _) = unsafe { DoubleArrayAhoCorasick::<u32>::deserialize_unchecked(&bytes) };
+    ///
+    /// assert!(pma.verify().is_ok());
+    /// ```
+    pub fn verify(&self) -> Result<()>
+    where
+        V: Copy,
+    {
+        let num_elements = self.states.len();
+        if num_elements == 0 || num_elements % usize::from_u32(BLOCK_LEN) != 0 {
+            return Err(DaachorseError::invalid_automaton(
+                "states.len() must be a positive multiple of the block length",
+            ));
+        }
+        if u32::try_from(num_elements).is_err() {
+            return Err(DaachorseError::invalid_automaton(
+                "states.len() must be <= u32::MAX",
+            ));
+        }
+
+        // Since states.len() is a multiple of the block length, base ^ c is always in bounds if
+        // base is in bounds.
+        for state in &self.states {
+            if state
+                .base()
+                .map_or(false, |base| usize::from_u32(base.get()) >= num_elements)
+            {
+                return Err(DaachorseError::invalid_automaton("base is out of bounds"));
+            }
+            if usize::from_u32(state.fail()) >= num_elements {
+                return Err(DaachorseError::invalid_automaton("fail is out of bounds"));
+            }
+            if state
+                .output_pos()
+                .map_or(false, |pos| usize::from_u32(pos.get()) > self.outputs.len())
+            {
+                return Err(DaachorseError::invalid_automaton(
+                    "output_pos is out of bounds",
+                ));
+            }
+        }
+
+        // Each output must be followed by a shorter suffix stored at a smaller position, so that
+        // the chain always terminates.
+        for (i, out) in self.outputs.iter().enumerate() {
+            if out.length() == 0 {
+                return Err(DaachorseError::invalid_automaton(
+                    "length of an output must be >= 1",
+                ));
+            }
+            if let Some(parent) = out.parent() {
+                let parent_idx = usize::from_u32(parent.get() - 1);
+                if parent_idx >= i || self.outputs[parent_idx].length() >= out.length() {
+                    return Err(DaachorseError::invalid_automaton(
+                        "chain of outputs must terminate",
+                    ));
+                }
+            }
+        }
+
+        let root = &self.states[usize::from_u32(ROOT_STATE_IDX)];
+        if root.fail() != ROOT_STATE_IDX || root.output_pos().is_some() {
+            return Err(DaachorseError::invalid_automaton(
+                "root state is not well-formed",
+            ));
+        }
+        let dead = &self.states[usize::from_u32(DEAD_STATE_IDX)];
+        if dead.base().is_some() || dead.fail() != ROOT_STATE_IDX || dead.output_pos().is_some() {
+            return Err(DaachorseError::invalid_automaton(
+                "dead state is not well-formed",
+            ));
+        }
+
+        // Visits states in the breadth-first order to compute their depths.
+        let mut depths = vec![None; num_elements];
+        depths[usize::from_u32(ROOT_STATE_IDX)] = Some(0);
+        depths[usize::from_u32(DEAD_STATE_IDX)] = Some(0);
+        let mut q = vec![ROOT_STATE_IDX];
+        let mut qi = 0;
+        while qi < q.len() {
+            let state_id = q[qi];
+            qi += 1;
+            let depth = depths[usize::from_u32(state_id)].unwrap();
+            if let Some(base) = self.states[usize::from_u32(state_id)].base() {
+                for c in u8::MIN..=u8::MAX {
+                    let child_id = base.get() ^ u32::from(c);
+                    if self.states[usize::from_u32(child_id)].check() != c {
+                        continue;
+                    }
+                    // A state can be shared by several parents of the same depth if the
+                    // automaton is built with minimize_suffixes().
+                    match depths[usize::from_u32(child_id)] {
+                        Some(child_depth) if child_depth == depth + 1 => continue,
+                        Some(_) => {
+                            return Err(DaachorseError::invalid_automaton(
+                                "trie must not have cyclic transitions",
+                            ));
+                        }
+                        None => {}
+                    }
+                    depths[usize::from_u32(child_id)] = Some(depth + 1);
+                    q.push(child_id);
+                }
+            }
+        }
+        if q.len() != usize::from_u32(self.num_states) {
+            return Err(DaachorseError::invalid_automaton(
+                "num_states is inconsistent with the trie",
+            ));
+        }
+
+        for &state_id in &q[1..] {
+            let state = &self.states[usize::from_u32(state_id)];
+            let depth = depths[usize::from_u32(state_id)].unwrap();
+            match depths[usize::from_u32(state.fail())] {
+                Some(fail_depth) if fail_depth < depth => {}
+                _ => {
+                    return Err(DaachorseError::invalid_automaton(
+                        "fail must point to a shallower state",
+                    ));
+                }
+            }
+            if let Some(pos) = state.output_pos() {
+                let out = self.outputs[usize::from_u32(pos.get() - 1)];
+                if usize::from_u32(out.length()) > depth {
+                    return Err(DaachorseError::invalid_automaton(
+                        "length of an output must be <= depth of the state",
+                    ));
+                }
+            }
+        }
+
+        if self.fail_table.len() != self.fail_ids.len() * 256
+            || self.fail_ids.len() > usize::from(u16::MAX)
+            || self.fail_ids.windows(2).any(|w| w[0] >= w[1])
+            || self
+                .fail_ids
+                .iter()
+                .chain(&self.fail_table)
+                .any(|&x| depths.get(usize::from_u32(x)).map_or(true, Option::is_none))
+        {
+            return Err(DaachorseError::invalid_automaton(
+                "compressed transitions are not well-formed",
+            ));
+        }
+
+        if self.skip_bytes.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(DaachorseError::invalid_automaton(
+                "skip bytes must be strictly increasing",
+            ));
+        }
+
+        if self.single_pattern.as_ref().map_or(false, |s| {
+            self.outputs.len() != 1 || usize::from_u32(self.outputs[0].length()) != s.needle().len()
+        }) {
+            return Err(DaachorseError::invalid_automaton(
+                "single pattern is inconsistent with the outputs",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Serializes the automaton into a [`Vec`].
+    ///
+    /// If no option added after version 1.0.0 is used, e.g.,
+    /// [`DoubleArrayAhoCorasickBuilder::compress_fail()`], the data has the same layout as
+    /// version 1.0.0 and can be deserialized by it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+    /// let bytes = pma.serialize();
+    /// ```
+    #[must_use]
+    pub fn serialize(&self) -> Vec<u8>
+    where
+        V: Serializable,
+    {
+        let single_pattern = self
+            .single_pattern
+            .as_ref()
+            .map_or_else(Vec::new, |s| s.needle().to_vec());
+        let mut flags = 0;
+        if self.prefix_only {
+            flags |= PREFIX_ONLY_FLAG;
+        }
+        if self.minimize_suffixes {
+            flags |= MINIMIZE_SUFFIXES_FLAG;
+        }
+        let extended = !self.fail_ids.is_empty()
+            || !self.skip_bytes.is_empty()
+            || !single_pattern.is_empty()
+            || flags != 0;
+        let mut capacity = self.states.serialized_bytes()
+            + self.outputs.serialized_bytes()
+            + MatchKind::serialized_bytes()
+            + u32::serialized_bytes();
+        if extended {
+            capacity += u32::serialized_bytes()
+                + self.fail_ids.serialized_bytes()
+                + self.fail_table.serialized_bytes()
+                + self.skip_bytes.serialized_bytes()
+                + single_pattern.serialized_bytes()
+                + u8::serialized_bytes();
+        }
+        let mut result = Vec::with_capacity(capacity);
+        self.states.serialize_to_vec(&mut result);
+        self.outputs.serialize_to_vec(&mut result);
+        let mut match_kind = u8::from(self.match_kind);
+        if extended {
+            match_kind |= EXTENDED_FORMAT_FLAG;
+        }
+        match_kind.serialize_to_vec(&mut result);
+        self.num_states.serialize_to_vec(&mut result);
+        if extended {
+            EXTENDED_FORMAT_VERSION.serialize_to_vec(&mut result);
+            self.fail_ids.serialize_to_vec(&mut result);
+            self.fail_table.serialize_to_vec(&mut result);
+            self.skip_bytes.serialize_to_vec(&mut result);
+            single_pattern.serialize_to_vec(&mut result);
+            flags.serialize_to_vec(&mut result);
+        }
+        result
+    }
+
+    /// Deserializes the automaton from a given slice.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - A source slice.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the automaton and the slice not used for the deserialization.
+    ///
+    /// # Safety
+    ///
+    /// The given data must be a correct automaton exported by
+    /// [`DoubleArrayAhoCorasick::serialize()`] function.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+    /// let bytes = pma.serialize();
+    ///
+    /// let (pma, _) = unsafe { DoubleArrayAhoCorasick::<u32>::deserialize_unchecked(&bytes) };
+    ///
+    /// let mut it = pma.find_overlapping_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 1, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 2, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    #[must_use]
+    pub unsafe fn deserialize_unchecked(source: &[u8]) -> (Self, &[u8])
+    where
+        V: Serializable,
+    {
+        let (states, source) = Vec::<State>::deserialize_from_slice(source);
+        let (outputs, source) = Vec::<Output<V>>::deserialize_from_slice(source);
+        let (match_kind, source) = u8::deserialize_from_slice(source);
+        let (num_states, mut source) = u32::deserialize_from_slice(source);
+        let mut fail_ids = vec![];
+        let mut fail_table = vec![];
+        let mut skip_bytes = vec![];
+        let mut single_pattern = None;
+        let mut flags = 0;
+        if match_kind & EXTENDED_FORMAT_FLAG != 0 {
+            let (version, rest) = u32::deserialize_from_slice(source);
+            let (ids, rest) = Vec::<u32>::deserialize_from_slice(rest);
+            let (table, rest) = Vec::<u32>::deserialize_from_slice(rest);
+            let (bytes, rest) = Vec::<u8>::deserialize_from_slice(rest);
+            let (needle, rest) = Vec::<u8>::deserialize_from_slice(rest);
+            fail_ids = ids;
+            fail_table = table;
+            skip_bytes = bytes;
+            single_pattern = (!needle.is_empty()).then(|| memmem::TwoWay::new(needle));
+            source = rest;
+            // The build options are absent in version 1.
+            if version >= 2 {
+                let (bits, rest) = u8::deserialize_from_slice(source);
+                flags = bits;
+                source = rest;
+            }
+        }
+        let match_kind = MatchKind::from(match_kind & !EXTENDED_FORMAT_FLAG);
+        let fail_slots = fail_slots(states.len(), &fail_ids);
+        (
+            Self {
+                states,
+                outputs,
+                match_kind,
+                num_states,
+                fail_ids,
+                fail_table,
+                fail_slots,
+                skip_bytes,
+                single_pattern,
+                prefix_only: flags & PREFIX_ONLY_FLAG != 0,
+                minimize_suffixes: flags & MINIMIZE_SUFFIXES_FLAG != 0,
+            },
+            source,
+        )
+    }
+
+    /// Returns the child index of the given state with the given byte if it exists.
+    ///
+    /// Unlike [`Self::child_index_unchecked()`], the indices are bounds-checked.
+    #[inline(always)]
+    fn child_index(&self, state_id: u32, c: u8) -> Option<u32> {
+        let base = self.states.get(usize::from_u32(state_id))?.base()?;
+        let child_idx = base.get() ^ u32::from(c);
+        self.states
+            .get(usize::from_u32(child_idx))
+            .filter(|child| child.check() == c)
+            .map(|_| child_idx)
+    }
+}
+
+impl<V, S> DoubleArrayAhoCorasick<V, S>
+where
+    S: DoubleArrayState,
+{
+    /// Returns an iterator of non-overlapping matches in the given haystack.
+    ///
+    /// If only one pattern is registered, its occurrences are located by the two-way substring
+    /// search algorithm instead of the transitions of the automaton.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 1, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_iter<P>(&self, haystack: P) -> FindIterator<'_, U8SliceIterator<P>, V, S>
+    where
+        P: AsRef<[u8]>,
+    {
+        assert!(
+            self.match_kind.is_standard(),
+            "Error: match_kind must be standard."
+        );
+        FindIterator::with_slice(self, haystack, ROOT_STATE_IDX, 0)
+    }
+
+    /// Returns an iterator of non-overlapping matches in the given haystack, ignoring the bytes
+    /// specified by [`DoubleArrayAhoCorasickBuilder::skip_bytes()`].
+    ///
+    /// Skipped bytes are consumed without any transition, so a pattern matches even if skipped
+    /// bytes are interleaved in the haystack. The span of a match starts at the first byte and
+    /// ends just after the last byte that are not skipped, so it includes the skipped bytes
+    /// inside the match but never ones around the match. Thus, [`Match::end()`] -
+    /// [`Match::start()`] can be longer than the pattern. If no byte is specified, this iterator
+    /// reports the same matches as [`DoubleArrayAhoCorasick::find_iter()`].
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasick, DoubleArrayAhoCorasickBuilder};
+    ///
+    /// let patterns = vec!["555-1234", "911"];
+    /// let pma: DoubleArrayAhoCorasick<usize> = DoubleArrayAhoCorasickBuilder::new()
+    ///     .skip_bytes(b" -")
+    ///     .build(patterns)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_iter_skipping("call 555 12-34 or 9-1-1 ");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((5, 14, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((18, 23, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_iter_skipping<P>(&self, haystack: P) -> FindSkippingIterator<'_, P, V, S>
+    where
+        P: AsRef<[u8]>,
+    {
+        assert!(
+            self.match_kind.is_standard(),
+            "Error: match_kind must be standard."
+        );
+        let mut skip_table = [false; 256];
+        for &c in &self.skip_bytes {
+            skip_table[usize::from(c)] = true;
+        }
+        let max_len = self
+            .outputs
+            .iter()
+            .map(|out| usize::from_u32(out.length))
+            .max()
+            .unwrap_or(0);
+        FindSkippingIterator {
+            pma: self,
+            haystack,
+            skip_table,
+            pos: 0,
+            starts: VecDeque::with_capacity(max_len),
+            max_len,
+        }
+    }
+
+    /// Returns an iterator of overlapping matches in the given haystack.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_overlapping_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 1, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 2, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_overlapping_iter<P>(
+        &self,
+        haystack: P,
+    ) -> FindOverlappingIterator<'_, U8SliceIterator<P>, V, S>
+    where
+        P: AsRef<[u8]>,
+    {
+        assert!(
+            self.match_kind.is_standard(),
+            "Error: match_kind must be standard."
+        );
+        FindOverlappingIterator {
+            pma: self,
+            haystack: U8SliceIterator::new(haystack).enumerate(),
+            state_id: ROOT_STATE_IDX,
+            output_pos: None,
+            pos: 0,
+        }
+    }
+
+    /// Returns an iterator of leftmost matches in the given haystack.
+    ///
+    /// The leftmost match greedily searches the longest possible match at each iteration, and
+    /// the match results do not overlap positionally such as
+    /// [`DoubleArrayAhoCorasick::find_iter()`].
+    ///
+    /// According to the [`MatchKind`] option you specified in the construction, the behavior is
+    /// changed for multiple possible matches, as follows.
+    ///
+    ///  - If you set [`MatchKind::LeftmostLongest`], it reports the match
+    ///    corresponding to the longest pattern.
+    ///
+    ///  - If you set [`MatchKind::LeftmostFirst`], it reports the match
+    ///    corresponding to the pattern earlier registered to the automaton.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::LeftmostFirst`] or [`MatchKind::LeftmostLongest`] in
+    /// the construction, the iterator is not supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ## LeftmostLongest
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasickBuilder, MatchKind};
+    ///
+    /// let patterns = vec!["ab", "a", "abcd"];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .match_kind(MatchKind::LeftmostLongest)
+    ///     .build(&patterns)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.leftmost_find_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 4, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    ///
+    /// ## LeftmostFirst
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasickBuilder, MatchKind};
+    ///
+    /// let patterns = vec!["ab", "a", "abcd"];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .match_kind(MatchKind::LeftmostFirst)
+    ///     .build(&patterns)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.leftmost_find_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 2, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn leftmost_find_iter<P>(&self, haystack: P) -> LeftmostFindIterator<'_, P, V, S>
+    where
+        P: AsRef<[u8]>,
+    {
+        assert!(
+            self.match_kind.is_leftmost(),
+            "Error: match_kind must be leftmost."
+        );
+        LeftmostFindIterator {
+            pma: self,
+            haystack,
+            pos: 0,
+        }
+    }
+
+    /// Returns the total amount of heap used by this automaton in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+    ///
+    /// assert_eq!(3108, pma.heap_bytes());
+    /// ```
+    #[must_use]
+    pub fn heap_bytes(&self) -> usize {
+        self.states.len() * mem::size_of::<S>()
+            + self.outputs.len() * mem::size_of::<Output<V>>()
+            + self.fail_ids.len() * mem::size_of::<u32>()
+            + self.fail_table.len() * mem::size_of::<u32>()
+            + self.fail_slots.len() * mem::size_of::<u16>()
+            + self.skip_bytes.len() * mem::size_of::<u8>()
+            + self
+                .single_pattern
+                .as_ref()
+                .map_or(0, |s| mem::size_of_val(s.needle()))
+    }
+
+    /// Returns the number of outputs in the chain of the given state, i.e., the number of
+    /// patterns reported when the search reaches the state in overlapping search.
+    ///
+    /// Aggregated over all states, it shows how deeply patterns are nested as suffixes of each
+    /// other, which determines the cost of overlapping search. Zero is returned for states
+    /// without outputs, including unused and out-of-range IDs.
+    ///
+    /// # Arguments
+    ///
+    /// * `state_id` - ID of the state, such as one returned by
+    ///   [`DoubleArrayAhoCorasick::state_path()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "cd", "d", "ab"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let path = pma.state_path("bcd").unwrap();
+    /// let counts: Vec<_> = path.iter().map(|&id| pma.output_count_at(id as usize)).collect();
+    /// assert_eq!(vec![0, 0, 0, 3], counts);
+    /// ```
+    #[must_use]
+    pub fn output_count_at(&self, state_id: usize) -> usize
+    where
+        V: Copy,
+    {
+        let mut output_pos = self
+            .states
+            .get(state_id)
+            .and_then(|state| state.output_pos());
+        let mut count = 0;
+        while let Some(pos) = output_pos {
+            count += 1;
+            output_pos = self.outputs[usize::from_u32(pos.get() - 1)].parent();
+        }
+        count
+    }
+
+    /// Returns the number of fail links followed while the automaton consumes all bytes of the
+    /// given haystack from the root without restarting at matches.
+    ///
+    /// Divided by the length of the haystack, it shows how many extra steps the search takes per
+    /// byte, which is what [`DoubleArrayAhoCorasickBuilder::compress_fail()`] reduces.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to scan.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasick, DoubleArrayAhoCorasickBuilder};
+    ///
+    /// let patterns = vec!["abcd", "bcd", "cd"];
+    /// let pma = DoubleArrayAhoCorasick::<u32>::new(&patterns).unwrap();
+    ///
+    /// // "abc" -> "bc" -> "c" -> the root
+    /// assert_eq!(3, pma.count_fail_hops("abcx"));
+    ///
+    /// let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+    ///     .compress_fail(true)
+    ///     .build(&patterns)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(0, pma.count_fail_hops("abcx"));
+    /// ```
+    #[must_use]
+    pub fn count_fail_hops<P>(&self, haystack: P) -> usize
+    where
+        P: AsRef<[u8]>,
+    {
+        let mut num_hops = 0;
+        let mut state_id = ROOT_STATE_IDX;
+        for &c in haystack.as_ref() {
+            // In the loop, state_id is always smaller than states.len() because it is the root
+            // or a value returned by child_index_unchecked() or fail(). fail_slots is checked
+            // for emptiness before compressed_next_state_id_unchecked() is called.
+            state_id = unsafe {
+                loop {
+                    if let Some(child_id) = self.child_index_unchecked(state_id, c) {
+                        break child_id;
+                    }
+                    if state_id == ROOT_STATE_IDX {
+                        break ROOT_STATE_IDX;
+                    }
+                    if !self.fail_slots.is_empty() {
+                        if let Some(next_id) = self.compressed_next_state_id_unchecked(state_id, c)
+                        {
+                            break next_id;
+                        }
+                    }
+                    num_hops += 1;
+                    let fail_id = self.states.get_unchecked(usize::from_u32(state_id)).fail();
+                    if fail_id == DEAD_STATE_IDX {
+                        break ROOT_STATE_IDX;
+                    }
+                    state_id = fail_id;
+                }
+            };
+        }
+        num_hops
+    }
+
+    /// Returns the total number of states this automaton has.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// assert_eq!(pma.num_states(), 6);
+    /// ```
+    #[must_use]
+    pub fn num_states(&self) -> usize {
+        usize::from_u32(self.num_states)
+    }
+
+    /// Returns the [`MatchKind`] specified in the construction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasick, MatchKind};
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// assert_eq!(MatchKind::Standard, pma.match_kind());
+    /// ```
+    #[inline(always)]
+    #[must_use]
+    pub const fn match_kind(&self) -> MatchKind {
+        self.match_kind
+    }
+
+    /// # Safety
+    ///
+    /// `state_id` must be smaller than the length of states.
+    #[inline(always)]
+    unsafe fn child_index_unchecked(&self, state_id: u32, c: u8) -> Option<u32> {
+        // child_idx is always smaller than states.len() because
+        //  - states.len() is 256 * k for some integer k, and
+        //  - base() returns smaller than states.len() when it is Some.
+        self.states
+            .get_unchecked(usize::from_u32(state_id))
+            .base()
+            .and_then(|base| {
+                let child_idx = base.get() ^ u32::from(c);
+                Some(child_idx)
+                    .filter(|&x| self.states.get_unchecked(usize::from_u32(x)).check() == c)
+            })
+    }
+
+    /// Returns the precomputed next state if `state_id` is compressed by
+    /// [`DoubleArrayAhoCorasickBuilder::compress_fail()`].
+    ///
+    /// # Safety
+    ///
+    /// At least one state must be compressed, and `state_id` must be smaller than the length of
+    /// states.
+    #[inline(always)]
+    unsafe fn compressed_next_state_id_unchecked(&self, state_id: u32, c: u8) -> Option<u32> {
+        // fail_slots has the same length as states if a state is compressed, and each non-zero
+        // slot is at most fail_ids.len(), for which fail_table has 256 entries.
+        let slot = *self.fail_slots.get_unchecked(usize::from_u32(state_id));
+        if slot == 0 {
+            return None;
+        }
+        Some(
+            *self
+                .fail_table
+                .get_unchecked(usize::from(slot - 1) * 256 + usize::from(c)),
+        )
+    }
+
+    /// # Safety
+    ///
+    /// `state_id` must be smaller than the length of states.
+    #[inline(always)]
+    unsafe fn next_state_id_unchecked(&self, mut state_id: u32, c: u8) -> u32 {
+        // In the loops, state_id is always set to values smaller than states.len(),
+        // because child_index_unchecked() and fail() return such values.
+        if self.fail_slots.is_empty() {
+            loop {
+                if let Some(state_id) = self.child_index_unchecked(state_id, c) {
+                    return state_id;
+                }
+                if state_id == ROOT_STATE_IDX {
+                    return ROOT_STATE_IDX;
+                }
+                state_id = self.states.get_unchecked(usize::from_u32(state_id)).fail();
+            }
+        }
+        loop {
+            if let Some(state_id) = self.child_index_unchecked(state_id, c) {
+                return state_id;
+            }
+            if state_id == ROOT_STATE_IDX {
+                return ROOT_STATE_IDX;
+            }
+            if let Some(state_id) = self.compressed_next_state_id_unchecked(state_id, c) {
+                return state_id;
+            }
+            state_id = self.states.get_unchecked(usize::from_u32(state_id)).fail();
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `state_id` must be smaller than the length of states.
+    #[inline(always)]
+    unsafe fn next_state_id_leftmost_unchecked(&self, mut state_id: u32, c: u8) -> u32 {
+        // In the loops, state_id is always set to values smaller than states.len(),
+        // because child_index_unchecked() and fail() return such values.
+        if self.fail_slots.is_empty() {
+            loop {
+                if let Some(state_id) = self.child_index_unchecked(state_id, c) {
+                    return state_id;
+                }
+                if state_id == ROOT_STATE_IDX {
+                    return ROOT_STATE_IDX;
+                }
+                let fail_id = self.states.get_unchecked(usize::from_u32(state_id)).fail();
+                if fail_id == DEAD_STATE_IDX {
+                    return ROOT_STATE_IDX;
+                }
+                state_id = fail_id;
+            }
+        }
+        loop {
+            if let Some(state_id) = self.child_index_unchecked(state_id, c) {
+                return state_id;
+            }
+            if state_id == ROOT_STATE_IDX {
+                return ROOT_STATE_IDX;
+            }
+            if let Some(state_id) = self.compressed_next_state_id_unchecked(state_id, c) {
+                return state_id;
+            }
+            let fail_id = self.states.get_unchecked(usize::from_u32(state_id)).fail();
             if fail_id == DEAD_STATE_IDX {
                 return ROOT_STATE_IDX;
             }
-            state_id = fail_id;
+            state_id = fail_id;
+        }
+    }
+}
+
+impl<V> TryFrom<&[u8]> for DoubleArrayAhoCorasick<V>
+where
+    V: Serializable + Copy,
+{
+    type Error = DaachorseError;
+
+    /// Deserializes the automaton from a slice exported by
+    /// [`DoubleArrayAhoCorasick::serialize()`].
+    ///
+    /// Unlike [`DoubleArrayAhoCorasick::deserialize_unchecked()`], this function is safe for
+    /// untrusted data because it checks the length of the slice and validates the automaton with
+    /// [`DoubleArrayAhoCorasick::verify()`].
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned when
+    ///   - the slice is too short or has trailing bytes,
+    ///   - the match kind is invalid,
+    ///   - the format version is unsupported, or
+    ///   - [`DoubleArrayAhoCorasick::verify()`] fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+    /// let bytes = pma.serialize();
+    ///
+    /// let pma = DoubleArrayAhoCorasick::<u32>::try_from(&bytes[..]).unwrap();
+    ///
+    /// let mut it = pma.find_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 1, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    ///
+    /// assert!(DoubleArrayAhoCorasick::<u32>::try_from(&bytes[1..]).is_err());
+    /// ```
+    fn try_from(source: &[u8]) -> Result<Self> {
+        let truncated = || DaachorseError::invalid_automaton("serialized data is truncated");
+        let rest = skip_serialized_vec::<State>(source).ok_or_else(truncated)?;
+        let rest = skip_serialized_vec::<Output<V>>(rest).ok_or_else(truncated)?;
+        let match_kind = *rest.first().ok_or_else(truncated)?;
+        let extended = match_kind & EXTENDED_FORMAT_FLAG != 0;
+        let match_kind = match_kind & !EXTENDED_FORMAT_FLAG;
+        if u8::from(MatchKind::from(match_kind)) != match_kind {
+            return Err(DaachorseError::invalid_automaton("match kind is invalid"));
+        }
+        let rest = skip_serialized::<MatchKind>(rest).ok_or_else(truncated)?;
+        let mut rest = skip_serialized::<u32>(rest).ok_or_else(truncated)?;
+        if extended {
+            let (version, _) = u32::deserialize_from_slice(rest.get(..4).ok_or_else(truncated)?);
+            if version == 0 || version > EXTENDED_FORMAT_VERSION {
+                return Err(DaachorseError::invalid_automaton(
+                    "format version is unsupported",
+                ));
+            }
+            rest = skip_serialized::<u32>(rest).ok_or_else(truncated)?;
+            rest = skip_serialized_vec::<u32>(rest).ok_or_else(truncated)?;
+            rest = skip_serialized_vec::<u32>(rest).ok_or_else(truncated)?;
+            rest = skip_serialized_vec::<u8>(rest).ok_or_else(truncated)?;
+            rest = skip_serialized_vec::<u8>(rest).ok_or_else(truncated)?;
+            if version >= 2 {
+                let flags = *rest.first().ok_or_else(truncated)?;
+                if flags & !(PREFIX_ONLY_FLAG | MINIMIZE_SUFFIXES_FLAG) != 0 {
+                    return Err(DaachorseError::invalid_automaton(
+                        "build options are invalid",
+                    ));
+                }
+                rest = skip_serialized::<u8>(rest).ok_or_else(truncated)?;
+            }
+        }
+        if !rest.is_empty() {
+            return Err(DaachorseError::invalid_automaton(
+                "serialized data has trailing bytes",
+            ));
+        }
+
+        // The slice has enough bytes for each component, and the automaton is validated below
+        // before it is returned.
+        let (pma, _) = unsafe { Self::deserialize_unchecked(source) };
+        pma.verify()?;
+        Ok(pma)
+    }
+}
+
+impl<V> TryFrom<Vec<u8>> for DoubleArrayAhoCorasick<V>
+where
+    V: Serializable + Copy,
+{
+    type Error = DaachorseError;
+
+    /// Deserializes the automaton from a vector exported by
+    /// [`DoubleArrayAhoCorasick::serialize()`].
+    ///
+    /// See [`TryFrom<&[u8]>`](#impl-TryFrom%3C%26%5Bu8%5D%3E-for-DoubleArrayAhoCorasick%3CV%3E)
+    /// for details.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned when the data is not a valid automaton.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+    ///
+    /// let pma = DoubleArrayAhoCorasick::<u32>::try_from(pma.serialize()).unwrap();
+    /// assert_eq!(Some(1), pma.contains_pattern("ab"));
+    /// ```
+    fn try_from(source: Vec<u8>) -> Result<Self> {
+        Self::try_from(source.as_slice())
+    }
+}
+
+/// Splits matches into three parallel vectors of start positions, end positions, and values.
+fn matches_to_columns<I, V>(matches: I) -> (Vec<usize>, Vec<usize>, Vec<V>)
+where
+    I: Iterator<Item = Match<V>>,
+    V: Copy,
+{
+    let mut starts = vec![];
+    let mut ends = vec![];
+    let mut values = vec![];
+    for m in matches {
+        starts.push(m.start());
+        ends.push(m.end());
+        values.push(m.value());
+    }
+    (starts, ends, values)
+}
+
+/// Returns the position of each state in `fail_ids` plus one, or zero for the other states, so
+/// that the search finds the precomputed transitions of a state in constant time.
+///
+/// IDs out of range are ignored, so that they are reported by
+/// [`DoubleArrayAhoCorasick::verify()`] instead of panicking.
+fn fail_slots(num_states: usize, fail_ids: &[u32]) -> Vec<u16> {
+    if fail_ids.is_empty() {
+        return vec![];
+    }
+    let mut slots = vec![0; num_states];
+    for (i, &state_id) in fail_ids.iter().enumerate() {
+        if let (Some(slot), Ok(pos)) = (
+            slots.get_mut(usize::from_u32(state_id)),
+            u16::try_from(i + 1),
+        ) {
+            *slot = pos;
+        }
+    }
+    slots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::num::NonZeroU32;
+
+    #[test]
+    fn test_double_array() {
+        /*
+         *          a--> 4
+         *         /
+         *   a--> 1 --c--> 5
+         *  /
+         * 0 --b--> 2 --c--> 6
+         *  \
+         *   c--> 3
+         *
+         *   a = 0
+         *   b = 1
+         *   c = 2
+         */
+        let patterns = vec![vec![0, 0], vec![0, 2], vec![1, 2], vec![2]];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+
+        let base_expected = vec![
+            NonZeroU32::new(4), // 0  (state=0)
+            None,               // 1  (reserved)
+            None,               // 2
+            None,               // 3  (state=6)
+            NonZeroU32::new(8), // 4  (state=1)
+            NonZeroU32::new(1), // 5  (state=2)
+            None,               // 6  (state=3)
+            None,               // 7
+            None,               // 8  (state=4)
+            None,               // 9
+            None,               // 10 (state=5)
+        ];
+        let check_expected = vec![
+            0, // 0  (state=0)
+            1, // 1
+            2, // 2
+            2, // 3  (state=6)
+            0, // 4  (state=1)
+            1, // 5  (state=2)
+            2, // 6  (state=3)
+            7, // 7
+            0, // 8  (state=4)
+            9, // 9
+            2, // 10 (state=5)
+        ];
+        let fail_expected = vec![
+            ROOT_STATE_IDX, // 0  (state=0)
+            ROOT_STATE_IDX, // 1  (reserved)
+            ROOT_STATE_IDX, // 2
+            6,              // 3  (state=6)
+            ROOT_STATE_IDX, // 4  (state=1)
+            ROOT_STATE_IDX, // 5  (state=2)
+            ROOT_STATE_IDX, // 6  (state=3)
+            ROOT_STATE_IDX, // 7
+            4,              // 8  (state=4)
+            ROOT_STATE_IDX, // 9
+            6,              // 10 (state=5)
+        ];
+
+        let pma_base: Vec<_> = pma.states[0..11].iter().map(|state| state.base()).collect();
+        let pma_check: Vec<_> = pma.states[0..11]
+            .iter()
+            .map(|state| state.check())
+            .collect();
+        let pma_fail: Vec<_> = pma.states[0..11].iter().map(|state| state.fail()).collect();
+
+        assert_eq!(base_expected, pma_base);
+        assert_eq!(check_expected, pma_check);
+        assert_eq!(fail_expected, pma_fail);
+    }
+
+    #[test]
+    fn test_num_states() {
+        /*
+         *   b-*-a-*-a-*-b-*-a-*
+         *  /
+         * *-a-*-b-*-b-*-a-*
+         *          \
+         *           a-*-b-*-a-*
+         */
+        let patterns = vec!["abba", "baaba", "ababa"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+
+        assert_eq!(13, pma.num_states());
+    }
+
+    #[test]
+    fn test_input_order() {
+        let patvals_sorted = vec![("ababa", 0), ("abba", 1), ("baaba", 2)];
+        let patvals_unsorted = vec![("abba", 1), ("baaba", 2), ("ababa", 0)];
+
+        let pma_sorted = DoubleArrayAhoCorasick::with_values(patvals_sorted).unwrap();
+        let pma_unsorted = DoubleArrayAhoCorasick::with_values(patvals_unsorted).unwrap();
+
+        assert_eq!(pma_sorted.states, pma_unsorted.states);
+        assert_eq!(pma_sorted.outputs, pma_unsorted.outputs);
+    }
+
+    #[test]
+    fn test_n_blocks_1_1() {
+        let mut patterns = vec![];
+        // state 0: reserved for the root state
+        // state 1: reserved for the dead state
+        // base = 0xfe; fills 0x02..=0xff
+        for i in 0x00..=0xfd {
+            let pattern = vec![i];
+            patterns.push(pattern);
+        }
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        assert_eq!(255, pma.num_states());
+        assert_eq!(256, pma.states.len());
+        assert_eq!(0xfe, pma.states[0].base().unwrap().get());
+    }
+
+    #[test]
+    fn test_n_blocks_1_2() {
+        let mut patterns = vec![];
+        // state 0: reserved for the root state
+        // state 1: reserved for the dead state
+        // base = 0x100; fills 0x100, 0x102, 0x104..=0x1ff
+        patterns.push(vec![0x00]);
+        patterns.push(vec![0x02]);
+        for i in 0x04..=0xff {
+            patterns.push(vec![i]);
+        }
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        assert_eq!(255, pma.num_states());
+        assert_eq!(512, pma.states.len());
+        assert_eq!(0x100, pma.states[0].base().unwrap().get());
+    }
+
+    #[test]
+    fn test_n_blocks_2_1() {
+        let mut patterns = vec![];
+        // state 0: reserved for the root state
+        // state 1: reserved for the dead state
+        // base = 0x80; fills 0x80..=0xff
+        for i in 0x00..=0x7f {
+            let pattern = vec![i];
+            patterns.push(pattern);
+        }
+        // base = 0x7e; fills 0x02..=0x7f
+        for i in 0x00..=0x7d {
+            let pattern = vec![0x00, i];
+            patterns.push(pattern);
+        }
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        assert_eq!(255, pma.num_states());
+        assert_eq!(256, pma.states.len());
+        assert_eq!(0x80, pma.states[0].base().unwrap().get());
+        assert_eq!(0x7e, pma.states[0x80].base().unwrap().get());
+    }
+
+    #[test]
+    fn test_n_blocks_2_2() {
+        let mut patterns = vec![];
+        // state 0: reserved for the root state
+        // state 1: reserved for the dead state
+        // base = 0x80; fills 0x80..=0xff
+        for i in 0..=0x7f {
+            let pattern = vec![i];
+            patterns.push(pattern);
+        }
+        // base = 0x100; fills 0x100, 0x102, 0x104..=0x17f
+        patterns.push(vec![0, 0x00]);
+        patterns.push(vec![0, 0x02]);
+        for i in 0x04..=0x7f {
+            let pattern = vec![0x00, i];
+            patterns.push(pattern);
+        }
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        assert_eq!(255, pma.num_states());
+        assert_eq!(512, pma.states.len());
+        assert_eq!(0x80, pma.states[0].base().unwrap().get());
+        assert_eq!(0x100, pma.states[0x80].base().unwrap().get());
+    }
+
+    #[test]
+    fn test_contains_pattern() {
+        let patterns = vec!["abba", "baaba", "ababa", "ba"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+
+        assert_eq!(Some(0), pma.contains_pattern("abba"));
+        assert_eq!(Some(1), pma.contains_pattern("baaba"));
+        assert_eq!(Some(2), pma.contains_pattern("ababa"));
+        assert_eq!(Some(3), pma.contains_pattern("ba"));
+        assert_eq!(None, pma.contains_pattern(""));
+        assert_eq!(None, pma.contains_pattern("ab"));
+        assert_eq!(None, pma.contains_pattern("aba"));
+        assert_eq!(None, pma.contains_pattern("abbab"));
+        assert_eq!(None, pma.contains_pattern("c"));
+    }
+
+    #[test]
+    fn test_find_at_most() {
+        let patterns = vec!["a", "ab"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        let haystack = "abaab";
+
+        assert!(pma.find_at_most(haystack, 0).is_empty());
+        assert_eq!(
+            pma.find_iter(haystack).take(2).collect::<Vec<_>>(),
+            pma.find_at_most(haystack, 2)
+        );
+        assert_eq!(
+            pma.find_iter(haystack).collect::<Vec<_>>(),
+            pma.find_at_most(haystack, 100)
+        );
+    }
+
+    #[test]
+    fn test_find_in_range() {
+        let patterns = vec!["abc", "bc", "c"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        let haystack = "abcabcabc";
+
+        // The context before the window is not carried over.
+        let matches: Vec<_> = pma
+            .find_in_range(haystack, 1..6)
+            .map(|m| (m.start(), m.end(), m.value()))
+            .collect();
+        assert_eq!(vec![(1, 3, 1), (3, 6, 0)], matches);
+
+        // Matches crossing the end of the window are not reported.
+        let matches: Vec<_> = pma
+            .find_in_range(haystack, 3..8)
+            .map(|m| (m.start(), m.end(), m.value()))
+            .collect();
+        assert_eq!(vec![(3, 6, 0)], matches);
+
+        assert_eq!(None, pma.find_in_range(haystack, 4..4).next());
+        assert_eq!(
+            pma.find_iter(haystack).collect::<Vec<_>>(),
+            pma.find_in_range(haystack, 0..haystack.len())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_find_in_range_out_of_bounds() {
+        let patterns = vec!["a"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        let _ = pma.find_in_range("aaa", 2..4);
+    }
+
+    #[test]
+    fn test_prefix_only() {
+        let patterns = vec!["abc", "a", "abcde", "bcd", "b"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(&patterns).unwrap();
+        let prefix_pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .prefix_only(true)
+            .build(&patterns)
+            .unwrap();
+        prefix_pma.verify().unwrap();
+
+        for haystack in ["abcdef", "abcd", "bcdx", "xabc", ""] {
+            let expected: Vec<_> = pma
+                .find_overlapping_iter(haystack)
+                .filter(|m| m.start() == 0)
+                .map(|m| (m.end(), m.value()))
+                .collect();
+            for pma in [&pma, &prefix_pma] {
+                let matches: Vec<_> = pma
+                    .find_anchored(haystack)
+                    .map(|m| (m.end(), m.value()))
+                    .collect();
+                assert_eq!(expected, matches);
+                assert_eq!(
+                    expected.last().copied(),
+                    pma.longest_prefix_match(haystack)
+                        .map(|m| (m.end(), m.value()))
+                );
+            }
         }
+
+        // No state has a fail link other than the root.
+        assert!(prefix_pma.states.iter().all(|s| s.fail() == ROOT_STATE_IDX));
+        // Suffix matches are not propagated.
+        let matches: Vec<_> = prefix_pma
+            .find_overlapping_iter("abcd")
+            .map(|m| (m.start(), m.end(), m.value()))
+            .collect();
+        assert_eq!(vec![(0, 1, 1), (0, 3, 0)], matches);
     }
-}
 
-#[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
-struct State {
-    base: Option<NonZeroU32>,
-    fail: u32,
-    // 3 bytes for output_pos and 1 byte for check.
-    opos_ch: U24nU8,
-}
+    #[test]
+    fn test_leftmost_first_priorities() {
+        let patvals_priorities = vec![("ab", 0, 1), ("abc", 1, 0), ("bcd", 2, 0), ("a", 3, 1)];
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostFirst)
+            .build_with_values_and_priorities(patvals_priorities)
+            .unwrap();
 
-impl State {
-    #[inline(always)]
-    pub const fn base(&self) -> Option<NonZeroU32> {
-        self.base
+        let matches: Vec<_> = pma
+            .leftmost_find_iter("abcdabxa")
+            .map(|m| (m.start(), m.end(), m.value()))
+            .collect();
+        assert_eq!(vec![(0, 3, 1), (4, 6, 0), (7, 8, 3)], matches);
     }
 
-    #[inline(always)]
-    pub fn check(&self) -> u8 {
-        self.opos_ch.b()
+    #[test]
+    fn test_coverage() {
+        let patterns = vec!["bc", "abcd", "b", "ef", "g"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+
+        assert_eq!(0, pma.coverage(""));
+        assert_eq!(0, pma.coverage("xyz"));
+        // Fully overlapping matches
+        assert_eq!(4, pma.coverage("abcd"));
+        assert_eq!(4, pma.coverage("xabcdx"));
+        // Adjacent matches
+        assert_eq!(3, pma.coverage("efg"));
+        assert_eq!(7, pma.coverage("abcdefg"));
+        // Separate matches
+        assert_eq!(3, pma.coverage("bxbcx"));
     }
 
-    #[inline(always)]
-    pub const fn fail(&self) -> u32 {
-        self.fail
+    #[test]
+    fn test_pattern_length_histogram() {
+        let patterns = vec!["ab", "abc", "a", "abcd"];
+
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns.clone()).unwrap();
+        let histogram = pma.pattern_length_histogram();
+        assert_eq!(
+            vec![(1, 1), (2, 1), (3, 1), (4, 1)],
+            histogram.into_iter().collect::<Vec<_>>()
+        );
+
+        // "abc" and "abcd" are never reported in leftmost-first matching.
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostFirst)
+            .build(patterns)
+            .unwrap();
+        let histogram = pma.pattern_length_histogram();
+        assert_eq!(
+            vec![(1, 1), (2, 1)],
+            histogram.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_is_primary() {
+        let patterns = vec!["a", "ab", "bab", "b"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        let haystack = "abab";
+
+        let mut it = pma.find_overlapping_iter(haystack);
+        let mut matches = vec![];
+        while let Some(m) = it.next() {
+            matches.push((m.start(), m.end(), it.is_primary()));
+        }
+        assert_eq!(
+            vec![
+                (0, 1, true),
+                (0, 2, true),
+                (1, 2, false),
+                (2, 3, true),
+                (1, 4, true),
+                (2, 4, false),
+                (3, 4, false),
+            ],
+            matches
+        );
+
+        let mut matches = vec![];
+        pma.for_each_overlapping_match(haystack, |m| {
+            matches.push(m);
+            ControlFlow::Continue(())
+        });
+        assert_eq!(
+            pma.find_overlapping_iter(haystack).collect::<Vec<_>>(),
+            matches
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let patterns1 = ["abc", "b", "bcd"];
+        let patterns2 = ["ab", "bcde", "c"];
+        let haystack = "abcdeabcbcd";
+
+        for match_kind in [
+            MatchKind::Standard,
+            MatchKind::LeftmostLongest,
+            MatchKind::LeftmostFirst,
+        ] {
+            let build = |patvals: Vec<(&str, u32)>| {
+                DoubleArrayAhoCorasickBuilder::new()
+                    .match_kind(match_kind)
+                    .build_with_values(patvals)
+                    .unwrap()
+            };
+            let pma1 = build(patterns1.iter().map(|&p| (p, 1)).collect());
+            let pma2 = build(patterns2.iter().map(|&p| (p, 2)).collect());
+            let merged = pma1.merge(&pma2, 100).unwrap();
+            let expected = build(
+                patterns1
+                    .iter()
+                    .map(|&p| (p, 1))
+                    .chain(patterns2.iter().map(|&p| (p, 102)))
+                    .collect(),
+            );
+            if match_kind.is_standard() {
+                assert_eq!(
+                    expected.find_overlapping_iter(haystack).collect::<Vec<_>>(),
+                    merged.find_overlapping_iter(haystack).collect::<Vec<_>>()
+                );
+            } else {
+                assert_eq!(
+                    expected.leftmost_find_iter(haystack).collect::<Vec<_>>(),
+                    merged.leftmost_find_iter(haystack).collect::<Vec<_>>()
+                );
+            }
+        }
+
+        let pma1 = DoubleArrayAhoCorasick::<u8>::new(vec!["a", "b"]).unwrap();
+        let pma2 = DoubleArrayAhoCorasick::<u8>::new(vec!["c", "b"]).unwrap();
+        assert!(pma1.merge(&pma2, 0).is_err());
+        let pma2 = DoubleArrayAhoCorasick::<u8>::new(vec!["c", "d"]).unwrap();
+        assert!(pma1.merge(&pma2, 255).is_err());
+
+        let build = |patterns: &[&str], prefix_only| -> DoubleArrayAhoCorasick<u32> {
+            DoubleArrayAhoCorasickBuilder::new()
+                .prefix_only(prefix_only)
+                .build(patterns)
+                .unwrap()
+        };
+        let merged = build(&["ab"], true).merge(&build(&["b"], true), 1).unwrap();
+        assert!(merged.prefix_only);
+        assert_eq!(None, merged.find_iter("ab").nth(1));
+        assert!(build(&["ab"], true)
+            .merge(&build(&["b"], false), 1)
+            .is_err());
+
+        let pma2 = DoubleArrayAhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostFirst)
+            .build(["c"])
+            .unwrap();
+        assert!(pma1.merge(&pma2, 2).is_err());
+        let pma2 = DoubleArrayAhoCorasickBuilder::new()
+            .skip_bytes(b"-")
+            .build(["c"])
+            .unwrap();
+        assert!(pma1.merge(&pma2, 2).is_err());
+    }
+
+    #[test]
+    fn test_find_overlapping_dedup_iter() {
+        let patvals = vec![
+            ("a", 0),
+            ("aa", 0),
+            ("ab", 1),
+            ("b", 1),
+            ("aba", 2),
+            ("abbab", 2),
+        ];
+        let pma = DoubleArrayAhoCorasick::with_values(patvals).unwrap();
+        let haystack = "aababaaabbaba";
+
+        assert_eq!(
+            pma.find_overlapping_iter(haystack).collect::<Vec<_>>(),
+            pma.find_overlapping_dedup_iter(haystack, 0)
+                .collect::<Vec<_>>()
+        );
+
+        for window in 1..5 {
+            // Naive implementation remembering all the reported matches.
+            let mut expected: Vec<Match<u32>> = vec![];
+            for m in pma.find_overlapping_iter(haystack) {
+                if !expected
+                    .iter()
+                    .any(|r| r.value() == m.value() && r.start().abs_diff(m.start()) < window)
+                {
+                    expected.push(m);
+                }
+            }
+            assert_eq!(
+                expected,
+                pma.find_overlapping_dedup_iter(haystack, window)
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        // A long match is suppressed by a prior report of a short one starting at the same
+        // position.
+        let pma = DoubleArrayAhoCorasick::with_values(vec![("a", 0), ("abcde", 0)]).unwrap();
+        let matches: Vec<_> = pma
+            .find_overlapping_dedup_iter("abcde", 2)
+            .map(|m| (m.start(), m.end(), m.value()))
+            .collect();
+        assert_eq!(vec![(0, 1, 0)], matches);
+    }
+
+    #[test]
+    fn test_bytes_scanned() {
+        let patterns = vec!["ab", "b", "cd"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        let haystack = "xabxcdx";
+
+        let mut it = pma.find_iter(haystack);
+        assert_eq!(0, it.bytes_scanned());
+        it.next();
+        assert_eq!(3, it.bytes_scanned());
+        it.next();
+        assert_eq!(6, it.bytes_scanned());
+        it.next();
+        assert_eq!(7, it.bytes_scanned());
+
+        let mut it = pma.find_overlapping_iter(haystack);
+        it.next();
+        assert_eq!(3, it.bytes_scanned());
+        it.next();
+        assert_eq!(3, it.bytes_scanned());
+        while it.next().is_some() {}
+        assert_eq!(7, it.bytes_scanned());
+
+        let mut it = pma.find_overlapping_no_suffix_iter(haystack);
+        it.next();
+        assert_eq!(3, it.bytes_scanned());
+        while it.next().is_some() {}
+        assert_eq!(7, it.bytes_scanned());
+
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(["ab", "abcd", "x"])
+            .unwrap();
+        let mut it = pma.leftmost_find_iter("abcabx");
+        it.next();
+        assert_eq!(2, it.bytes_scanned());
+        it.next();
+        assert_eq!(5, it.bytes_scanned());
+        it.next();
+        assert_eq!(6, it.bytes_scanned());
+        assert_eq!(None, it.next());
+        assert_eq!(6, it.bytes_scanned());
+    }
+
+    #[cfg(all(unix, feature = "std"))]
+    #[test]
+    fn test_find_iter_os() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let patterns = vec![b"\xff\xfe".as_slice(), b"b"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        // Not a valid UTF-8 sequence.
+        let haystack = OsStr::from_bytes(b"a\xff\xfeb");
+
+        assert_eq!(
+            pma.find_iter(haystack.as_bytes()).collect::<Vec<_>>(),
+            pma.find_iter_os(haystack).collect::<Vec<_>>()
+        );
+        assert_eq!(2, pma.find_iter_os(haystack).count());
+    }
+
+    #[test]
+    fn test_map_values() {
+        let patterns = vec!["ab", "b", "cd"];
+        let table = vec!["AB", "B", "CD"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        let haystack = "abcdb";
+
+        let mapped: Vec<_> = pma.find_iter(haystack).map_values(&table).collect();
+        let expected: Vec<_> = pma
+            .find_iter(haystack)
+            .map(|m| (m, &table[usize::from_u32(m.value())]))
+            .collect();
+        assert_eq!(expected, mapped);
+        assert_eq!(
+            pma.find_overlapping_iter(haystack).count(),
+            pma.find_overlapping_iter(haystack)
+                .map_values(&table)
+                .count()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds of the table")]
+    fn test_map_values_out_of_bounds() {
+        let patterns = vec!["ab", "b", "cd"];
+        let table = vec!["AB", "B"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        for _ in pma.find_iter("abcd").map_values(&table) {}
+    }
+
+    #[test]
+    fn test_find_all_into() {
+        let patterns = vec!["a", "ab", "bab", "b"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(&patterns).unwrap();
+        let haystack = "abababcab";
+
+        let mut matches = vec![];
+        pma.find_all_into(haystack, &mut matches);
+        assert_eq!(pma.find_iter(haystack).collect::<Vec<_>>(), matches);
+
+        let capacity = matches.capacity();
+        pma.find_all_into("b", &mut matches);
+        assert_eq!(pma.find_iter("b").collect::<Vec<_>>(), matches);
+        assert_eq!(capacity, matches.capacity());
+
+        pma.find_overlapping_all_into(haystack, &mut matches);
+        assert_eq!(
+            pma.find_overlapping_iter(haystack).collect::<Vec<_>>(),
+            matches
+        );
+
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostFirst)
+            .build(&patterns)
+            .unwrap();
+        pma.leftmost_find_all_into(haystack, &mut matches);
+        assert_eq!(
+            pma.leftmost_find_iter(haystack).collect::<Vec<_>>(),
+            matches
+        );
+    }
+
+    #[test]
+    fn test_clone_iter() {
+        let patterns = vec!["a", "ab", "bab", "b"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        let haystack = "abab";
+
+        let mut it = pma.find_overlapping_iter(haystack);
+        it.next();
+        it.next();
+        let cloned = it.clone();
+        assert_eq!(it.collect::<Vec<_>>(), cloned.collect::<Vec<_>>());
+
+        let mut it = pma.find_iter(haystack);
+        it.next();
+        let cloned = it.clone();
+        assert_eq!(1, cloned.bytes_scanned());
+        assert_eq!(it.collect::<Vec<_>>(), cloned.collect::<Vec<_>>());
+
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(["a", "ab", "bab", "b"])
+            .unwrap();
+        let mut it = pma.leftmost_find_iter(haystack);
+        it.next();
+        let cloned = it.clone();
+        assert_eq!(it.collect::<Vec<_>>(), cloned.collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_find_iter_filtered() {
+        let patvals = [("abc", 0), ("b", 1), ("abcd", 2), ("bcd", 3), ("c", 4)];
+        let haystack = "abcdbcab";
+        let pma = DoubleArrayAhoCorasick::with_values(patvals).unwrap();
+
+        for keep in [
+            [true; 5],
+            [false, true, true, true, false],
+            [true, false, false, true, false],
+            [false; 5],
+        ] {
+            let expected_pma = DoubleArrayAhoCorasick::with_values(
+                patvals.iter().copied().filter(|&(_, v)| keep[v]),
+            );
+            let expected =
+                expected_pma.map_or_else(|_| vec![], |p| p.find_iter(haystack).collect());
+            let matches: Vec<_> = pma
+                .find_iter_filtered(haystack, |v: usize| keep[v])
+                .collect();
+            assert_eq!(expected, matches);
+        }
+
+        // "b" is rejected at position 2, and the scan continues to find "abc".
+        let matches: Vec<_> = pma
+            .find_iter_filtered(haystack, |v| v != 1)
+            .map(|m| (m.start(), m.end(), m.value()))
+            .collect();
+        assert_eq!(vec![(0, 3, 0), (5, 6, 4)], matches);
+    }
+
+    #[test]
+    fn test_find_overlapping_iter_fast() {
+        // A simple xorshift generator to make the test deterministic.
+        let mut seed = 0x2545_f491_u32;
+        let mut rand = move |n: u32| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            u8::try_from(seed % n).unwrap()
+        };
+        for _ in 0..20 {
+            let mut patterns = vec![];
+            for _ in 0..10 {
+                let len = rand(5) + 1;
+                let pattern: Vec<u8> = (0..len).map(|_| b'a' + rand(4)).collect();
+                if !patterns.contains(&pattern) {
+                    patterns.push(pattern);
+                }
+            }
+            let pma = DoubleArrayAhoCorasick::<u32>::new(&patterns).unwrap();
+            // Bytes other than 'a' to 'd' are frequent so that the skip is exercised.
+            let haystack: Vec<u8> = (0..500).map(|_| b'a' + rand(12)).collect();
+            assert_eq!(
+                pma.find_overlapping_iter(&haystack).collect::<Vec<_>>(),
+                pma.find_overlapping_iter_fast(&haystack)
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_distinct_values() {
+        let patvals = [("ab", 3), ("abc", 1), ("a", 3), ("abcd", 2)];
+
+        let pma = DoubleArrayAhoCorasick::with_values(patvals).unwrap();
+        assert_eq!(
+            vec![1, 2, 3],
+            pma.distinct_values().into_iter().collect::<Vec<_>>()
+        );
+
+        // "abc" and "abcd" are never reported in leftmost-first matching.
+        let pma = DoubleArrayAhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostFirst)
+            .build_with_values(patvals)
+            .unwrap();
+        assert_eq!(
+            vec![3],
+            pma.distinct_values().into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_stream_replace_all() {
+        // Reader returning at most `chunk_len` bytes at once to split matches across reads.
+        struct ChunkReader<'a> {
+            data: &'a [u8],
+            chunk_len: usize,
+        }
+
+        impl std::io::Read for ChunkReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = self.chunk_len.min(buf.len()).min(self.data.len());
+                buf[..len].copy_from_slice(&self.data[..len]);
+                self.data = &self.data[len..];
+                Ok(len)
+            }
+        }
+
+        let patterns = ["abba", "baaba", "ababa", "ba", "bb", "aab"];
+        let replacements = ["<0>", "<1>", "<2>", "<3>", "<4>", ""];
+        let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+        let haystack = b"cabbababaabaabbbaababacbcaabb";
+
+        let mut expected = vec![];
+        let mut last = 0;
+        for m in pma.find_iter(haystack) {
+            expected.extend_from_slice(&haystack[last..m.start()]);
+            expected.extend_from_slice(replacements[m.value()].as_bytes());
+            last = m.end();
+        }
+        expected.extend_from_slice(&haystack[last..]);
+
+        for chunk_len in 1..=haystack.len() {
+            let reader = ChunkReader {
+                data: haystack,
+                chunk_len,
+            };
+            let mut output = vec![];
+            pma.stream_replace_all(reader, &mut output, &replacements)
+                .unwrap();
+            assert_eq!(expected, output);
+        }
+    }
+
+    #[test]
+    fn test_num_patterns_at_end() {
+        let patterns = ["a", "ab", "bab", "b", "abab"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        let haystack = "ababab";
+
+        let mut it = pma.find_overlapping_iter(haystack);
+        let mut counts = vec![];
+        while let Some(m) = it.next() {
+            counts.push((m.end(), it.num_patterns_at_end()));
+        }
+        for (end, count) in counts {
+            let expected = pma
+                .find_overlapping_iter(haystack)
+                .filter(|m| m.end() == end)
+                .count();
+            assert_eq!(expected, count);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_build_from_reader() {
+        let input = b"ab\r\n\xff\0c\nb\n";
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .build_from_reader(input.as_slice())
+            .unwrap();
+        let expected =
+            DoubleArrayAhoCorasick::<u32>::new([b"ab".as_slice(), b"\xff\0c", b"b"]).unwrap();
+        let haystack = b"ab\xff\0cb\0";
+        assert_eq!(
+            expected.find_overlapping_iter(haystack).collect::<Vec<_>>(),
+            pma.find_overlapping_iter(haystack).collect::<Vec<_>>()
+        );
+
+        let input = b"ab\n\r\n\nb";
+        assert!(DoubleArrayAhoCorasickBuilder::new()
+            .build_from_reader::<_, u32>(input.as_slice())
+            .is_err());
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .skip_blank_lines(true)
+            .build_from_reader(input.as_slice())
+            .unwrap();
+        assert_eq!(
+            vec![0, 1],
+            pma.find_iter("abb").map(|m| m.value()).collect::<Vec<_>>()
+        );
+
+        // Errors of the reader are propagated.
+        struct FailingReader;
+        impl std::io::Read for FailingReader {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::Other.into())
+            }
+        }
+        let result = DoubleArrayAhoCorasickBuilder::new()
+            .build_from_reader::<_, u32>(std::io::BufReader::new(FailingReader));
+        assert!(matches!(
+            result,
+            Err(DaachorseError::Io(e)) if e.get_ref().kind() == std::io::ErrorKind::Other
+        ));
+    }
+
+    #[test]
+    fn test_state_path() {
+        let patterns = ["abba", "baaba", "ababa", "ba", "a"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+
+        for pattern in patterns {
+            let path = pma.state_path(pattern).unwrap();
+            assert_eq!(pattern.len() + 1, path.len());
+            assert_eq!(ROOT_STATE_IDX, path[0]);
+            let last = path[path.len() - 1];
+            let output_pos = pma.states[usize::from_u32(last)].output_pos().unwrap();
+            assert_eq!(
+                u32::try_from(pattern.len()).unwrap(),
+                pma.outputs[usize::from_u32(output_pos.get() - 1)].length()
+            );
+        }
+        assert_eq!(Some(vec![ROOT_STATE_IDX]), pma.state_path(""));
+        assert_eq!(None, pma.state_path("abbb"));
+    }
+
+    #[test]
+    fn test_build_with_wildcards() {
+        let patvals = [("a?c", 0), ("[bx]b", 1), ("\\?", 2)];
+        let pma = DoubleArrayAhoCorasickBuilder::new()
+            .build_with_wildcards(patvals)
+            .unwrap();
+        let matches: Vec<_> = pma
+            .find_iter(b"a\x00cxbbb?a?")
+            .map(|m| (m.start(), m.end(), m.value()))
+            .collect();
+        assert_eq!(
+            vec![(0, 3, 0), (3, 5, 1), (5, 7, 1), (7, 8, 2), (9, 10, 2)],
+            matches
+        );
+
+        let result =
+            DoubleArrayAhoCorasickBuilder::new().build_with_wildcards([("ab", 0), ("a[", 1)]);
+        assert!(matches!(result, Err(DaachorseError::InvalidWildcard(_))));
+        let result = DoubleArrayAhoCorasickBuilder::new().build_with_wildcards([("???", 0)]);
+        assert!(matches!(result, Err(DaachorseError::InvalidWildcard(_))));
+        // The number of expansions is limited in total.
+        let patvals: Vec<_> = (0..17u8)
+            .map(|i| (vec![b'a' + i, b'?', b'?'], u32::from(i)))
+            .collect();
+        let result = DoubleArrayAhoCorasickBuilder::new().build_with_wildcards(patvals);
+        assert!(matches!(result, Err(DaachorseError::InvalidWildcard(_))));
+        let result =
+            DoubleArrayAhoCorasickBuilder::new().build_with_wildcards([("a?", 0), ("ab", 1)]);
+        assert!(matches!(result, Err(DaachorseError::DuplicatePattern(_))));
+    }
+
+    #[test]
+    fn test_find_overlapping_sorted() {
+        let patterns = ["abba", "baaba", "ababa", "ba", "a", "bb", "aab"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        let haystack = "abbababaabaabbbaababab";
+
+        let matches = pma.find_overlapping_sorted(haystack);
+        assert!(matches
+            .windows(2)
+            .all(|w| (w[0].start(), w[0].end()) < (w[1].start(), w[1].end())));
+
+        let mut expected: Vec<_> = pma.find_overlapping_iter(haystack).collect();
+        expected.sort_by_key(|m| (m.start(), m.end()));
+        assert_eq!(expected, matches);
+    }
+
+    #[test]
+    fn test_output_count_at() {
+        let patterns = ["a", "ab", "bab", "b", "abab"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+
+        // "abab" has suffixes "bab", "ab", and "b" in the patterns.
+        let path = pma.state_path("abab").unwrap();
+        let counts: Vec<_> = path
+            .iter()
+            .map(|&id| pma.output_count_at(usize::from_u32(id)))
+            .collect();
+        assert_eq!(vec![0, 1, 2, 1, 4], counts);
+        assert_eq!(0, pma.output_count_at(pma.states.len()));
+    }
+
+    #[test]
+    fn test_find_iter_skipping() {
+        let patterns = ["ab-c", "bd", " x"];
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .skip_bytes(b"- ")
+            .build(patterns)
+            .unwrap();
+        let haystack = "- a-b c-b--d x ab";
+        let matches: Vec<_> = pma
+            .find_iter_skipping(haystack)
+            .map(|m| (m.start(), m.end(), m.value()))
+            .collect();
+        assert_eq!(vec![(2, 7, 0), (8, 12, 1), (13, 14, 2)], matches);
+
+        // Skip bytes are stored in the extension fields of the serialized data.
+        let bytes = pma.serialize();
+        let other = DoubleArrayAhoCorasick::<u32>::try_from(bytes).unwrap();
+        assert_eq!(b" -", other.skip_bytes.as_slice());
+        assert!(pma == other);
+
+        // Without skip bytes, the search is the same as find_iter().
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        assert_eq!(
+            pma.find_iter(haystack).collect::<Vec<_>>(),
+            pma.find_iter_skipping(haystack).collect::<Vec<_>>()
+        );
+
+        // Patterns consisting only of skip bytes are empty after the normalization.
+        assert!(DoubleArrayAhoCorasickBuilder::new()
+            .skip_bytes(b"-")
+            .build::<_, _, u32>(["a", "--"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_find_all_columns() {
+        let patterns = ["a", "ab", "bab", "b", "abab"];
+        let haystack = "ababcbab";
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        for (columns, matches) in [
+            (
+                pma.find_all_columns(haystack),
+                pma.find_iter(haystack).collect::<Vec<_>>(),
+            ),
+            (
+                pma.find_overlapping_all_columns(haystack),
+                pma.find_overlapping_iter(haystack).collect(),
+            ),
+        ] {
+            let (starts, ends, values) = columns;
+            let expected: Vec<_> = matches
+                .iter()
+                .map(|m| (m.start(), m.end(), m.value()))
+                .collect();
+            let actual: Vec<_> = starts
+                .into_iter()
+                .zip(ends)
+                .zip(values)
+                .map(|((s, e), v)| (s, e, v))
+                .collect();
+            assert_eq!(expected, actual);
+        }
+
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostFirst)
+            .build(patterns)
+            .unwrap();
+        assert_eq!(
+            (vec![0, 1, 5], vec![1, 4, 8], vec![0, 2, 2]),
+            pma.leftmost_find_all_columns(haystack)
+        );
+    }
+
+    #[test]
+    fn test_build_with_stats() {
+        let patterns = ["abc", "ab", "a", "bc", "bcd", "b"];
+        let (pma, stats) = DoubleArrayAhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostFirst)
+            .build_with_stats::<_, _, u32>(patterns)
+            .unwrap();
+        assert_eq!(&[4], stats.shadowed_patterns());
+        for (i, pattern) in patterns.iter().enumerate() {
+            let reported = pma
+                .leftmost_find_iter(pattern)
+                .any(|m| m.value() == u32::try_from(i).unwrap());
+            assert_eq!(!stats.shadowed_patterns().contains(&i), reported);
+        }
+
+        for match_kind in [MatchKind::Standard, MatchKind::LeftmostLongest] {
+            let (_, stats) = DoubleArrayAhoCorasickBuilder::new()
+                .match_kind(match_kind)
+                .build_with_stats::<_, _, u32>(patterns)
+                .unwrap();
+            assert!(stats.shadowed_patterns().is_empty());
+        }
     }
 
-    #[inline(always)]
-    pub const fn output_pos(&self) -> Option<NonZeroU32> {
-        NonZeroU32::new(self.opos_ch.a().get())
+    #[test]
+    fn test_drive() {
+        // Keeps matches of even values and stops after the limit.
+        struct EvenSink {
+            matches: Vec<Match<u32>>,
+            limit: usize,
+        }
+
+        impl MatchSink<u32> for EvenSink {
+            fn push(&mut self, m: Match<u32>) -> ControlFlow<()> {
+                if m.value() % 2 == 0 {
+                    self.matches.push(m);
+                }
+                if self.matches.len() == self.limit {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            }
+        }
+
+        let patterns = ["a", "ab", "bab", "b", "abab"];
+        let haystack = "ababcbabab";
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+
+        let mut matches = vec![];
+        pma.drive(haystack, &mut matches);
+        assert_eq!(pma.find_iter(haystack).collect::<Vec<_>>(), matches);
+
+        let mut sink = EvenSink {
+            matches: vec![],
+            limit: 2,
+        };
+        pma.drive(haystack, &mut sink);
+        let expected: Vec<_> = pma
+            .find_iter(haystack)
+            .filter(|m| m.value() % 2 == 0)
+            .take(2)
+            .collect();
+        assert_eq!(expected, sink.matches);
+    }
+
+    #[test]
+    fn test_single_pattern() {
+        let haystack = "aabaaabaabaab";
+        let pma = DoubleArrayAhoCorasick::<u32>::new(["aab"]).unwrap();
+        assert_eq!(b"aab", pma.single_pattern.as_ref().unwrap().needle());
+        let matches: Vec<_> = pma.find_iter(haystack).map(|m| m.start()).collect();
+        assert_eq!(vec![0, 4, 7, 10], matches);
+        assert_eq!(haystack.len(), {
+            let mut it = pma.find_iter(haystack);
+            while it.next().is_some() {}
+            it.bytes_scanned()
+        });
+        assert!(pma.is_match(haystack));
+        assert!(!pma.is_match("abaacab"));
+
+        // The state is carried over chunks even if the pattern is not found in a chunk.
+        let mut state = FindState::default();
+        let mut chunked_matches = vec![];
+        for chunk in ["a", "abaa", "ab", "a", "aba", "ab"] {
+            let mut it = pma.find_iter_with_state(chunk, state);
+            chunked_matches.extend(it.by_ref().map(|m| m.start()));
+            state = it.into_state();
+        }
+        assert_eq!(matches, chunked_matches);
+
+        for haystack in ["", "a", "aa", "aab", "xaabaab", "aaaab", "aabaab"] {
+            assert_eq!(
+                pma.find_iter_from_iter(haystack.bytes())
+                    .collect::<Vec<_>>(),
+                pma.find_iter(haystack).collect::<Vec<_>>()
+            );
+        }
+
+        let bytes = pma.serialize();
+        let other = DoubleArrayAhoCorasick::<u32>::try_from(bytes).unwrap();
+        assert!(pma == other);
+
+        // Data in the 1.0.0 layout has no searcher, which only disables the two-way search.
+        let mut bytes = vec![];
+        pma.states.serialize_to_vec(&mut bytes);
+        pma.outputs.serialize_to_vec(&mut bytes);
+        pma.match_kind.serialize_to_vec(&mut bytes);
+        pma.num_states.serialize_to_vec(&mut bytes);
+        let other = DoubleArrayAhoCorasick::<u32>::try_from(bytes).unwrap();
+        assert!(other.single_pattern.is_none());
+        assert_eq!(
+            pma.find_iter("xaabaab").collect::<Vec<_>>(),
+            other.find_iter("xaabaab").collect::<Vec<_>>()
+        );
+
+        // Multiple patterns and prefix-only automata use the general path.
+        let pma = DoubleArrayAhoCorasick::<u32>::new(["aab", "ba"]).unwrap();
+        assert!(pma.single_pattern.is_none());
+        assert!(pma.is_match("xbax"));
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .prefix_only(true)
+            .build(["aab"])
+            .unwrap();
+        assert!(pma.single_pattern.is_none());
+
+        // Leftmost-first drops shadowed patterns, so only one pattern remains.
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostFirst)
+            .build(["ab", "abc"])
+            .unwrap();
+        assert_eq!(b"ab", pma.single_pattern.as_ref().unwrap().needle());
+        assert!(pma.is_match("xabx"));
+        assert!(!pma.is_match("xacx"));
+    }
+
+    #[test]
+    fn test_trie_edges() {
+        let patterns = ["ab", "abc", "b", "bcd", "ba"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        let edges = pma.trie_edges();
+        assert_eq!(pma.num_states() - 1, edges.len());
+
+        // Every prefix of the patterns corresponds to a path of the edges.
+        for pattern in patterns {
+            let path = pma.state_path(pattern).unwrap();
+            for (i, &c) in pattern.as_bytes().iter().enumerate() {
+                assert!(edges.contains(&(path[i], c, path[i + 1])));
+            }
+        }
+
+        // Each state except the root has exactly one parent.
+        let mut children: Vec<_> = edges.iter().map(|&(_, _, child)| child).collect();
+        children.sort_unstable();
+        children.dedup();
+        assert_eq!(edges.len(), children.len());
+        assert!(!children.contains(&ROOT_STATE_IDX));
     }
 
-    #[inline(always)]
-    pub fn set_base(&mut self, x: NonZeroU32) {
-        self.base = Some(x);
-    }
+    #[test]
+    fn test_find_overlapping_no_suffix_with_policy() {
+        let patterns = vec!["cd", "abcd", "d", "bcd", "b"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(&patterns).unwrap();
+        let collect = |policy| {
+            pma.find_overlapping_no_suffix_iter_with_policy("abcdbd", policy)
+                .map(|m| (m.start(), m.end(), m.value()))
+                .collect::<Vec<_>>()
+        };
 
-    #[inline(always)]
-    pub fn set_check(&mut self, x: u8) {
-        self.opos_ch.set_b(x);
+        assert_eq!(
+            pma.find_overlapping_no_suffix_iter("abcdbd")
+                .map(|m| (m.start(), m.end(), m.value()))
+                .collect::<Vec<_>>(),
+            collect(SuffixPolicy::Longest),
+        );
+        assert_eq!(
+            vec![(1, 2, 4), (0, 4, 1), (4, 5, 4), (5, 6, 2)],
+            collect(SuffixPolicy::Longest),
+        );
+        assert_eq!(
+            vec![(1, 2, 4), (3, 4, 2), (4, 5, 4), (5, 6, 2)],
+            collect(SuffixPolicy::Shortest),
+        );
+        assert_eq!(
+            vec![(1, 2, 4), (2, 4, 0), (4, 5, 4), (5, 6, 2)],
+            collect(SuffixPolicy::SmallestValue),
+        );
     }
 
-    #[inline(always)]
-    pub fn set_fail(&mut self, x: u32) {
-        self.fail = x;
+    #[test]
+    fn test_memory_layout() {
+        let patterns = vec!["bcd", "ab", "a", "abcde"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(&patterns).unwrap();
+        let (states_bytes, outputs_bytes) = pma.memory_layout();
+        assert_eq!(pma.states.len() * mem::size_of::<State>(), states_bytes);
+        assert_eq!(4 * mem::size_of::<Output<u32>>(), outputs_bytes);
+        assert!(states_bytes + outputs_bytes <= pma.heap_bytes());
+
+        pma.prefault();
+        assert_eq!(
+            vec![(0, 1, 2), (0, 2, 1), (0, 5, 3)],
+            pma.find_overlapping_iter("abcde")
+                .filter(|m| m.start() == 0)
+                .map(|m| (m.start(), m.end(), m.value()))
+                .collect::<Vec<_>>()
+        );
     }
 
-    #[inline(always)]
-    pub fn set_output_pos(&mut self, x: Option<NonZeroU32>) -> Result<()> {
-        let x = x.map_or(0, NonZeroU32::get);
-        if let Ok(x) = U24::try_from(x) {
-            self.opos_ch.set_a(x);
-            Ok(())
-        } else {
-            Err(DaachorseError::automaton_scale("output_pos", U24::MAX))
+    #[test]
+    fn test_find_iter_with_state() {
+        let patterns = vec!["abcab", "bca", "ca", "abx", "b"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(&patterns).unwrap();
+        let haystack = "abcabxbcabcaab";
+        let expected: Vec<_> = pma
+            .find_iter(haystack)
+            .map(|m| (m.start(), m.end(), m.value()))
+            .collect();
+        for i in 0..=haystack.len() {
+            for j in i..=haystack.len() {
+                let mut state = FindState::default();
+                let mut matches = vec![];
+                for (begin, end) in [(0, i), (i, j), (j, haystack.len())] {
+                    let mut it = pma.find_iter_with_state(&haystack[begin..end], state);
+                    matches.extend(it.by_ref().map(|m| (m.start(), m.end(), m.value())));
+                    state = it.into_state();
+                }
+                assert_eq!(expected, matches);
+            }
         }
     }
-}
 
-impl Serializable for State {
-    #[inline(always)]
-    fn serialize_to_vec(&self, dst: &mut Vec<u8>) {
-        self.base.serialize_to_vec(dst);
-        self.fail.serialize_to_vec(dst);
-        self.opos_ch.serialize_to_vec(dst);
+    #[test]
+    fn test_match_kind_accessors() {
+        let patterns = vec!["bcd", "ab", "a"];
+        for (kind, overlapping, leftmost) in [
+            (MatchKind::Standard, true, false),
+            (MatchKind::LeftmostLongest, false, true),
+            (MatchKind::LeftmostFirst, false, true),
+        ] {
+            let pma = DoubleArrayAhoCorasickBuilder::new()
+                .match_kind(kind)
+                .build::<_, _, u32>(&patterns)
+                .unwrap();
+            assert_eq!(kind, pma.match_kind());
+            assert_eq!(overlapping, pma.supports_overlapping());
+            assert_eq!(leftmost, pma.supports_leftmost());
+        }
     }
 
-    #[inline(always)]
-    fn deserialize_from_slice(src: &[u8]) -> (Self, &[u8]) {
-        let (base, src) = Option::<NonZeroU32>::deserialize_from_slice(src);
-        let (fail, src) = u32::deserialize_from_slice(src);
-        let (opos_ch, src) = U24nU8::deserialize_from_slice(src);
-        (
-            Self {
-                base,
-                fail,
-                opos_ch,
-            },
-            src,
-        )
-    }
+    #[test]
+    fn test_minimize_suffixes() {
+        // Patterns over a small alphabet whose values depend only on their last bytes, so that
+        // many suffixes can be shared.
+        let mut patvals = vec![];
+        for len in 1..=5 {
+            for i in 0..3usize.pow(len) {
+                let pattern: Vec<_> = (0..len).map(|j| b"abc"[i / 3usize.pow(j) % 3]).collect();
+                if i % 4 != 1 && i % 7 != 3 {
+                    let value = u32::from(pattern[pattern.len() - 1] == b'a');
+                    patvals.push((pattern, value));
+                }
+            }
+        }
+        let haystack: Vec<_> = (0..500u32)
+            .map(|i| b"abcabbcacba"[usize::from_u32(i * i % 11)])
+            .collect();
 
-    #[inline(always)]
-    fn serialized_bytes() -> usize {
-        Option::<NonZeroU32>::serialized_bytes()
-            + u32::serialized_bytes()
-            + U24nU8::serialized_bytes()
-    }
-}
+        for kind in [
+            MatchKind::Standard,
+            MatchKind::LeftmostLongest,
+            MatchKind::LeftmostFirst,
+        ] {
+            let builder = || {
+                DoubleArrayAhoCorasickBuilder::new()
+                    .match_kind(kind)
+                    .compress_fail(true)
+            };
+            let (pma, stats) = builder()
+                .build_with_values_and_stats(patvals.clone())
+                .unwrap();
+            assert_eq!(0, stats.num_merged_states());
+            let (min_pma, stats) = builder()
+                .minimize_suffixes(true)
+                .build_with_values_and_stats(patvals.clone())
+                .unwrap();
+            assert!(stats.num_merged_states() > 0);
+            assert_eq!(
+                pma.num_states() - stats.num_merged_states(),
+                min_pma.num_states()
+            );
+            min_pma.verify().unwrap();
 
-impl core::fmt::Debug for State {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("State")
-            .field("base", &self.base())
-            .field("check", &self.check())
-            .field("fail", &self.fail())
-            .field("output_pos", &self.output_pos())
-            .finish()
-    }
-}
+            let bytes = min_pma.serialize();
+            assert!(min_pma == DoubleArrayAhoCorasick::try_from(bytes).ok().unwrap());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+            if kind.is_standard() {
+                assert_eq!(
+                    pma.find_iter(&haystack).collect::<Vec<_>>(),
+                    min_pma.find_iter(&haystack).collect::<Vec<_>>()
+                );
+                assert_eq!(
+                    pma.find_overlapping_iter(&haystack).collect::<Vec<_>>(),
+                    min_pma.find_overlapping_iter(&haystack).collect::<Vec<_>>()
+                );
+                assert_eq!(
+                    pma.find_overlapping_no_suffix_iter(&haystack)
+                        .collect::<Vec<_>>(),
+                    min_pma
+                        .find_overlapping_no_suffix_iter(&haystack)
+                        .collect::<Vec<_>>()
+                );
+            } else {
+                assert_eq!(
+                    pma.leftmost_find_iter(&haystack).collect::<Vec<_>>(),
+                    min_pma.leftmost_find_iter(&haystack).collect::<Vec<_>>()
+                );
+            }
+            let mut expected = pma.pattern_values();
+            let mut results = min_pma.pattern_values();
+            expected.sort_unstable();
+            results.sort_unstable();
+            assert_eq!(expected, results);
+        }
 
-    #[test]
-    fn test_double_array() {
-        /*
-         *          a--> 4
-         *         /
-         *   a--> 1 --c--> 5
-         *  /
-         * 0 --b--> 2 --c--> 6
-         *  \
-         *   c--> 3
-         *
-         *   a = 0
-         *   b = 1
-         *   c = 2
-         */
-        let patterns = vec![vec![0, 0], vec![0, 2], vec![1, 2], vec![2]];
-        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        // Values of the other build methods cannot be compared.
+        let builder = || DoubleArrayAhoCorasickBuilder::new().minimize_suffixes(true);
+        let err = builder()
+            .build_with_values(patvals.clone())
+            .map(|_: DoubleArrayAhoCorasick<u32>| ())
+            .unwrap_err();
+        assert!(alloc::format!("{err}").contains("build_with_values_and_stats()"));
+        assert!(builder().build_with_wildcards(vec![("c?t", 0u32)]).is_err());
 
-        let base_expected = vec![
-            NonZeroU32::new(4), // 0  (state=0)
-            None,               // 1  (reserved)
-            None,               // 2
-            None,               // 3  (state=6)
-            NonZeroU32::new(8), // 4  (state=1)
-            NonZeroU32::new(1), // 5  (state=2)
-            None,               // 6  (state=3)
-            None,               // 7
-            None,               // 8  (state=4)
-            None,               // 9
-            None,               // 10 (state=5)
-        ];
-        let check_expected = vec![
-            0, // 0  (state=0)
-            1, // 1
-            2, // 2
-            2, // 3  (state=6)
-            0, // 4  (state=1)
-            1, // 5  (state=2)
-            2, // 6  (state=3)
-            7, // 7
-            0, // 8  (state=4)
-            9, // 9
-            2, // 10 (state=5)
-        ];
-        let fail_expected = vec![
-            ROOT_STATE_IDX, // 0  (state=0)
-            ROOT_STATE_IDX, // 1  (reserved)
-            ROOT_STATE_IDX, // 2
-            6,              // 3  (state=6)
-            ROOT_STATE_IDX, // 4  (state=1)
-            ROOT_STATE_IDX, // 5  (state=2)
-            ROOT_STATE_IDX, // 6  (state=3)
-            ROOT_STATE_IDX, // 7
-            4,              // 8  (state=4)
-            ROOT_STATE_IDX, // 9
-            6,              // 10 (state=5)
-        ];
+        // Indices are distinct, so no state is merged.
+        let patterns: Vec<_> = patvals.into_iter().map(|(pattern, _)| pattern).collect();
+        let pma: DoubleArrayAhoCorasick<u32> =
+            DoubleArrayAhoCorasick::new(patterns.clone()).unwrap();
+        let (min_pma, stats) = builder().build_with_stats::<_, _, u32>(patterns).unwrap();
+        assert_eq!(0, stats.num_merged_states());
+        assert_eq!(pma.states, min_pma.states);
+        assert_eq!(pma.outputs, min_pma.outputs);
+    }
 
-        let pma_base: Vec<_> = pma.states[0..11].iter().map(|state| state.base()).collect();
-        let pma_check: Vec<_> = pma.states[0..11]
-            .iter()
-            .map(|state| state.check())
+    #[test]
+    fn test_minimize_suffixes_large() {
+        // Each state is looked up once per class, so distinct values do not make the
+        // construction quadratic.
+        let patvals: Vec<_> = (0..50_000u32)
+            .map(|i| (alloc::format!("x{i:07}"), i % 3))
             .collect();
-        let pma_fail: Vec<_> = pma.states[0..11].iter().map(|state| state.fail()).collect();
-
-        assert_eq!(base_expected, pma_base);
-        assert_eq!(check_expected, pma_check);
-        assert_eq!(fail_expected, pma_fail);
+        let (pma, _) = DoubleArrayAhoCorasickBuilder::new()
+            .build_with_values_and_stats(patvals.clone())
+            .unwrap();
+        let (min_pma, stats) = DoubleArrayAhoCorasickBuilder::new()
+            .minimize_suffixes(true)
+            .build_with_values_and_stats(patvals)
+            .unwrap();
+        assert!(stats.num_merged_states() > 0);
+        let haystack = "x0000001x0012345x0049999x";
+        assert_eq!(
+            pma.find_overlapping_iter(haystack).collect::<Vec<_>>(),
+            min_pma.find_overlapping_iter(haystack).collect::<Vec<_>>()
+        );
     }
 
     #[test]
-    fn test_num_states() {
-        /*
-         *   b-*-a-*-a-*-b-*-a-*
-         *  /
-         * *-a-*-b-*-b-*-a-*
-         *          \
-         *           a-*-b-*-a-*
-         */
-        let patterns = vec!["abba", "baaba", "ababa"];
-        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+    fn test_find_iter_many() {
+        let patterns = vec!["abcab", "bca", "ca", "abx", "b"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(&patterns).unwrap();
+        let haystacks = vec!["abcabxbcab", "", "xyz", "ab", "cab", "bcabcaab"];
 
-        assert_eq!(13, pma.num_states());
+        let mut expected = vec![];
+        for (i, haystack) in haystacks.iter().enumerate() {
+            expected.extend(pma.find_iter(haystack).map(|m| (i, m)));
+        }
+        assert_eq!(expected, pma.find_iter_many(&haystacks).collect::<Vec<_>>());
+
+        let it = pma.find_iter_many(haystacks.iter().map(|h| h.as_bytes().to_vec()));
+        assert_eq!(expected, it.collect::<Vec<_>>());
     }
 
     #[test]
-    fn test_input_order() {
-        let patvals_sorted = vec![("ababa", 0), ("abba", 1), ("baaba", 2)];
-        let patvals_unsorted = vec![("abba", 1), ("baaba", 2), ("ababa", 0)];
+    fn test_for_each_match() {
+        let patterns = vec!["a", "ab", "bab", "b"];
+        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        let haystack = "abababcab";
 
-        let pma_sorted = DoubleArrayAhoCorasick::with_values(patvals_sorted).unwrap();
-        let pma_unsorted = DoubleArrayAhoCorasick::with_values(patvals_unsorted).unwrap();
+        let mut matches = vec![];
+        pma.for_each_match(haystack, |m| {
+            matches.push(m);
+            ControlFlow::Continue(())
+        });
+        assert_eq!(pma.find_iter(haystack).collect::<Vec<_>>(), matches);
 
-        assert_eq!(pma_sorted.states, pma_unsorted.states);
-        assert_eq!(pma_sorted.outputs, pma_unsorted.outputs);
+        let mut matches = vec![];
+        pma.for_each_overlapping_match(haystack, |m| {
+            matches.push(m);
+            ControlFlow::Continue(())
+        });
+        assert_eq!(
+            pma.find_overlapping_iter(haystack).collect::<Vec<_>>(),
+            matches
+        );
+
+        let mut matches = vec![];
+        pma.for_each_overlapping_match(haystack, |m| {
+            matches.push(m);
+            if matches.len() == 4 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(
+            pma.find_overlapping_iter(haystack)
+                .take(4)
+                .collect::<Vec<_>>(),
+            matches
+        );
     }
 
     #[test]
-    fn test_n_blocks_1_1() {
-        let mut patterns = vec![];
-        // state 0: reserved for the root state
-        // state 1: reserved for the dead state
-        // base = 0xfe; fills 0x02..=0xff
-        for i in 0x00..=0xfd {
-            let pattern = vec![i];
-            patterns.push(pattern);
+    fn test_verify() {
+        let patterns = vec!["abba", "baaba", "ababa", "ba", "a"];
+        for match_kind in [
+            MatchKind::Standard,
+            MatchKind::LeftmostLongest,
+            MatchKind::LeftmostFirst,
+        ] {
+            for compress_fail in [false, true] {
+                let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+                    .match_kind(match_kind)
+                    .compress_fail(compress_fail)
+                    .build(&patterns)
+                    .unwrap();
+                assert!(pma.verify().is_ok());
+            }
         }
-        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
-        assert_eq!(255, pma.num_states());
-        assert_eq!(256, pma.states.len());
-        assert_eq!(0xfe, pma.states[0].base().unwrap().get());
     }
 
     #[test]
-    fn test_n_blocks_1_2() {
-        let mut patterns = vec![];
-        // state 0: reserved for the root state
-        // state 1: reserved for the dead state
-        // base = 0x100; fills 0x100, 0x102, 0x104..=0x1ff
-        patterns.push(vec![0x00]);
-        patterns.push(vec![0x02]);
-        for i in 0x04..=0xff {
-            patterns.push(vec![i]);
-        }
-        let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
-        assert_eq!(255, pma.num_states());
-        assert_eq!(512, pma.states.len());
-        assert_eq!(0x100, pma.states[0].base().unwrap().get());
+    fn test_verify_broken() {
+        let patterns = vec!["abba", "baaba", "ababa", "ba", "a"];
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .compress_fail(true)
+            .build(patterns)
+            .unwrap();
+        let root_base = pma.states[0].base().unwrap().get();
+        let a_idx = usize::from_u32(root_base ^ u32::from(b'a'));
+
+        let mut broken = pma.clone();
+        broken.states.truncate(100);
+        assert!(broken.verify().is_err());
+
+        let mut broken = pma.clone();
+        let len = u32::try_from(broken.states.len()).unwrap();
+        broken.states[0].set_base(NonZeroU32::new(len).unwrap());
+        assert!(broken.verify().is_err());
+
+        let mut broken = pma.clone();
+        broken.states[a_idx].set_fail(a_idx.try_into().unwrap());
+        assert!(broken.verify().is_err());
+
+        let mut broken = pma.clone();
+        broken.states[a_idx]
+            .set_output_pos(NonZeroU32::new(100))
+            .unwrap();
+        assert!(broken.verify().is_err());
+
+        let mut broken = pma.clone();
+        let last = broken.outputs.len() - 1;
+        broken.outputs[0].parent = NonZeroU32::new(u32::try_from(last + 1).unwrap());
+        assert!(broken.verify().is_err());
+
+        let mut broken = pma.clone();
+        broken.num_states += 1;
+        assert!(broken.verify().is_err());
+
+        let mut broken = pma.clone();
+        broken.fail_table.pop();
+        assert!(broken.verify().is_err());
+
+        let mut broken = pma;
+        broken.states[usize::from_u32(DEAD_STATE_IDX)].set_fail(a_idx.try_into().unwrap());
+        assert!(broken.verify().is_err());
     }
 
     #[test]
-    fn test_n_blocks_2_1() {
-        let mut patterns = vec![];
-        // state 0: reserved for the root state
-        // state 1: reserved for the dead state
-        // base = 0x80; fills 0x80..=0xff
-        for i in 0x00..=0x7f {
-            let pattern = vec![i];
-            patterns.push(pattern);
-        }
-        // base = 0x7e; fills 0x02..=0x7f
-        for i in 0x00..=0x7d {
-            let pattern = vec![0x00, i];
-            patterns.push(pattern);
-        }
+    fn test_iter_size_hint() {
+        let patterns = vec!["a", "ab"];
         let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
-        assert_eq!(255, pma.num_states());
-        assert_eq!(256, pma.states.len());
-        assert_eq!(0x80, pma.states[0].base().unwrap().get());
-        assert_eq!(0x7e, pma.states[0x80].base().unwrap().get());
+
+        let mut it = pma.find_iter("aabx");
+        assert_eq!((0, Some(4)), it.size_hint());
+        it.next();
+        assert_eq!((0, Some(3)), it.size_hint());
+
+        let pma_suffix = DoubleArrayAhoCorasick::<u32>::new(["b", "ab"]).unwrap();
+        let mut it = pma_suffix.find_overlapping_iter("ab");
+        assert_eq!((0, None), it.size_hint());
+        it.next();
+        assert_eq!((1, None), it.size_hint());
+
+        let mut it = pma.find_overlapping_no_suffix_iter("aabx").fuse();
+        assert_eq!((0, Some(4)), it.size_hint());
+        assert_eq!(3, it.by_ref().count());
+        assert_eq!(None, it.next());
+
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(["a", "ab"])
+            .unwrap();
+        let mut it = pma.leftmost_find_iter("abax");
+        assert_eq!((0, Some(4)), it.size_hint());
+        it.next();
+        assert_eq!((0, Some(2)), it.size_hint());
+        it.next();
+        assert_eq!(None, it.next());
+        assert_eq!(None, it.next());
     }
 
+    #[cfg(feature = "unicode")]
     #[test]
-    fn test_n_blocks_2_2() {
-        let mut patterns = vec![];
-        // state 0: reserved for the root state
-        // state 1: reserved for the dead state
-        // base = 0x80; fills 0x80..=0xff
-        for i in 0..=0x7f {
-            let pattern = vec![i];
-            patterns.push(pattern);
-        }
-        // base = 0x100; fills 0x100, 0x102, 0x104..=0x17f
-        patterns.push(vec![0, 0x00]);
-        patterns.push(vec![0, 0x02]);
-        for i in 0x04..=0x7f {
-            let pattern = vec![0x00, i];
-            patterns.push(pattern);
-        }
+    fn test_find_iter_graphemes() {
+        let patterns = vec!["\u{1f44d}", "a", "\u{1f1ef}\u{1f1f5}"];
         let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
-        assert_eq!(255, pma.num_states());
-        assert_eq!(512, pma.states.len());
-        assert_eq!(0x80, pma.states[0].base().unwrap().get());
-        assert_eq!(0x100, pma.states[0x80].base().unwrap().get());
-    }
 
-    #[test]
-    fn test_serialize_state() {
-        let mut opos_ch = U24nU8::default();
-        opos_ch.set_a(U24::try_from(57).unwrap());
-        opos_ch.set_b(77);
-        let x = State {
-            base: NonZeroU32::new(42),
-            fail: 13,
-            opos_ch,
-        };
-        let mut data = vec![];
-        x.serialize_to_vec(&mut data);
-        assert_eq!(data.len(), State::serialized_bytes());
-        let (y, rest) = State::deserialize_from_slice(&data);
-        assert!(rest.is_empty());
-        assert_eq!(x, y);
+        // U+1F3FD is a skin tone modifier, and U+0300 is a combining grave accent.
+        let haystack = "\u{1f44d}\u{1f3fd} a\u{300} \u{1f44d} a \u{1f1fa}\u{1f1ef}\u{1f1f5}";
+        let matches: Vec<_> = pma
+            .find_iter_graphemes(haystack)
+            .map(|m| (m.start(), m.end(), m.value()))
+            .collect();
+        assert_eq!(vec![(13, 17, 0), (18, 19, 1)], matches);
     }
 
     #[test]
@@ -1016,5 +4799,162 @@ mod tests {
         assert_eq!(pma.outputs, other.outputs);
         assert_eq!(pma.match_kind, other.match_kind);
         assert_eq!(pma.num_states, other.num_states);
+        assert_eq!(pma.fail_ids, other.fail_ids);
+        assert_eq!(pma.fail_table, other.fail_table);
+
+        // Without the options added after 1.0.0, the layout of 1.0.0 is kept.
+        let mut expected = vec![];
+        pma.states.serialize_to_vec(&mut expected);
+        pma.outputs.serialize_to_vec(&mut expected);
+        pma.match_kind.serialize_to_vec(&mut expected);
+        pma.num_states.serialize_to_vec(&mut expected);
+        assert_eq!(expected, bytes);
+    }
+
+    #[test]
+    fn test_try_from_bytes() {
+        let patterns = vec!["abba", "baaba", "ababa"];
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .compress_fail(true)
+            .skip_bytes(b"-")
+            .build(patterns)
+            .unwrap();
+        let bytes = pma.serialize();
+        let other = DoubleArrayAhoCorasick::<u32>::try_from(&bytes[..]).unwrap();
+        assert!(pma == other);
+        let other = DoubleArrayAhoCorasick::<u32>::try_from(bytes.clone()).unwrap();
+        assert!(pma == other);
+
+        for i in 0..bytes.len() {
+            assert!(DoubleArrayAhoCorasick::<u32>::try_from(&bytes[..i]).is_err());
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(DoubleArrayAhoCorasick::<u32>::try_from(trailing).is_err());
+
+        // Sets the fail link of the root to a non-root state.
+        let mut broken = bytes.clone();
+        broken[8] = 2;
+        assert!(DoubleArrayAhoCorasick::<u32>::try_from(broken).is_err());
+
+        // Sets the match kind to an unknown value.
+        let match_kind_pos = 4
+            + pma.states.len() * State::serialized_bytes()
+            + 4
+            + pma.outputs.len() * Output::<u32>::serialized_bytes();
+        let mut broken = bytes.clone();
+        broken[match_kind_pos] = 3 | EXTENDED_FORMAT_FLAG;
+        assert!(DoubleArrayAhoCorasick::<u32>::try_from(broken).is_err());
+
+        // Sets the format version to an unknown value.
+        let mut broken = bytes;
+        broken[match_kind_pos + 5] += 1;
+        assert!(DoubleArrayAhoCorasick::<u32>::try_from(broken).is_err());
+    }
+
+    #[test]
+    fn test_serialize_pma_options() {
+        let patvals = vec![("abba", 0), ("baaba", 0), ("ababa", 1)];
+        let (pma, _) = DoubleArrayAhoCorasickBuilder::new()
+            .prefix_only(true)
+            .minimize_suffixes(true)
+            .build_with_values_and_stats(patvals)
+            .unwrap();
+        let bytes = pma.serialize();
+        let other = DoubleArrayAhoCorasick::<u32>::try_from(&bytes[..]).unwrap();
+        assert!(other.prefix_only);
+        assert!(other.minimize_suffixes);
+        assert!(pma == other);
+
+        // Sets an unknown build option.
+        let mut broken = bytes;
+        *broken.last_mut().unwrap() |= 0x80;
+        assert!(DoubleArrayAhoCorasick::<u32>::try_from(broken).is_err());
+    }
+
+    #[test]
+    fn test_serialize_pma_compress_fail() {
+        let patterns = vec!["abba", "baaba", "ababa"];
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .compress_fail(true)
+            .build(patterns)
+            .unwrap();
+        assert!(!pma.fail_ids.is_empty());
+        let bytes = pma.serialize();
+        let (other, rest) = unsafe { DoubleArrayAhoCorasick::<u32>::deserialize_unchecked(&bytes) };
+        assert!(rest.is_empty());
+        assert_eq!(pma.fail_ids, other.fail_ids);
+        assert_eq!(pma.fail_table, other.fail_table);
+        assert_eq!(pma.fail_slots, other.fail_slots);
+    }
+
+    #[test]
+    fn test_compress_fail_selection() {
+        let mut seed = 2463534242u32;
+        let mut random_bytes = |len: u32| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 17;
+                    seed ^= seed << 5;
+                    b'a' + u8::try_from(seed % 4).unwrap()
+                })
+                .collect()
+        };
+        // Shallow states rarely visited, which are more than the compressed ones.
+        let mut patterns: Vec<Vec<u8>> = vec![];
+        for b in 0x80..=0xff {
+            patterns.push(vec![b]);
+            patterns.push(vec![b'y', b]);
+            patterns.push(vec![b'z', b]);
+        }
+        // Deeper states frequently visited.
+        for len in 1..=200 {
+            patterns.push(random_bytes(len % 16 + 1));
+        }
+        patterns.sort_unstable();
+        patterns.dedup();
+        let haystack = random_bytes(10000);
+
+        let pma: DoubleArrayAhoCorasick<u32> =
+            DoubleArrayAhoCorasick::new(patterns.clone()).unwrap();
+        let pma_compressed: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .compress_fail(true)
+            .build(patterns)
+            .unwrap();
+        assert_eq!(256, pma_compressed.fail_ids.len());
+        assert!(pma_compressed.count_fail_hops(&haystack) * 4 < pma.count_fail_hops(&haystack));
+        for (i, &state_id) in pma_compressed.fail_ids.iter().enumerate() {
+            assert_eq!(
+                i + 1,
+                usize::from(pma_compressed.fail_slots[usize::from_u32(state_id)]),
+            );
+        }
+    }
+
+    #[test]
+    fn test_compress_fail_hops() {
+        let patterns = vec!["abcdefg", "bcdefg", "cdefg", "defg", "efg", "fgh"];
+        let haystack = "abcdefxabcdefgxbcdefh".repeat(100);
+
+        let pma: DoubleArrayAhoCorasick<u32> =
+            DoubleArrayAhoCorasick::new(patterns.clone()).unwrap();
+        let pma_compressed: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .compress_fail(true)
+            .build(patterns)
+            .unwrap();
+
+        let hops = pma.count_fail_hops(&haystack);
+        let hops_compressed = pma_compressed.count_fail_hops(&haystack);
+        assert_eq!(1700, hops);
+        assert_eq!(0, hops_compressed);
+
+        assert_eq!(
+            pma.find_overlapping_iter(&haystack).collect::<Vec<_>>(),
+            pma_compressed
+                .find_overlapping_iter(&haystack)
+                .collect::<Vec<_>>(),
+        );
     }
 }
@@ -1,35 +1,111 @@
 use core::num::NonZeroU32;
 
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 
+use crate::bytewise::grouped::GroupedDoubleArrayAhoCorasick;
+use crate::bytewise::memmem::TwoWay;
+use crate::bytewise::narrow::AdaptiveDoubleArrayAhoCorasick;
+use crate::bytewise::sorted::SortedDoubleArrayAhoCorasick;
+use crate::bytewise::state::DoubleArrayState;
+use crate::bytewise::wildcard;
 use crate::bytewise::{
-    BuildHelper, DoubleArrayAhoCorasick, MatchKind, State, DEAD_STATE_IDX, ROOT_STATE_IDX,
+    fail_slots, BuildHelper, DoubleArrayAhoCorasick, MatchKind, State, DEAD_STATE_IDX,
+    ROOT_STATE_IDX,
 };
 use crate::errors::{DaachorseError, Result};
 use crate::intpack::U24;
 use crate::nfa_builder::{NfaBuilder, DEAD_STATE_ID, ROOT_STATE_ID};
 use crate::utils::FromU32;
+use crate::BuildPhase;
 
 // The length of each double-array block.
-const BLOCK_LEN: u32 = 256;
+pub(crate) const BLOCK_LEN: u32 = 256;
+
+// The maximum number of states whose transitions are precomputed by `compress_fail`.
+const MAX_COMPRESSED_STATES: usize = 256;
+
+// The number of processed items between invocations of the progress callback.
+const PROGRESS_INTERVAL: usize = 1 << 16;
+// The maximum total number of byte strings patterns with wildcards can be expanded into.
+const MAX_WILDCARD_EXPANSIONS: usize = 1 << 20;
+
+// Callback specified in `progress`.
+type ProgressCallback<'a> = Box<dyn FnMut(BuildPhase, usize, usize) + 'a>;
 
 // Specialized [`NfaBuilder`] handling labels of `u8`.
 type BytewiseNfaBuilder<V> = NfaBuilder<u8, V>;
 
+// Function merging equivalent states of the NFA, returning the queue without merged states.
+type MergeFn<V> = fn(&mut BytewiseNfaBuilder<V>, &[u32]) -> Vec<u32>;
+
+// Handling of `minimize_suffixes()` depending on the values given to the build method.
+enum SuffixMerge<V> {
+    // Values cannot be compared, so the option is rejected.
+    Unsupported,
+    // Values are distinct, so no state can be merged and the pass is skipped.
+    Distinct,
+    // Values are compared in the given function.
+    ByValue(MergeFn<V>),
+}
+
+/// Statistics of the construction returned by
+/// [`DoubleArrayAhoCorasickBuilder::build_with_stats()`].
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct BuildStats {
+    shadowed_patterns: Vec<usize>,
+    num_merged_states: usize,
+}
+
+impl BuildStats {
+    /// Returns the indices of patterns that can never be reported because an earlier pattern is
+    /// their prefix, in ascending order.
+    ///
+    /// This can happen only with [`MatchKind::LeftmostFirst`]. For example, if `"ab"` is
+    /// registered before `"abc"`, `"ab"` always wins wherever `"abc"` starts, so `"abc"` is never
+    /// reported. Such patterns are omitted from the automaton. For the other match kinds, this
+    /// is always empty.
+    #[inline(always)]
+    #[must_use]
+    pub fn shadowed_patterns(&self) -> &[usize] {
+        &self.shadowed_patterns
+    }
+
+    /// Returns the number of states removed by
+    /// [`DoubleArrayAhoCorasickBuilder::minimize_suffixes()`].
+    #[inline(always)]
+    #[must_use]
+    pub const fn num_merged_states(&self) -> usize {
+        self.num_merged_states
+    }
+}
+
 /// Builder of [`DoubleArrayAhoCorasick`].
-pub struct DoubleArrayAhoCorasickBuilder {
+///
+/// The lifetime `'a` is that of the callback specified in
+/// [`progress()`](Self::progress), which can borrow from the caller.
+pub struct DoubleArrayAhoCorasickBuilder<'a> {
     states: Vec<State>,
     match_kind: MatchKind,
     num_free_blocks: u32,
+    compress_fail: bool,
+    prefix_only: bool,
+    minimize_suffixes: bool,
+    // Sorted bytes removed from patterns and ignored by find_iter_skipping().
+    skip_bytes: Vec<u8>,
+    #[cfg(feature = "std")]
+    skip_blank_lines: bool,
+    progress: Option<ProgressCallback<'a>>,
 }
 
-impl Default for DoubleArrayAhoCorasickBuilder {
+impl Default for DoubleArrayAhoCorasickBuilder<'_> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl DoubleArrayAhoCorasickBuilder {
+impl<'a> DoubleArrayAhoCorasickBuilder<'a> {
     /// Creates a new [`DoubleArrayAhoCorasickBuilder`].
     ///
     /// # Examples
@@ -58,6 +134,13 @@ impl DoubleArrayAhoCorasickBuilder {
             states: vec![],
             match_kind: MatchKind::Standard,
             num_free_blocks: 16,
+            compress_fail: false,
+            prefix_only: false,
+            minimize_suffixes: false,
+            skip_bytes: vec![],
+            #[cfg(feature = "std")]
+            skip_blank_lines: false,
+            progress: None,
         }
     }
 
@@ -114,6 +197,237 @@ impl DoubleArrayAhoCorasickBuilder {
         self
     }
 
+    /// Specifies whether to precompute direct transitions for frequently visited states.
+    ///
+    /// If enabled, the builder selects up to 256 states whose fail links do not point to the
+    /// root and stores the destination of every byte transition of them in a side table. The
+    /// search then jumps to the destination directly instead of following a chain of fail links.
+    /// The states are selected in descending order of the expected number of fail links
+    /// followed from them per byte, assuming that bytes of haystacks occur independently with
+    /// the frequencies of the labels in the trie. Each compressed state consumes additional
+    /// 1 KiB of memory, and the automaton consumes additional 2 bytes per state to look up the
+    /// table.
+    ///
+    /// [`DoubleArrayAhoCorasick::count_fail_hops()`] shows the effect on a given haystack.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to compress fail links. The default is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasickBuilder;
+    ///
+    /// let patterns = vec!["abcd", "bcd", "cd"];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .compress_fail(true)
+    ///     .build(&patterns)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_iter("abcabcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((3, 7, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    #[must_use]
+    pub const fn compress_fail(mut self, enabled: bool) -> Self {
+        self.compress_fail = enabled;
+        self
+    }
+
+    /// Specifies whether to build a plain double-array trie without fail links.
+    ///
+    /// If enabled, the construction of fail links and the propagation of outputs to longer
+    /// patterns are skipped. The resulting automaton is intended for prefix matching with
+    /// [`DoubleArrayAhoCorasick::find_anchored()`] and
+    /// [`DoubleArrayAhoCorasick::longest_prefix_match()`]. The other search methods never follow
+    /// fail links on such an automaton: on a mismatch, they restart from the root and report only
+    /// patterns registered at the reached states, so matches overlapping a failed attempt are
+    /// missed.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to skip fail links. The default is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasickBuilder;
+    ///
+    /// let patterns = vec!["a", "ab", "abcd", "bc"];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .prefix_only(true)
+    ///     .build(&patterns)
+    ///     .unwrap();
+    ///
+    /// let m = pma.longest_prefix_match("abcx").unwrap();
+    /// assert_eq!((0, 2, 1), (m.start(), m.end(), m.value()));
+    /// ```
+    #[must_use]
+    pub const fn prefix_only(mut self, enabled: bool) -> Self {
+        self.prefix_only = enabled;
+        self
+    }
+
+    /// Specifies whether to merge states that behave the same in the search, such as ones
+    /// representing common suffixes of patterns.
+    ///
+    /// Unlike a minimal automaton for membership queries, every match of this automaton reports
+    /// the value and the length of its pattern, so a state can be merged only with one reporting
+    /// the same values and lengths for all following bytes. For example, the suffixes `ing` of
+    /// `running` and `jumping` are shared if the two patterns have the same value. The search
+    /// results are unchanged. Since each state in the double array is entered by a single byte,
+    /// merged states are actually shared only among parents with the same outgoing bytes.
+    ///
+    /// Since values must be compared, the build methods taking values of an arbitrary type, e.g.,
+    /// [`DoubleArrayAhoCorasickBuilder::build_with_values()`], return an error if this option is
+    /// enabled. Use [`DoubleArrayAhoCorasickBuilder::build_with_values_and_stats()`] instead,
+    /// which requires [`Ord`] values and reports the number of removed states in
+    /// [`BuildStats::num_merged_states()`]. The build methods associating patterns with their
+    /// indices accept this option but skip merging, since no state can be merged when all values
+    /// are distinct.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to merge equivalent states. The default is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasickBuilder;
+    ///
+    /// let patvals = vec![("running", 0), ("jumping", 0), ("walking", 0), ("jump", 1)];
+    /// let (pma, stats) = DoubleArrayAhoCorasickBuilder::new()
+    ///     .minimize_suffixes(true)
+    ///     .build_with_values_and_stats(patvals)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(6, stats.num_merged_states());
+    ///
+    /// let mut it = pma.find_iter("jumping");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 4, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    #[must_use]
+    pub const fn minimize_suffixes(mut self, enabled: bool) -> Self {
+        self.minimize_suffixes = enabled;
+        self
+    }
+
+    /// Specifies whether to skip blank lines in
+    /// [`DoubleArrayAhoCorasickBuilder::build_from_reader()`].
+    ///
+    /// If disabled, a blank line is regarded as a pattern of length zero, and the construction
+    /// fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to skip blank lines. The default is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasick, DoubleArrayAhoCorasickBuilder};
+    ///
+    /// let pma: DoubleArrayAhoCorasick<usize> = DoubleArrayAhoCorasickBuilder::new()
+    ///     .skip_blank_lines(true)
+    ///     .build_from_reader("bcd\n\nab\n".as_bytes())
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 2, 1), (m.start(), m.end(), m.value()));
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    #[must_use]
+    pub const fn skip_blank_lines(mut self, enabled: bool) -> Self {
+        self.skip_blank_lines = enabled;
+        self
+    }
+
+    /// Specifies bytes to be ignored by
+    /// [`DoubleArrayAhoCorasick::find_iter_skipping()`], such as spaces and hyphens in phone
+    /// numbers.
+    ///
+    /// The bytes are removed from the patterns in the construction, so a pattern consisting only
+    /// of them is regarded as a pattern of length zero, and patterns that differ only in them are
+    /// regarded as duplicates. The other search methods do not ignore the bytes, but they search
+    /// for the patterns without the bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - Bytes to be ignored. The default is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasick, DoubleArrayAhoCorasickBuilder};
+    ///
+    /// let patterns = vec!["ab-cd"];
+    /// let pma: DoubleArrayAhoCorasick<usize> = DoubleArrayAhoCorasickBuilder::new()
+    ///     .skip_bytes(b"-")
+    ///     .build(patterns)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_iter_skipping("a-b-c-d");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 7, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    #[must_use]
+    pub fn skip_bytes(mut self, bytes: &[u8]) -> Self {
+        self.skip_bytes = bytes.to_vec();
+        self.skip_bytes.sort_unstable();
+        self.skip_bytes.dedup();
+        self
+    }
+
+    /// Specifies a callback to report the progress of the construction.
+    ///
+    /// The callback is invoked with `(phase, done, total)` at the beginning and end of each
+    /// [`BuildPhase`] and every 65536 items processed in between. It is useful for displaying a
+    /// progress bar when building an automaton from a large number of patterns.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Callback receiving the current phase, the number of processed items, and the
+    ///   total number of items in the phase.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{BuildPhase, DoubleArrayAhoCorasick, DoubleArrayAhoCorasickBuilder};
+    ///
+    /// let mut reports = vec![];
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+    ///     .progress(|phase, done, total| reports.push((phase, done, total)))
+    ///     .build(patterns)
+    ///     .unwrap();
+    ///
+    /// assert_eq!((BuildPhase::TrieInsertion, 0, 3), reports[0]);
+    /// assert_eq!((BuildPhase::TrieInsertion, 3, 3), reports[1]);
+    /// ```
+    #[must_use]
+    pub fn progress<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(BuildPhase, usize, usize) + 'a,
+    {
+        self.progress = Some(Box::new(f));
+        self
+    }
+
     /// Builds and returns a new [`DoubleArrayAhoCorasick`] from input patterns. The value `i` is
     /// automatically associated with `patterns[i]`.
     ///
@@ -157,15 +471,123 @@ impl DoubleArrayAhoCorasickBuilder {
         P: AsRef<[u8]>,
         V: Copy + TryFrom<usize>,
     {
-        // The following code implicitly replaces large indices with 0,
-        // but build_with_values() returns an error variant for such iterators.
+        self.build_with_stats(patterns).map(|(pma, _)| pma)
+    }
+
+    /// Builds and returns a new [`DoubleArrayAhoCorasick`] from input patterns along with
+    /// statistics of the construction. The value `i` is automatically associated with
+    /// `patterns[i]`.
+    ///
+    /// See [`BuildStats`] for the reported statistics. For example,
+    /// [`BuildStats::shadowed_patterns()`] helps to find patterns that silently never match
+    /// under [`MatchKind::LeftmostFirst`].
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - List of patterns.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned when
+    ///   - `patterns` is empty,
+    ///   - `patterns` contains entries of length zero,
+    ///   - `patterns` contains duplicate entries,
+    ///   - the conversion from the index `i` to the specified type `V` fails,
+    ///   - the scale of `patterns` exceeds the expected one, or
+    ///   - the scale of the resulting automaton exceeds the expected one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasick, DoubleArrayAhoCorasickBuilder, MatchKind};
+    ///
+    /// let patterns = vec!["ab", "abc", "b", "bcd", "bc"];
+    /// let (pma, stats) = DoubleArrayAhoCorasickBuilder::new()
+    ///     .match_kind(MatchKind::LeftmostFirst)
+    ///     .build_with_stats::<_, _, usize>(patterns)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(&[1, 3, 4], stats.shadowed_patterns());
+    /// ```
+    pub fn build_with_stats<I, P, V>(
+        self,
+        patterns: I,
+    ) -> Result<(DoubleArrayAhoCorasick<V>, BuildStats)>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u8]>,
+        V: Copy + TryFrom<usize>,
+    {
         let patvals: Vec<_> = patterns
             .into_iter()
             .enumerate()
             .map(|(i, p)| V::try_from(i).map(|i| (p, i)))
             .collect::<Result<_, _>>()
             .map_err(|_| DaachorseError::invalid_conversion("index", "V"))?;
-        self.build_with_values(patvals)
+        self.build_with_values_impl(patvals, SuffixMerge::Distinct)
+    }
+
+    /// Builds and returns a new [`DoubleArrayAhoCorasick`] from patterns given line by line. The
+    /// value `i` is automatically associated with the `i`-th pattern.
+    ///
+    /// Lines are separated by `\n`, and a trailing `\r` of each line is removed. Lines are
+    /// handled as byte strings, so they need not be valid UTF-8 and can contain any bytes
+    /// including `\0`. Blank lines are rejected unless
+    /// [`DoubleArrayAhoCorasickBuilder::skip_blank_lines()`] is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Reader of newline-delimited patterns.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned when
+    ///   - reading from `reader` fails,
+    ///   - `reader` contains no pattern,
+    ///   - `reader` contains blank lines and they are not skipped,
+    ///   - `reader` contains duplicate patterns,
+    ///   - the conversion from the index `i` to the specified type `V` fails,
+    ///   - the scale of patterns exceeds the expected one, or
+    ///   - the scale of the resulting automaton exceeds the expected one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasick, DoubleArrayAhoCorasickBuilder};
+    ///
+    /// let pma: DoubleArrayAhoCorasick<usize> = DoubleArrayAhoCorasickBuilder::new()
+    ///     .build_from_reader("bcd\r\nab\r\na".as_bytes())
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 1, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn build_from_reader<R, V>(self, reader: R) -> Result<DoubleArrayAhoCorasick<V>>
+    where
+        R: std::io::BufRead,
+        V: Copy + TryFrom<usize>,
+    {
+        let mut patterns = vec![];
+        for line in reader.split(b'\n') {
+            let mut line = line.map_err(DaachorseError::io)?;
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            if line.is_empty() && self.skip_blank_lines {
+                continue;
+            }
+            patterns.push(line);
+        }
+        self.build(patterns)
     }
 
     /// Builds and returns a new [`DoubleArrayAhoCorasick`] from input pattern-value pairs.
@@ -180,6 +602,7 @@ impl DoubleArrayAhoCorasickBuilder {
     ///   - `patvals` is empty,
     ///   - `patvals` contains patterns of length zero,
     ///   - `patvals` contains duplicate patterns,
+    ///   - [`DoubleArrayAhoCorasickBuilder::minimize_suffixes()`] is enabled,
     ///   - the scale of `patvals` exceeds the expected one, or
     ///   - the scale of the resulting automaton exceeds the expected one.
     ///
@@ -206,36 +629,495 @@ impl DoubleArrayAhoCorasickBuilder {
     ///
     /// assert_eq!(None, it.next());
     /// ```
-    pub fn build_with_values<I, P, V>(mut self, patvals: I) -> Result<DoubleArrayAhoCorasick<V>>
+    pub fn build_with_values<I, P, V>(self, patvals: I) -> Result<DoubleArrayAhoCorasick<V>>
     where
         I: IntoIterator<Item = (P, V)>,
         P: AsRef<[u8]>,
         V: Copy,
     {
-        let nfa = self.build_sparse_nfa(patvals)?;
-        self.build_double_array(&nfa)?;
+        self.build_with_values_impl(patvals, SuffixMerge::Unsupported)
+            .map(|(pma, _)| pma)
+    }
+
+    /// Builds and returns a new [`DoubleArrayAhoCorasick`] from input pattern-value pairs along
+    /// with statistics of the construction.
+    ///
+    /// This method compares values with [`Ord`], so it supports
+    /// [`DoubleArrayAhoCorasickBuilder::minimize_suffixes()`]. See [`BuildStats`] for the
+    /// reported statistics.
+    ///
+    /// # Arguments
+    ///
+    /// * `patvals` - List of pattern-value pairs.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned when
+    ///   - `patvals` is empty,
+    ///   - `patvals` contains patterns of length zero,
+    ///   - `patvals` contains duplicate patterns,
+    ///   - the scale of `patvals` exceeds the expected one, or
+    ///   - the scale of the resulting automaton exceeds the expected one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasickBuilder;
+    ///
+    /// let patvals = vec![("cat", 'a'), ("bat", 'a'), ("rat", 'b')];
+    /// let (pma, stats) = DoubleArrayAhoCorasickBuilder::new()
+    ///     .minimize_suffixes(true)
+    ///     .build_with_values_and_stats(patvals)
+    ///     .unwrap();
+    ///
+    /// // "at" of "cat" and "bat" is shared.
+    /// assert_eq!(2, stats.num_merged_states());
+    /// assert_eq!(8, pma.num_states());
+    ///
+    /// let mut it = pma.find_iter("bat");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 3, 'a'), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn build_with_values_and_stats<I, P, V>(
+        self,
+        patvals: I,
+    ) -> Result<(DoubleArrayAhoCorasick<V>, BuildStats)>
+    where
+        I: IntoIterator<Item = (P, V)>,
+        P: AsRef<[u8]>,
+        V: Copy + Ord,
+    {
+        self.build_with_values_impl(
+            patvals,
+            SuffixMerge::ByValue(BytewiseNfaBuilder::merge_equivalent_states),
+        )
+    }
 
+    /// Builds an automaton, merging equivalent states as specified by `merge` if
+    /// [`DoubleArrayAhoCorasickBuilder::minimize_suffixes()`] is enabled. An error is returned if
+    /// it is enabled but values cannot be compared.
+    fn build_with_values_impl<I, P, V>(
+        mut self,
+        patvals: I,
+        merge: SuffixMerge<V>,
+    ) -> Result<(DoubleArrayAhoCorasick<V>, BuildStats)>
+    where
+        I: IntoIterator<Item = (P, V)>,
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        let merge = match merge {
+            SuffixMerge::Unsupported if self.minimize_suffixes => {
+                return Err(DaachorseError::unsupported_argument(
+                    "minimize_suffixes",
+                    "requires values compared with Ord; use build_with_values_and_stats()",
+                ));
+            }
+            SuffixMerge::ByValue(merge) if self.minimize_suffixes => Some(merge),
+            _ => None,
+        };
+        let nfa = if self.skip_bytes.is_empty() {
+            self.build_sparse_nfa(patvals, merge)?
+        } else {
+            let skip_bytes = self.skip_bytes.clone();
+            self.build_sparse_nfa(
+                patvals.into_iter().map(|(pattern, value)| {
+                    let pattern: Vec<_> = pattern
+                        .as_ref()
+                        .iter()
+                        .copied()
+                        .filter(|c| skip_bytes.binary_search(c).is_err())
+                        .collect();
+                    (pattern, value)
+                }),
+                merge,
+            )?
+        };
+        let num_states = self.build_double_array(&nfa, merge.is_some())?;
         // -1 is for dead state
-        let num_states = u32::try_from(nfa.states.len() - 1)
-            .map_err(|_| DaachorseError::automaton_scale("num_states", u32::MAX))?;
+        let num_merged_states = nfa.states.len() - 1 - usize::from_u32(num_states);
 
-        Ok(DoubleArrayAhoCorasick {
+        // The pattern is reconstructed from the trie, which is a single path in this case.
+        let mut single_pattern = None;
+        if nfa.len == 1 && !self.prefix_only {
+            let mut pattern = vec![];
+            let mut state_id = ROOT_STATE_ID;
+            while let Some((&c, &child_id)) = nfa.states[usize::from_u32(state_id)]
+                .borrow()
+                .edges
+                .iter()
+                .next()
+            {
+                pattern.push(c);
+                state_id = child_id;
+            }
+            single_pattern = Some(TwoWay::new(pattern));
+        }
+
+        let mut pma = DoubleArrayAhoCorasick {
             states: self.states,
             outputs: nfa.outputs,
             match_kind: self.match_kind,
             num_states,
-        })
+            fail_ids: vec![],
+            fail_table: vec![],
+            fail_slots: vec![],
+            skip_bytes: self.skip_bytes,
+            single_pattern,
+            prefix_only: self.prefix_only,
+            minimize_suffixes: self.minimize_suffixes,
+        };
+        if self.compress_fail {
+            Self::build_fail_table(&mut pma);
+        }
+        let stats = BuildStats {
+            shadowed_patterns: nfa.shadowed,
+            num_merged_states,
+        };
+        Ok((pma, stats))
+    }
+
+    /// Builds and returns a new [`DoubleArrayAhoCorasick`] from input triples of a pattern, a
+    /// value, and a priority.
+    ///
+    /// When [`MatchKind::LeftmostFirst`] is specified, the pattern with the smallest priority is
+    /// reported among ones starting from the same position, instead of the earliest registered
+    /// one. Patterns with the same priority are ordered by their positions in the input. For the
+    /// other match kinds, priorities do not affect the search results.
+    ///
+    /// # Arguments
+    ///
+    /// * `patvals_priorities` - List of pattern-value-priority triples.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned when
+    ///   - `patvals_priorities` is empty,
+    ///   - `patvals_priorities` contains patterns of length zero,
+    ///   - `patvals_priorities` contains duplicate patterns,
+    ///   - the scale of `patvals_priorities` exceeds the expected one, or
+    ///   - the scale of the resulting automaton exceeds the expected one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasickBuilder, MatchKind};
+    ///
+    /// let patvals_priorities = vec![("ab", 0, 2), ("a", 1, 1), ("abcd", 2, 0)];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .match_kind(MatchKind::LeftmostFirst)
+    ///     .build_with_values_and_priorities(patvals_priorities)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.leftmost_find_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 4, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn build_with_values_and_priorities<I, P, V>(
+        self,
+        patvals_priorities: I,
+    ) -> Result<DoubleArrayAhoCorasick<V>>
+    where
+        I: IntoIterator<Item = (P, V, u32)>,
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        // The leftmost-first semantics is determined by the insertion order, so patterns are
+        // inserted in ascending order of priority. The sort is stable to keep the input order of
+        // patterns with the same priority.
+        let mut patvals_priorities: Vec<_> = patvals_priorities.into_iter().collect();
+        patvals_priorities.sort_by_key(|&(_, _, priority)| priority);
+        self.build_with_values(
+            patvals_priorities
+                .into_iter()
+                .map(|(pattern, value, _)| (pattern, value)),
+        )
+    }
+
+    /// Builds and returns a new [`DoubleArrayAhoCorasick`] from input pairs of a pattern with
+    /// wildcards and a value.
+    ///
+    /// Each pattern is expanded into the byte strings it matches, which share the value of the
+    /// pattern. The following syntax is supported:
+    ///
+    /// - `?` matches any single byte.
+    /// - `[abc]` matches any single byte in the brackets.
+    /// - `\x` matches the byte `x` literally, e.g., `\?`, `\[`, or `\\`.
+    ///
+    /// Since the number of byte strings grows exponentially with the number of wildcards, all
+    /// patterns can be expanded into at most 2^20 byte strings in total, e.g., 16 patterns with
+    /// two `?`s each.
+    ///
+    /// # Arguments
+    ///
+    /// * `patvals` - List of pairs of a pattern with wildcards and a value.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned when
+    ///   - `patvals` contains malformed patterns,
+    ///   - `patvals` is expanded into more than 2^20 byte strings in total,
+    ///   - `patvals` contains patterns expanded into the same byte string, or
+    ///   - [`DoubleArrayAhoCorasickBuilder::build_with_values()`] fails with the expanded pairs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasickBuilder;
+    ///
+    /// let patvals = vec![("c[ao]t", 0), ("d?g", 1)];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .build_with_wildcards(patvals)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_iter("cot dig cut");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 3, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((4, 7, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn build_with_wildcards<I, P, V>(self, patvals: I) -> Result<DoubleArrayAhoCorasick<V>>
+    where
+        I: IntoIterator<Item = (P, V)>,
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        let mut expanded = vec![];
+        for (i, (pattern, value)) in patvals.into_iter().enumerate() {
+            let patterns =
+                wildcard::expand(pattern.as_ref(), MAX_WILDCARD_EXPANSIONS - expanded.len())
+                    .map_err(|reason| DaachorseError::invalid_wildcard(i, reason))?;
+            expanded.extend(patterns.into_iter().map(|pattern| (pattern, value)));
+        }
+        self.build_with_values(expanded)
+    }
+
+    /// Builds and returns a new [`SortedDoubleArrayAhoCorasick`] from input pattern-value pairs
+    /// whose values are in ascending order.
+    ///
+    /// The values are stored as differences from the first value in each block, which reduces
+    /// the memory usage when the values are dense, such as sorted identifiers.
+    ///
+    /// # Arguments
+    ///
+    /// * `patvals` - List of pattern-value pairs sorted by value in ascending order.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned when
+    ///   - `patvals` is empty,
+    ///   - `patvals` contains values not in ascending order,
+    ///   - `patvals` contains patterns of length zero,
+    ///   - `patvals` contains duplicate patterns,
+    ///   - the scale of `patvals` exceeds the expected one, or
+    ///   - the scale of the resulting automaton exceeds the expected one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasickBuilder;
+    ///
+    /// let patvals = vec![("bcd", 100), ("ab", 101), ("a", 105)];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .build_from_sorted_pairs(patvals)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 1, 105), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 100), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn build_from_sorted_pairs<I, P>(self, patvals: I) -> Result<SortedDoubleArrayAhoCorasick>
+    where
+        I: IntoIterator<Item = (P, u32)>,
+        P: AsRef<[u8]>,
+    {
+        let mut patterns = vec![];
+        let mut values: Vec<u32> = vec![];
+        for (pattern, value) in patvals {
+            if let Some(&prev) = values.last() {
+                if value < prev {
+                    return Err(DaachorseError::invalid_argument("value", ">=", prev));
+                }
+            }
+            patterns.push(pattern);
+            values.push(value);
+        }
+        // Each pattern is associated with its index so that outputs can be rearranged in the
+        // input order.
+        let pma: DoubleArrayAhoCorasick<u32> = self.build(patterns)?;
+        SortedDoubleArrayAhoCorasick::new(pma, &values)
     }
 
-    fn build_sparse_nfa<I, P, V>(&self, patvals: I) -> Result<BytewiseNfaBuilder<V>>
+    /// Builds and returns a new [`GroupedDoubleArrayAhoCorasick`] from input
+    /// pattern-value-group triples.
+    ///
+    /// In the non-overlapping search of the resulting automaton, a match suppresses subsequent
+    /// overlapping matches only if they are of the same group.
+    ///
+    /// # Arguments
+    ///
+    /// * `patvals_groups` - List of triples of a pattern, its value, and its group ID.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned when
+    ///   - `patvals_groups` is empty,
+    ///   - `patvals_groups` contains patterns of length zero,
+    ///   - `patvals_groups` contains duplicate patterns,
+    ///   - the scale of `patvals_groups` exceeds the expected one, or
+    ///   - the scale of the resulting automaton exceeds the expected one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasickBuilder;
+    ///
+    /// let patvals_groups = vec![("abc", 10, 0), ("bcd", 11, 0), ("cd", 20, 1)];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .build_with_groups(patvals_groups)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_iter("abcd");
+    ///
+    /// let (m, group) = it.next().unwrap();
+    /// assert_eq!((0, 3, 10, 0), (m.start(), m.end(), m.value(), group));
+    ///
+    /// let (m, group) = it.next().unwrap();
+    /// assert_eq!((2, 4, 20, 1), (m.start(), m.end(), m.value(), group));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn build_with_groups<I, P>(self, patvals_groups: I) -> Result<GroupedDoubleArrayAhoCorasick>
+    where
+        I: IntoIterator<Item = (P, u32, u32)>,
+        P: AsRef<[u8]>,
+    {
+        let mut patterns = vec![];
+        let mut values = vec![];
+        let mut groups = vec![];
+        for (pattern, value, group) in patvals_groups {
+            patterns.push(pattern);
+            values.push(value);
+            groups.push(group);
+        }
+        // Each pattern is associated with its index to look up its value and group.
+        let pma: DoubleArrayAhoCorasick<u32> = self.build(patterns)?;
+        Ok(GroupedDoubleArrayAhoCorasick::new(pma, values, groups))
+    }
+
+    /// Builds and returns a new automaton from input patterns, using 16-bit indices if the
+    /// resulting automaton is small enough. The value `i` is automatically associated with
+    /// `patterns[i]`.
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - List of patterns.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned under the same conditions as
+    /// [`DoubleArrayAhoCorasickBuilder::build()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{bytewise::AdaptiveDoubleArrayAhoCorasick, DoubleArrayAhoCorasickBuilder};
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .build_adaptive(patterns)
+    ///     .unwrap();
+    ///
+    /// if let AdaptiveDoubleArrayAhoCorasick::Narrow(pma) = pma {
+    ///     let mut it = pma.find_iter("abcd");
+    ///
+    ///     let m = it.next().unwrap();
+    ///     assert_eq!((0, 1, 2), (m.start(), m.end(), m.value()));
+    ///
+    ///     let m = it.next().unwrap();
+    ///     assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    ///
+    ///     assert_eq!(None, it.next());
+    /// } else {
+    ///     unreachable!();
+    /// }
+    /// ```
+    pub fn build_adaptive<I, P, V>(self, patterns: I) -> Result<AdaptiveDoubleArrayAhoCorasick<V>>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u8]>,
+        V: Copy + TryFrom<usize>,
+    {
+        self.build(patterns)
+            .map(AdaptiveDoubleArrayAhoCorasick::from)
+    }
+
+    /// Builds and returns a new automaton from input pattern-value pairs, using 16-bit indices
+    /// if the resulting automaton is small enough.
+    ///
+    /// # Arguments
+    ///
+    /// * `patvals` - List of pattern-value pairs.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned under the same conditions as
+    /// [`DoubleArrayAhoCorasickBuilder::build_with_values()`].
+    pub fn build_adaptive_with_values<I, P, V>(
+        self,
+        patvals: I,
+    ) -> Result<AdaptiveDoubleArrayAhoCorasick<V>>
+    where
+        I: IntoIterator<Item = (P, V)>,
+        P: AsRef<[u8]>,
+        V: Copy,
+    {
+        self.build_with_values(patvals)
+            .map(AdaptiveDoubleArrayAhoCorasick::from)
+    }
+
+    fn build_sparse_nfa<I, P, V>(
+        &mut self,
+        patvals: I,
+        merge: Option<MergeFn<V>>,
+    ) -> Result<BytewiseNfaBuilder<V>>
     where
         I: IntoIterator<Item = (P, V)>,
         P: AsRef<[u8]>,
         V: Copy,
     {
         let mut nfa = BytewiseNfaBuilder::new(self.match_kind);
+        let patvals = patvals.into_iter();
+        let total = match patvals.size_hint() {
+            (lower, Some(upper)) if lower == upper => upper,
+            _ => 0,
+        };
+        self.report_progress(BuildPhase::TrieInsertion, 0, total);
+        let mut done = 0;
         for (pattern, value) in patvals {
             nfa.add(pattern.as_ref(), value)?;
+            done += 1;
+            self.report_progress(BuildPhase::TrieInsertion, done, total);
+        }
+        if total == 0 {
+            // The total becomes known at the end.
+            self.report_progress(BuildPhase::TrieInsertion, done, done);
         }
         if nfa.len == 0 {
             return Err(DaachorseError::invalid_argument("patvals.len()", ">=", 1));
@@ -243,36 +1125,80 @@ impl DoubleArrayAhoCorasickBuilder {
         if nfa.len > usize::from_u32(U24::MAX) {
             return Err(DaachorseError::automaton_scale("patvals.len()", U24::MAX));
         }
-        let q = match self.match_kind {
-            MatchKind::Standard => nfa.build_fails(),
-            MatchKind::LeftmostLongest | MatchKind::LeftmostFirst => nfa.build_fails_leftmost(),
+        // -2 is for the root and dead states.
+        let total = nfa.states.len() - 2;
+        let (match_kind, prefix_only) = (self.match_kind, self.prefix_only);
+        let mut report = |phase, done| self.report_progress(phase, done, total);
+        report(BuildPhase::FailConstruction, 0);
+        let q = match match_kind {
+            _ if prefix_only => nfa.build_queue(|done| report(BuildPhase::FailConstruction, done)),
+            MatchKind::Standard => {
+                nfa.build_fails(|done| report(BuildPhase::FailConstruction, done))
+            }
+            MatchKind::LeftmostLongest | MatchKind::LeftmostFirst => {
+                nfa.build_fails_leftmost(|done| report(BuildPhase::FailConstruction, done))
+            }
         };
-        nfa.build_outputs(&q);
+        let q = match merge {
+            Some(merge) => merge(&mut nfa, &q),
+            None => q,
+        };
+        report(BuildPhase::OutputLinking, 0);
+        nfa.build_outputs(&q, |done| report(BuildPhase::OutputLinking, done));
+        if q.len() < total {
+            // Merged states are not linked.
+            report(BuildPhase::OutputLinking, total);
+        }
         Ok(nfa)
     }
 
-    fn build_double_array<V>(&mut self, nfa: &BytewiseNfaBuilder<V>) -> Result<()> {
+    /// Arranges the states of the NFA in the double array, and returns the number of states
+    /// except the dead state.
+    ///
+    /// If `share_children` is enabled, states with the same edges, which appear after
+    /// [`NfaBuilder::merge_equivalent_states()`], share the base value and thus the children.
+    fn build_double_array<V>(
+        &mut self,
+        nfa: &BytewiseNfaBuilder<V>,
+        share_children: bool,
+    ) -> Result<u32> {
         let mut helper = self.init_array()?;
 
+        // A state of the NFA can be arranged at several indices if it is a child of states with
+        // different edges. The first index is used as the target of fail links.
         let mut state_id_map = vec![DEAD_STATE_IDX; nfa.states.len()];
         state_id_map[usize::from_u32(ROOT_STATE_ID)] = ROOT_STATE_IDX;
+        let mut arranged = vec![(ROOT_STATE_ID, ROOT_STATE_IDX)];
+        let mut shared_bases = BTreeMap::new();
 
         // Arranges base & check values
-        let mut stack = vec![ROOT_STATE_ID];
+        let mut stack = vec![(ROOT_STATE_ID, ROOT_STATE_IDX)];
         let mut labels = vec![];
 
-        while let Some(state_id) = stack.pop() {
+        // -1 is for the dead state.
+        let total = nfa.states.len() - 1;
+        let mut done = 0;
+        self.report_progress(BuildPhase::BaseAssignment, done, total);
+
+        while let Some((state_id, state_idx)) = stack.pop() {
+            done += 1;
+            self.report_progress(BuildPhase::BaseAssignment, done, total);
             debug_assert_ne!(state_id, DEAD_STATE_ID);
+            debug_assert_ne!(state_idx, DEAD_STATE_IDX);
             let state = &nfa.states[usize::from_u32(state_id)];
 
-            let state_idx = usize::from_u32(state_id_map[usize::from_u32(state_id)]);
-            debug_assert_ne!(state_idx, usize::from_u32(DEAD_STATE_IDX));
-
             let s = &state.borrow();
             if s.edges.is_empty() {
                 continue;
             }
 
+            if share_children {
+                if let Some(&base) = shared_bases.get(&s.edges) {
+                    self.states[usize::from_u32(state_idx)].set_base(base);
+                    continue;
+                }
+            }
+
             labels.clear();
             s.edges.keys().for_each(|&k| labels.push(k));
 
@@ -285,23 +1211,28 @@ impl DoubleArrayAhoCorasickBuilder {
                 let child_idx = base.get() ^ u32::from(c);
                 helper.use_index(child_idx);
                 self.states[usize::from_u32(child_idx)].set_check(c);
-                state_id_map[usize::from_u32(child_id)] = child_idx;
-                stack.push(child_id);
+                let first_idx = &mut state_id_map[usize::from_u32(child_id)];
+                if *first_idx == DEAD_STATE_IDX {
+                    *first_idx = child_idx;
+                }
+                arranged.push((child_id, child_idx));
+                stack.push((child_id, child_idx));
             }
-            self.states[state_idx].set_base(base);
+            self.states[usize::from_u32(state_idx)].set_base(base);
             helper.use_base(base);
+            if share_children {
+                shared_bases.insert(s.edges.clone(), base);
+            }
+        }
+        if done < total {
+            // Merged states are not arranged.
+            self.report_progress(BuildPhase::BaseAssignment, total, total);
         }
 
         // Sets fail & output_pos values
-        for (i, state) in nfa.states.iter().enumerate() {
-            if i == usize::from_u32(DEAD_STATE_ID) {
-                continue;
-            }
-
-            let idx = usize::from_u32(state_id_map[i]);
-            debug_assert_ne!(idx, usize::from_u32(DEAD_STATE_IDX));
-
-            let s = &state.borrow();
+        for &(state_id, idx) in &arranged {
+            let idx = usize::from_u32(idx);
+            let s = &nfa.states[usize::from_u32(state_id)].borrow();
             self.states[idx].set_output_pos(s.output_pos)?;
 
             let fail_id = s.fail;
@@ -319,7 +1250,143 @@ impl DoubleArrayAhoCorasickBuilder {
         }
         self.states.shrink_to_fit();
 
-        Ok(())
+        u32::try_from(arranged.len())
+            .map_err(|_| DaachorseError::automaton_scale("num_states", u32::MAX))
+    }
+
+    #[inline(always)]
+    fn report_progress(&mut self, phase: BuildPhase, done: usize, total: usize) {
+        if let Some(f) = self.progress.as_mut() {
+            if done % PROGRESS_INTERVAL == 0 || done == total {
+                f(phase, done, total);
+            }
+        }
+    }
+
+    /// Precomputes all transitions of states frequently visited in the search.
+    fn build_fail_table<V>(pma: &mut DoubleArrayAhoCorasick<V>) {
+        // Lists states in the breadth-first order, in which fail states precede the states
+        // linking to them.
+        let mut parents = vec![ROOT_STATE_IDX; pma.states.len()];
+        let mut visited = vec![false; pma.states.len()];
+        visited[usize::from_u32(ROOT_STATE_IDX)] = true;
+        let mut q = vec![ROOT_STATE_IDX];
+        let mut qi = 0;
+        while qi < q.len() {
+            let state_id = q[qi];
+            qi += 1;
+            for c in u8::MIN..=u8::MAX {
+                // state_id is always smaller than pma.states.len() because it is the root or
+                // a value returned by child_index_unchecked().
+                if let Some(child_id) = unsafe { pma.child_index_unchecked(state_id, c) } {
+                    // A child can be shared if the automaton is built with minimize_suffixes().
+                    if !visited[usize::from_u32(child_id)] {
+                        visited[usize::from_u32(child_id)] = true;
+                        parents[usize::from_u32(child_id)] = state_id;
+                        q.push(child_id);
+                    }
+                }
+            }
+        }
+
+        // The search is modeled as reading bytes independently with the frequencies of the edge
+        // labels in the trie. The score of a state is the expected number of fail links followed
+        // from it per byte of a haystack, i.e., the probability of visiting it multiplied by the
+        // expected number of hops from it, which the compression saves.
+        let mut freqs = [0f64; 256];
+        for &state_id in &q[1..] {
+            freqs[usize::from(pma.states[usize::from_u32(state_id)].check())] += 1.;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let num_edges = (q.len() - 1) as f64;
+        for freq in &mut freqs {
+            *freq /= num_edges;
+        }
+        let labels: Vec<u8> = (u8::MIN..=u8::MAX)
+            .filter(|&c| freqs[usize::from(c)] != 0.)
+            .collect();
+        let is_terminal = |state_id: u32| state_id == ROOT_STATE_IDX || state_id == DEAD_STATE_IDX;
+        // state_id is always smaller than pma.states.len() because it is the root or a value
+        // returned by child_index_unchecked() or fail().
+        let has_child = |state_id: u32, c: u8| unsafe {
+            !is_terminal(state_id) && pma.child_index_unchecked(state_id, c).is_some()
+        };
+        // Returns the number of hops from the state without the child of the label.
+        let hops = |mut state_id: u32, c: u8| {
+            let mut num_hops = 0;
+            loop {
+                state_id = pma.states[usize::from_u32(state_id)].fail();
+                num_hops += 1;
+                if is_terminal(state_id) || has_child(state_id, c) {
+                    return num_hops;
+                }
+            }
+        };
+        let mut probs = vec![0f64; pma.states.len()];
+        probs[usize::from_u32(ROOT_STATE_IDX)] = 1.;
+        let mut expected_hops = vec![0f64; pma.states.len()];
+        let mut scores = vec![0f64; pma.states.len()];
+        for &state_id in &q[1..] {
+            let state = &pma.states[usize::from_u32(state_id)];
+            let fail_id = state.fail();
+            let parent_prob = probs[usize::from_u32(parents[usize::from_u32(state_id)])];
+            let prob = parent_prob * freqs[usize::from(state.check())];
+            // The first hop is taken for every missing label, and the following ones are those
+            // from the fail state, except for the labels the state has.
+            let mut h = 1.;
+            if !is_terminal(fail_id) {
+                h += expected_hops[usize::from_u32(fail_id)];
+            }
+            for &c in &labels {
+                if has_child(state_id, c) {
+                    h -= freqs[usize::from(c)];
+                    if !is_terminal(fail_id) && !has_child(fail_id, c) {
+                        #[allow(clippy::cast_precision_loss)]
+                        let num_hops = hops(fail_id, c) as f64;
+                        h -= freqs[usize::from(c)] * num_hops;
+                    }
+                }
+            }
+            probs[usize::from_u32(state_id)] = prob;
+            expected_hops[usize::from_u32(state_id)] = h;
+            scores[usize::from_u32(state_id)] = prob * h;
+        }
+
+        // If the fail link points to the root or the dead state, the next state is always found
+        // in at most one hop, so the compression is unprofitable.
+        let mut fail_ids: Vec<_> = q[1..]
+            .iter()
+            .copied()
+            .filter(|&state_id| !is_terminal(pma.states[usize::from_u32(state_id)].fail()))
+            .collect();
+        // The sort is stable, so shallower states are preferred among equal scores.
+        fail_ids.sort_by(|&a, &b| {
+            scores[usize::from_u32(b)]
+                .partial_cmp(&scores[usize::from_u32(a)])
+                .unwrap_or(core::cmp::Ordering::Equal)
+        });
+        fail_ids.truncate(MAX_COMPRESSED_STATES);
+        fail_ids.sort_unstable();
+
+        let mut fail_table = Vec::with_capacity(fail_ids.len() * 256);
+        for &state_id in &fail_ids {
+            for c in u8::MIN..=u8::MAX {
+                // state_id is always smaller than pma.states.len() because it is a value
+                // returned by child_index_unchecked().
+                let next_state_id = unsafe {
+                    if pma.match_kind.is_leftmost() {
+                        pma.next_state_id_leftmost_unchecked(state_id, c)
+                    } else {
+                        pma.next_state_id_unchecked(state_id, c)
+                    }
+                };
+                fail_table.push(next_state_id);
+            }
+        }
+
+        pma.fail_slots = fail_slots(pma.states.len(), &fail_ids);
+        pma.fail_ids = fail_ids;
+        pma.fail_table = fail_table;
     }
 
     fn init_array(&mut self) -> Result<BuildHelper> {
@@ -0,0 +1,247 @@
+//! A byte-wise automaton whose patterns suppress overlapping matches only within their groups.
+
+use core::mem;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::bytewise::iter::{FindOverlappingIterator, U8SliceIterator};
+use crate::bytewise::DoubleArrayAhoCorasick;
+use crate::utils::FromU32;
+use crate::{Match, MatchKind};
+
+/// A variant of [`DoubleArrayAhoCorasick`] whose patterns are partitioned into groups.
+///
+/// In the non-overlapping search of this automaton, a match suppresses subsequent matches
+/// overlapping with it only if they are of the same group. Matches of different groups are
+/// reported independently even if they overlap.
+///
+/// This automaton is built by
+/// [`DoubleArrayAhoCorasickBuilder::build_with_groups()`](super::DoubleArrayAhoCorasickBuilder::build_with_groups).
+#[derive(Clone)]
+pub struct GroupedDoubleArrayAhoCorasick {
+    pma: DoubleArrayAhoCorasick<u32>,
+    values: Vec<u32>,
+    groups: Vec<u32>,
+    group_indices: Vec<u32>,
+    num_groups: usize,
+}
+
+impl GroupedDoubleArrayAhoCorasick {
+    /// Creates a new automaton from an automaton associating each pattern with its index in the
+    /// input, and the values and group IDs of the patterns in the input order.
+    pub(crate) fn new(
+        pma: DoubleArrayAhoCorasick<u32>,
+        values: Vec<u32>,
+        groups: Vec<u32>,
+    ) -> Self {
+        // Group IDs are mapped to dense indices so that iterators can track them in a vector.
+        let mut dense_ids = BTreeMap::new();
+        let group_indices = groups
+            .iter()
+            .map(|&group| {
+                let next_idx = u32::try_from(dense_ids.len()).unwrap();
+                *dense_ids.entry(group).or_insert(next_idx)
+            })
+            .collect();
+        Self {
+            pma,
+            values,
+            groups,
+            group_indices,
+            num_groups: dense_ids.len(),
+        }
+    }
+
+    /// Returns an iterator of matches in the given haystack, where matches are non-overlapping
+    /// within each group.
+    ///
+    /// Each match is reported together with the group ID of its pattern. Within a group, the
+    /// matches are the same as those of [`DoubleArrayAhoCorasick::find_iter()`] over the
+    /// patterns of the group. The matches of all groups are reported in the order of their end
+    /// positions.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasickBuilder;
+    ///
+    /// let patvals_groups = vec![("abc", 10, 0), ("bcd", 11, 0), ("cd", 20, 1)];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .build_with_groups(patvals_groups)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_iter("abcd");
+    ///
+    /// let (m, group) = it.next().unwrap();
+    /// assert_eq!((0, 3, 10, 0), (m.start(), m.end(), m.value(), group));
+    ///
+    /// // "bcd" overlaps with "abc" of the same group, but "cd" is of another group.
+    /// let (m, group) = it.next().unwrap();
+    /// assert_eq!((2, 4, 20, 1), (m.start(), m.end(), m.value(), group));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_iter<P>(&self, haystack: P) -> FindIterator<'_, P>
+    where
+        P: AsRef<[u8]>,
+    {
+        assert!(
+            self.pma.match_kind.is_standard(),
+            "Error: match_kind must be standard."
+        );
+        FindIterator {
+            pma: self,
+            it: self.pma.find_overlapping_iter(haystack),
+            last_ends: vec![0; self.num_groups],
+        }
+    }
+
+    /// Returns the number of distinct groups.
+    #[must_use]
+    pub const fn num_groups(&self) -> usize {
+        self.num_groups
+    }
+
+    /// Returns the total amount of heap used by this automaton in bytes.
+    #[must_use]
+    pub fn heap_bytes(&self) -> usize {
+        self.pma.heap_bytes()
+            + self.values.len() * mem::size_of::<u32>()
+            + self.groups.len() * mem::size_of::<u32>()
+            + self.group_indices.len() * mem::size_of::<u32>()
+    }
+
+    /// Returns the total number of states this automaton has.
+    #[must_use]
+    pub fn num_states(&self) -> usize {
+        self.pma.num_states()
+    }
+
+    /// Returns the [`MatchKind`] specified in the construction.
+    #[inline(always)]
+    #[must_use]
+    pub fn match_kind(&self) -> MatchKind {
+        self.pma.match_kind
+    }
+}
+
+impl core::fmt::Debug for GroupedDoubleArrayAhoCorasick {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("GroupedDoubleArrayAhoCorasick")
+            .field("match_kind", &self.pma.match_kind)
+            .field("num_states", &self.pma.num_states)
+            .field("num_groups", &self.num_groups)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Iterator created by [`GroupedDoubleArrayAhoCorasick::find_iter()`].
+pub struct FindIterator<'a, P> {
+    pma: &'a GroupedDoubleArrayAhoCorasick,
+    it: FindOverlappingIterator<'a, U8SliceIterator<P>, u32>,
+    // The end position of the last reported match of each group.
+    last_ends: Vec<usize>,
+}
+
+impl<P> Clone for FindIterator<'_, P>
+where
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            it: self.it.clone(),
+            last_ends: self.last_ends.clone(),
+        }
+    }
+}
+
+impl<P> Iterator for FindIterator<'_, P>
+where
+    P: AsRef<[u8]>,
+{
+    type Item = (Match<u32>, u32);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        // The overlapping search reports matches in the order of end positions and, at the same
+        // end position, from the longest. Taking the first match not overlapping with the last
+        // one of its group is equivalent to restarting the search of the group after the match.
+        for m in self.it.by_ref() {
+            let idx = usize::from_u32(m.value);
+            let group_idx = usize::from_u32(self.pma.group_indices[idx]);
+            let last_end = &mut self.last_ends[group_idx];
+            if m.end - m.length >= *last_end {
+                *last_end = m.end;
+                return Some((
+                    Match {
+                        length: m.length,
+                        end: m.end,
+                        value: self.pma.values[idx],
+                    },
+                    self.pma.groups[idx],
+                ));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::DoubleArrayAhoCorasickBuilder;
+
+    #[test]
+    fn test_find_iter_per_group() {
+        let patvals_groups = vec![
+            ("abba", 0, 7),
+            ("baaba", 1, 3),
+            ("ababa", 2, 7),
+            ("ba", 3, 3),
+            ("a", 4, 7),
+            ("bb", 5, 9),
+            ("aab", 6, 3),
+        ];
+        let haystack = "abbababaabaabbbaababab";
+        let pma = DoubleArrayAhoCorasickBuilder::new()
+            .build_with_groups(patvals_groups.clone())
+            .unwrap();
+        assert_eq!(3, pma.num_groups());
+
+        let mut expected = vec![];
+        for group in [3, 7, 9] {
+            let patvals: Vec<_> = patvals_groups
+                .iter()
+                .filter(|&&(_, _, g)| g == group)
+                .map(|&(p, v, _)| (p, v))
+                .collect();
+            let group_pma = DoubleArrayAhoCorasick::<u32>::with_values(patvals).unwrap();
+            expected.extend(
+                group_pma
+                    .find_iter(haystack)
+                    .map(|m| (m.end(), m.start(), m.value(), group)),
+            );
+        }
+        expected.sort_unstable();
+
+        let mut results: Vec<_> = pma
+            .find_iter(haystack)
+            .map(|(m, group)| (m.end(), m.start(), m.value(), group))
+            .collect();
+        // The order at the same end position depends on the lengths, which differ between groups.
+        results.sort_unstable();
+        assert_eq!(expected, results);
+    }
+}
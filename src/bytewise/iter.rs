@@ -1,16 +1,24 @@
 //! Iterators for [`DoubleArrayAhoCorasick`].
 
-use core::iter::Enumerate;
+use core::iter::{Enumerate, FusedIterator};
 use core::num::NonZeroU32;
 
+use alloc::collections::{BTreeSet, VecDeque};
+
+use crate::bytewise::memmem::TwoWay;
+use crate::bytewise::state::{DoubleArrayState, State};
 use crate::bytewise::DoubleArrayAhoCorasick;
-use crate::Match;
+use crate::{Match, Output};
 
 use crate::bytewise::ROOT_STATE_IDX;
 use crate::utils::FromU32;
 
+#[cfg(feature = "unicode")]
+use unicode_segmentation::GraphemeCursor;
+
 /// Iterator for some struct that implements [`AsRef<[u8]>`].
 #[doc(hidden)]
+#[derive(Clone)]
 pub struct U8SliceIterator<P> {
     inner: P,
     pos: usize,
@@ -24,6 +32,12 @@ where
     pub(crate) fn new(inner: P) -> Self {
         Self { inner, pos: 0 }
     }
+
+    /// Returns the bytes not consumed yet.
+    #[inline(always)]
+    pub(crate) fn rest(&self) -> &[u8] {
+        &self.inner.as_ref()[self.pos..]
+    }
 }
 
 impl<P> Iterator for U8SliceIterator<P>
@@ -38,18 +52,327 @@ where
         self.pos += 1;
         Some(ret)
     }
+
+    #[inline(always)]
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.pos = self.pos.saturating_add(n).min(self.inner.as_ref().len());
+        self.next()
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let rest = self.inner.as_ref().len() - self.pos;
+        (rest, Some(rest))
+    }
+}
+
+impl<P> FusedIterator for U8SliceIterator<P> where P: AsRef<[u8]> {}
+
+/// State of a search over a document split into chunks.
+///
+/// This is created by [`FindIterator::into_state()`] and consumed by
+/// [`DoubleArrayAhoCorasick::find_iter_with_state()`]. It holds the state of the automaton and the
+/// position in the document where the search stopped. The default value is the state at the
+/// beginning of a document.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FindState {
+    pub(crate) state_id: u32,
+    pub(crate) offset: usize,
+}
+
+impl Default for FindState {
+    fn default() -> Self {
+        Self {
+            state_id: ROOT_STATE_IDX,
+            offset: 0,
+        }
+    }
 }
 
 /// Iterator created by [`DoubleArrayAhoCorasick::find_iter()`].
-pub struct FindIterator<'a, P, V> {
+pub struct FindIterator<'a, P, V, S = State> {
+    pub(crate) pma: &'a DoubleArrayAhoCorasick<V, S>,
+    pub(crate) haystack: P,
+    pub(crate) pos: usize,
+    pub(crate) state_id: u32,
+    pub(crate) offset: usize,
+    // The searcher of the single pattern and the function returning the rest of the haystack,
+    // which are given only if the automaton has a single pattern and the haystack is a slice.
+    pub(crate) single_pattern: Option<(&'a TwoWay, RestFn<P>)>,
+}
+
+// Function returning the bytes of a haystack not consumed yet.
+pub(crate) type RestFn<P> = fn(&P) -> &[u8];
+
+impl<'a, P, V, S> FindIterator<'a, U8SliceIterator<P>, V, S>
+where
+    P: AsRef<[u8]>,
+    S: DoubleArrayState,
+{
+    /// Creates an iterator over a slice, which is searched by the two-way algorithm if the
+    /// automaton has a single pattern.
+    pub(crate) fn with_slice(
+        pma: &'a DoubleArrayAhoCorasick<V, S>,
+        haystack: P,
+        state_id: u32,
+        offset: usize,
+    ) -> Self {
+        Self {
+            pma,
+            haystack: U8SliceIterator::new(haystack),
+            pos: 0,
+            state_id,
+            offset,
+            single_pattern: pma
+                .single_pattern
+                .as_ref()
+                .map(|s| (s, U8SliceIterator::rest as RestFn<_>)),
+        }
+    }
+}
+
+impl<P, V, S> Clone for FindIterator<'_, P, V, S>
+where
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            pos: self.pos,
+            state_id: self.state_id,
+            offset: self.offset,
+            single_pattern: self.single_pattern,
+        }
+    }
+}
+
+impl<P, V, S> Iterator for FindIterator<'_, P, V, S>
+where
+    P: Iterator<Item = u8>,
+    V: Copy,
+    S: DoubleArrayState,
+{
+    type Item = Match<V>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((single_pattern, rest)) = self.single_pattern {
+            if self.state_id == ROOT_STATE_IDX {
+                // Skips to the next occurrence of the pattern, or to the last bytes that can be
+                // a prefix of it, which are consumed by the automaton below.
+                let rest = rest(&self.haystack);
+                let skip = single_pattern.find(rest).unwrap_or_else(|| {
+                    rest.len().saturating_sub(single_pattern.needle().len() - 1)
+                });
+                if skip != 0 {
+                    self.haystack.nth(skip - 1);
+                    self.pos += skip;
+                }
+            }
+        }
+        let mut state_id = self.state_id;
+        let mut pos = self.pos;
+        for c in self.haystack.by_ref() {
+            pos += 1;
+            // state_id is always smaller than self.pma.states.len() because
+            // self.pma.next_state_id_unchecked() ensures to return such a value.
+            state_id = unsafe { self.pma.next_state_id_unchecked(state_id, c) };
+            if let Some(output_pos) = unsafe {
+                self.pma
+                    .states
+                    .get_unchecked(usize::from_u32(state_id))
+                    .output_pos()
+            } {
+                // output_pos is always smaller than self.pma.outputs.len() because
+                // State::output_pos() ensures to return such a value when it is Some.
+                let out = unsafe {
+                    self.pma
+                        .outputs
+                        .get_unchecked(usize::from_u32(output_pos.get() - 1))
+                };
+                self.pos = pos;
+                self.state_id = ROOT_STATE_IDX;
+                return Some(Match {
+                    length: usize::from_u32(out.length()),
+                    end: self.offset + pos,
+                    value: out.value(),
+                });
+            }
+        }
+        self.pos = pos;
+        // Keeps the state for FindIterator::into_state().
+        self.state_id = state_id;
+        None
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Each match consumes at least one byte.
+        (0, self.haystack.size_hint().1)
+    }
+}
+
+impl<P, V, S> FindIterator<'_, P, V, S>
+where
+    P: Iterator<Item = u8>,
+    S: DoubleArrayState,
+{
+    /// Returns the number of bytes of the haystack consumed so far.
+    #[inline(always)]
+    #[must_use]
+    pub const fn bytes_scanned(&self) -> usize {
+        self.pos
+    }
+
+    /// Consumes the iterator and returns the state where the search stopped.
+    ///
+    /// If the iterator is exhausted, the state represents the trailing bytes of the haystack that
+    /// may start a match and the position of the end of the haystack. Passing it to
+    /// [`DoubleArrayAhoCorasick::find_iter_with_state()`] for the next chunk continues the search
+    /// as if the chunks were concatenated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_iter("xbc");
+    /// assert_eq!(None, it.next());
+    /// let state = it.into_state();
+    ///
+    /// let mut it = pma.find_iter_with_state("dx", state);
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    /// ```
+    #[inline(always)]
+    #[must_use]
+    pub fn into_state(self) -> FindState {
+        FindState {
+            state_id: self.state_id,
+            offset: self.offset + self.pos,
+        }
+    }
+
+    /// Converts the iterator into one yielding each match together with `&table[m.value()]`.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - Side table indexed by the values of matches.
+    ///
+    /// # Panics
+    ///
+    /// The iterator panics if the value of a match is out of the bounds of `table`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasickBuilder;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let names = vec!["BCD", "AB", "A"];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .build::<_, _, usize>(&patterns)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_iter("abcd").map_values(&names);
+    ///
+    /// let (m, name) = it.next().unwrap();
+    /// assert_eq!((0, 1, "A"), (m.start(), m.end(), *name));
+    /// ```
+    #[must_use]
+    pub fn map_values<T>(self, table: &[T]) -> MapValues<'_, Self, T>
+    where
+        V: Copy + TryInto<usize>,
+    {
+        MapValues { inner: self, table }
+    }
+}
+
+impl<P, V, S> FusedIterator for FindIterator<'_, P, V, S>
+where
+    P: FusedIterator<Item = u8>,
+    V: Copy,
+    S: DoubleArrayState,
+{
+}
+
+/// Iterator created by [`DoubleArrayAhoCorasick::find_iter_many()`].
+pub struct FindManyIterator<'a, I, V>
+where
+    I: Iterator,
+{
+    pub(crate) pma: &'a DoubleArrayAhoCorasick<V>,
+    pub(crate) haystacks: Enumerate<I>,
+    // Index of the haystack searched by `current`.
+    pub(crate) index: usize,
+    pub(crate) current: Option<FindIterator<'a, U8SliceIterator<I::Item>, V>>,
+}
+
+impl<I, V> Clone for FindManyIterator<'_, I, V>
+where
+    I: Iterator + Clone,
+    I::Item: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystacks: self.haystacks.clone(),
+            index: self.index,
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<I, V> Iterator for FindManyIterator<'_, I, V>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+    V: Copy,
+{
+    type Item = (usize, Match<V>);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(m) = self.current.as_mut().and_then(Iterator::next) {
+                return Some((self.index, m));
+            }
+            let (index, haystack) = self.haystacks.next()?;
+            self.index = index;
+            self.current = Some(FindIterator::with_slice(
+                self.pma,
+                haystack,
+                ROOT_STATE_IDX,
+                0,
+            ));
+        }
+    }
+}
+
+impl<I, V> FusedIterator for FindManyIterator<'_, I, V>
+where
+    I: FusedIterator,
+    I::Item: AsRef<[u8]>,
+    V: Copy,
+{
+}
+
+/// Iterator created by [`DoubleArrayAhoCorasick::find_iter_filtered()`].
+pub struct FindFilteredIterator<'a, P, V, F> {
     pub(crate) pma: &'a DoubleArrayAhoCorasick<V>,
     pub(crate) haystack: Enumerate<P>,
+    pub(crate) keep: F,
 }
 
-impl<P, V> Iterator for FindIterator<'_, P, V>
+impl<P, V, F> Iterator for FindFilteredIterator<'_, P, V, F>
 where
     P: Iterator<Item = u8>,
     V: Copy,
+    F: FnMut(V) -> bool,
 {
     type Item = Match<V>;
 
@@ -57,6 +380,105 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         let mut state_id = ROOT_STATE_IDX;
         for (pos, c) in self.haystack.by_ref() {
+            // state_id is always smaller than self.pma.states.len() because
+            // self.pma.next_state_id_unchecked() ensures to return such a value.
+            state_id = unsafe { self.pma.next_state_id_unchecked(state_id, c) };
+            let mut output_pos = unsafe {
+                self.pma
+                    .states
+                    .get_unchecked(usize::from_u32(state_id))
+                    .output_pos()
+            };
+            // Outputs are visited in descending order of length, so the first accepted one is
+            // the longest.
+            while let Some(opos) = output_pos {
+                // opos is always smaller than self.pma.outputs.len() because
+                // State::output_pos() and Output::parent() ensure to return such a value when it
+                // is Some.
+                let out = unsafe {
+                    self.pma
+                        .outputs
+                        .get_unchecked(usize::from_u32(opos.get() - 1))
+                };
+                if (self.keep)(out.value()) {
+                    return Some(Match {
+                        length: usize::from_u32(out.length()),
+                        end: pos + 1,
+                        value: out.value(),
+                    });
+                }
+                output_pos = out.parent();
+            }
+        }
+        None
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Each match consumes at least one byte.
+        (0, self.haystack.size_hint().1)
+    }
+}
+
+impl<P, V, F> FusedIterator for FindFilteredIterator<'_, P, V, F>
+where
+    P: FusedIterator<Item = u8>,
+    V: Copy,
+    F: FnMut(V) -> bool,
+{
+}
+
+/// Iterator created by [`DoubleArrayAhoCorasick::find_iter_skipping()`].
+pub struct FindSkippingIterator<'a, P, V, S = State> {
+    pub(crate) pma: &'a DoubleArrayAhoCorasick<V, S>,
+    pub(crate) haystack: P,
+    // Whether each byte is skipped.
+    pub(crate) skip_table: [bool; 256],
+    pub(crate) pos: usize,
+    // Positions of the last `max_len` bytes consumed by transitions since the last restart.
+    pub(crate) starts: VecDeque<usize>,
+    pub(crate) max_len: usize,
+}
+
+impl<P, V, S> Clone for FindSkippingIterator<'_, P, V, S>
+where
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            skip_table: self.skip_table,
+            pos: self.pos,
+            starts: self.starts.clone(),
+            max_len: self.max_len,
+        }
+    }
+}
+
+impl<P, V, S> Iterator for FindSkippingIterator<'_, P, V, S>
+where
+    P: AsRef<[u8]>,
+    V: Copy,
+    S: DoubleArrayState,
+{
+    type Item = Match<V>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut state_id = ROOT_STATE_IDX;
+        self.starts.clear();
+        let haystack = self.haystack.as_ref();
+        while self.pos < haystack.len() {
+            let c = haystack[self.pos];
+            self.pos += 1;
+            if self.skip_table[usize::from(c)] {
+                continue;
+            }
+            if self.starts.len() == self.max_len {
+                self.starts.pop_front();
+            }
+            self.starts.push_back(self.pos - 1);
             // state_id is always smaller than self.pma.states.len() because
             // self.pma.next_state_id_unchecked() ensures to return such a value.
             state_id = unsafe { self.pma.next_state_id_unchecked(state_id, c) };
@@ -66,16 +488,19 @@ where
                     .get_unchecked(usize::from_u32(state_id))
                     .output_pos()
             } {
-                // output_pos is always smaller than self.pma.outputs.len() because
+                // output_pos.get() is always smaller than self.pma.outputs.len() because
                 // State::output_pos() ensures to return such a value when it is Some.
                 let out = unsafe {
                     self.pma
                         .outputs
                         .get_unchecked(usize::from_u32(output_pos.get() - 1))
                 };
+                // The matched pattern consists of the last out.length() bytes consumed by
+                // transitions, which is at most max_len.
+                let start = self.starts[self.starts.len() - usize::from_u32(out.length())];
                 return Some(Match {
-                    length: usize::from_u32(out.length()),
-                    end: pos + 1,
+                    length: self.pos - start,
+                    end: self.pos,
                     value: out.value(),
                 });
             }
@@ -84,19 +509,43 @@ where
     }
 }
 
+impl<P, V, S> FusedIterator for FindSkippingIterator<'_, P, V, S>
+where
+    P: AsRef<[u8]>,
+    V: Copy,
+    S: DoubleArrayState,
+{
+}
+
 /// Iterator created by [`DoubleArrayAhoCorasick::find_overlapping_iter()`].
-pub struct FindOverlappingIterator<'a, P, V> {
-    pub(crate) pma: &'a DoubleArrayAhoCorasick<V>,
+pub struct FindOverlappingIterator<'a, P, V, S = State> {
+    pub(crate) pma: &'a DoubleArrayAhoCorasick<V, S>,
     pub(crate) haystack: Enumerate<P>,
     pub(crate) state_id: u32,
     pub(crate) pos: usize,
     pub(crate) output_pos: Option<NonZeroU32>,
 }
 
-impl<P, V> Iterator for FindOverlappingIterator<'_, P, V>
+impl<P, V, S> Clone for FindOverlappingIterator<'_, P, V, S>
+where
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            state_id: self.state_id,
+            pos: self.pos,
+            output_pos: self.output_pos,
+        }
+    }
+}
+
+impl<P, V, S> Iterator for FindOverlappingIterator<'_, P, V, S>
 where
     P: Iterator<Item = u8>,
     V: Copy,
+    S: DoubleArrayState,
 {
     type Item = Match<V>;
 
@@ -117,7 +566,227 @@ where
                 value: out.value(),
             });
         }
+        let mut scanned = self.pos;
         for (pos, c) in self.haystack.by_ref() {
+            scanned = pos + 1;
+            // self.state_id is always smaller than self.pma.states.len() because
+            // self.pma.next_state_id_unchecked() ensures to return such a value.
+            self.state_id = unsafe { self.pma.next_state_id_unchecked(self.state_id, c) };
+            if let Some(output_pos) = unsafe {
+                self.pma
+                    .states
+                    .get_unchecked(usize::from_u32(self.state_id))
+                    .output_pos()
+            } {
+                // output_pos.get() is always smaller than self.pma.outputs.len() because
+                // State::output_pos() ensures to return such a value when it is Some.
+                let out = unsafe {
+                    self.pma
+                        .outputs
+                        .get_unchecked(usize::from_u32(output_pos.get() - 1))
+                };
+                self.output_pos = out.parent();
+                self.pos = scanned;
+                return Some(Match {
+                    length: usize::from_u32(out.length()),
+                    end: self.pos,
+                    value: out.value(),
+                });
+            }
+        }
+        self.pos = scanned;
+        None
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // More than one match can be reported at each position, so there is no upper bound.
+        (usize::from(self.output_pos.is_some()), None)
+    }
+}
+
+impl<P, V, S> FindOverlappingIterator<'_, P, V, S>
+where
+    P: Iterator<Item = u8>,
+    S: DoubleArrayState,
+{
+    /// Returns the number of bytes of the haystack consumed so far.
+    #[inline(always)]
+    #[must_use]
+    pub const fn bytes_scanned(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the number of patterns ending at the end position of the last reported match,
+    /// including ones already reported.
+    ///
+    /// The count is the length of the output chain of the current state, which can be used as a
+    /// cheap signal of the specificity of the position. Zero is returned before the first match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "cd", "d", "ab"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_overlapping_iter("abcd");
+    /// assert_eq!(0, it.num_patterns_at_end());
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 2, 3), (m.start(), m.end(), m.value()));
+    /// assert_eq!(1, it.num_patterns_at_end());
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 0), (m.start(), m.end(), m.value()));
+    /// assert_eq!(3, it.num_patterns_at_end());
+    /// ```
+    #[must_use]
+    pub fn num_patterns_at_end(&self) -> usize
+    where
+        V: Copy,
+    {
+        self.pma.output_count_at(usize::from_u32(self.state_id))
+    }
+
+    /// Returns whether the match last returned by [`Iterator::next()`] is the first one reported
+    /// at its ending position.
+    ///
+    /// The search reports all patterns ending at a position in descending order of length. The
+    /// longest one is the primary match, and the others, which are its suffixes, are
+    /// continuations. `false` is returned before the first match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "cd", "d"];
+    /// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_overlapping_iter("abcd");
+    /// assert!(!it.is_primary());
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, true), (m.start(), m.end(), it.is_primary()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((2, 4, false), (m.start(), m.end(), it.is_primary()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((3, 4, false), (m.start(), m.end(), it.is_primary()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    #[must_use]
+    pub fn is_primary(&self) -> bool
+    where
+        V: Copy,
+    {
+        self.pma.states[usize::from_u32(self.state_id)]
+            .output_pos()
+            .map_or(false, |head| {
+                self.pma.outputs[usize::from_u32(head.get() - 1)].parent() == self.output_pos
+            })
+    }
+
+    /// Converts the iterator into one yielding each match together with `&table[m.value()]`.
+    ///
+    /// See [`FindIterator::map_values()`] for an example.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - Side table indexed by the values of matches.
+    ///
+    /// # Panics
+    ///
+    /// The iterator panics if the value of a match is out of the bounds of `table`.
+    #[must_use]
+    pub fn map_values<T>(self, table: &[T]) -> MapValues<'_, Self, T>
+    where
+        V: Copy + TryInto<usize>,
+    {
+        MapValues { inner: self, table }
+    }
+}
+
+impl<P, V, S> FusedIterator for FindOverlappingIterator<'_, P, V, S>
+where
+    P: FusedIterator<Item = u8>,
+    V: Copy,
+    S: DoubleArrayState,
+{
+}
+
+/// Iterator created by [`DoubleArrayAhoCorasick::find_overlapping_iter_fast()`].
+pub struct FindOverlappingFastIterator<'a, P, V> {
+    pub(crate) pma: &'a DoubleArrayAhoCorasick<V>,
+    pub(crate) haystack: P,
+    // Whether the root has a transition with each byte.
+    pub(crate) root_labels: [bool; 256],
+    pub(crate) state_id: u32,
+    pub(crate) pos: usize,
+    pub(crate) output_pos: Option<NonZeroU32>,
+}
+
+impl<P, V> Clone for FindOverlappingFastIterator<'_, P, V>
+where
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            root_labels: self.root_labels,
+            state_id: self.state_id,
+            pos: self.pos,
+            output_pos: self.output_pos,
+        }
+    }
+}
+
+impl<P, V> Iterator for FindOverlappingFastIterator<'_, P, V>
+where
+    P: AsRef<[u8]>,
+    V: Copy,
+{
+    type Item = Match<V>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(output_pos) = self.output_pos {
+            // output_pos.get() is always smaller than self.pma.outputs.len() because
+            // Output::parent() ensures to return such a value when it is Some.
+            let out = unsafe {
+                self.pma
+                    .outputs
+                    .get_unchecked(usize::from_u32(output_pos.get() - 1))
+            };
+            self.output_pos = out.parent();
+            return Some(Match {
+                length: usize::from_u32(out.length()),
+                end: self.pos,
+                value: out.value(),
+            });
+        }
+        let haystack = self.haystack.as_ref();
+        while self.pos < haystack.len() {
+            if self.state_id == ROOT_STATE_IDX {
+                // Bytes without transitions from the root keep the automaton at the root.
+                let skip = haystack[self.pos..]
+                    .iter()
+                    .position(|&c| self.root_labels[usize::from(c)]);
+                match skip {
+                    Some(skip) => self.pos += skip,
+                    None => {
+                        self.pos = haystack.len();
+                        return None;
+                    }
+                }
+            }
+            let c = haystack[self.pos];
+            self.pos += 1;
             // self.state_id is always smaller than self.pma.states.len() because
             // self.pma.next_state_id_unchecked() ensures to return such a value.
             self.state_id = unsafe { self.pma.next_state_id_unchecked(self.state_id, c) };
@@ -127,7 +796,6 @@ where
                     .get_unchecked(usize::from_u32(self.state_id))
                     .output_pos()
             } {
-                self.pos = pos + 1;
                 // output_pos.get() is always smaller than self.pma.outputs.len() because
                 // State::output_pos() ensures to return such a value when it is Some.
                 let out = unsafe {
@@ -147,11 +815,188 @@ where
     }
 }
 
+impl<P, V> FusedIterator for FindOverlappingFastIterator<'_, P, V>
+where
+    P: AsRef<[u8]>,
+    V: Copy,
+{
+}
+
+/// Iterator created by [`DoubleArrayAhoCorasick::find_overlapping_dedup_iter()`].
+pub struct FindOverlappingDedupIterator<'a, P, V> {
+    pub(crate) inner: FindOverlappingIterator<'a, P, V>,
+    pub(crate) window: usize,
+    pub(crate) max_len: usize,
+    // Values and starting positions of the remembered reports.
+    pub(crate) reported: BTreeSet<(V, usize)>,
+    // Starting positions and values of the remembered reports, in the order of the reports.
+    pub(crate) order: VecDeque<(usize, V)>,
+}
+
+impl<P, V> Clone for FindOverlappingDedupIterator<'_, P, V>
+where
+    P: Clone,
+    V: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            window: self.window,
+            max_len: self.max_len,
+            reported: self.reported.clone(),
+            order: self.order.clone(),
+        }
+    }
+}
+
+impl<P, V> Iterator for FindOverlappingDedupIterator<'_, P, V>
+where
+    P: Iterator<Item = u8>,
+    V: Copy + Ord,
+{
+    type Item = Match<V>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.window == 0 {
+            return self.inner.next();
+        }
+        loop {
+            let m = self.inner.next()?;
+            // Subsequent matches start at m.end - max_len or later, so reports starting
+            // window + max_len bytes before m.end can no longer suppress them.
+            while let Some(&(start, value)) = self.order.front() {
+                if start + self.window + self.max_len > m.end {
+                    break;
+                }
+                self.order.pop_front();
+                self.reported.remove(&(value, start));
+            }
+            let start = m.start();
+            let lo = (start + 1).saturating_sub(self.window);
+            if self
+                .reported
+                .range((m.value, lo)..(m.value, start + self.window))
+                .next()
+                .is_some()
+            {
+                continue;
+            }
+            self.reported.insert((m.value, start));
+            self.order.push_back((start, m.value));
+            return Some(m);
+        }
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+}
+
+impl<P, V> FindOverlappingDedupIterator<'_, P, V>
+where
+    P: Iterator<Item = u8>,
+{
+    /// Returns the number of bytes of the haystack consumed so far.
+    #[inline(always)]
+    #[must_use]
+    pub const fn bytes_scanned(&self) -> usize {
+        self.inner.pos
+    }
+}
+
+impl<P, V> FusedIterator for FindOverlappingDedupIterator<'_, P, V>
+where
+    P: FusedIterator<Item = u8>,
+    V: Copy + Ord,
+{
+}
+
+/// Selection of the output reported by [`FindOverlappingNoSuffixIterator`] from the chain
+/// starting at the position of the current state.
+pub(crate) enum OutputSelector<V> {
+    /// Selects the head of the chain, i.e., the longest pattern.
+    Longest,
+
+    /// Selects the tail of the chain with [`select_shortest()`].
+    Shortest,
+
+    /// Selects with the given function, which is [`select_smallest_value()`] whose bound `V: Ord`
+    /// is not required by the iterator.
+    SmallestValue(fn(&[Output<V>], NonZeroU32) -> NonZeroU32),
+}
+
+// Implemented manually because deriving requires `V: Clone`.
+impl<V> Clone for OutputSelector<V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for OutputSelector<V> {}
+
+/// Selects the tail of the chain, i.e., the shortest pattern.
+pub(crate) fn select_shortest<V>(outputs: &[Output<V>], mut output_pos: NonZeroU32) -> NonZeroU32
+where
+    V: Copy,
+{
+    // output_pos is always smaller than outputs.len() because State::output_pos() and
+    // Output::parent() ensure to return such a value when it is Some.
+    while let Some(parent) =
+        unsafe { outputs.get_unchecked(usize::from_u32(output_pos.get() - 1)) }.parent()
+    {
+        output_pos = parent;
+    }
+    output_pos
+}
+
+/// Selects the pattern with the smallest value, preferring the longer one on ties.
+pub(crate) fn select_smallest_value<V>(outputs: &[Output<V>], output_pos: NonZeroU32) -> NonZeroU32
+where
+    V: Copy + Ord,
+{
+    let mut best = output_pos;
+    // output_pos is always smaller than outputs.len() because State::output_pos() ensures to
+    // return such a value when it is Some.
+    let head = unsafe { outputs.get_unchecked(usize::from_u32(output_pos.get() - 1)) };
+    let mut best_value = head.value();
+    let mut next = head.parent();
+    while let Some(pos) = next {
+        // pos is always smaller than outputs.len() because Output::parent() ensures to return
+        // such a value when it is Some.
+        let out = unsafe { outputs.get_unchecked(usize::from_u32(pos.get() - 1)) };
+        if out.value() < best_value {
+            best = pos;
+            best_value = out.value();
+        }
+        next = out.parent();
+    }
+    best
+}
+
 /// Iterator created by [`DoubleArrayAhoCorasick::find_overlapping_no_suffix_iter()`].
 pub struct FindOverlappingNoSuffixIterator<'a, P, V> {
     pub(crate) pma: &'a DoubleArrayAhoCorasick<V>,
     pub(crate) haystack: Enumerate<P>,
     pub(crate) state_id: u32,
+    pub(crate) pos: usize,
+    pub(crate) select: OutputSelector<V>,
+}
+
+impl<P, V> Clone for FindOverlappingNoSuffixIterator<'_, P, V>
+where
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            state_id: self.state_id,
+            pos: self.pos,
+            select: self.select,
+        }
+    }
 }
 
 impl<P, V> Iterator for FindOverlappingNoSuffixIterator<'_, P, V>
@@ -163,7 +1008,9 @@ where
 
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
+        let mut scanned = self.pos;
         for (pos, c) in self.haystack.by_ref() {
+            scanned = pos + 1;
             // self.state_id is always smaller than self.pma.states.len() because
             // self.pma.next_state_id_unchecked() ensures to return such a value.
             self.state_id = unsafe { self.pma.next_state_id_unchecked(self.state_id, c) };
@@ -173,13 +1020,19 @@ where
                     .get_unchecked(usize::from_u32(self.state_id))
                     .output_pos()
             } {
+                let output_pos = match self.select {
+                    OutputSelector::Longest => output_pos,
+                    OutputSelector::Shortest => select_shortest(&self.pma.outputs, output_pos),
+                    OutputSelector::SmallestValue(select) => select(&self.pma.outputs, output_pos),
+                };
                 // output_pos is always smaller than self.pma.outputs.len() because
-                // State::output_pos() ensures to return such a value when it is Some.
+                // State::output_pos() and the selector ensure to return such a value.
                 let out = unsafe {
                     self.pma
                         .outputs
                         .get_unchecked(usize::from_u32(output_pos.get() - 1))
                 };
+                self.pos = scanned;
                 return Some(Match {
                     length: usize::from_u32(out.length()),
                     end: pos + 1,
@@ -187,24 +1040,68 @@ where
                 });
             }
         }
+        self.pos = scanned;
         None
     }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Each match consumes at least one byte.
+        (0, self.haystack.size_hint().1)
+    }
+}
+
+impl<P, V> FindOverlappingNoSuffixIterator<'_, P, V>
+where
+    P: Iterator<Item = u8>,
+{
+    /// Returns the number of bytes of the haystack consumed so far.
+    #[inline(always)]
+    #[must_use]
+    pub const fn bytes_scanned(&self) -> usize {
+        self.pos
+    }
+}
+
+impl<P, V> FusedIterator for FindOverlappingNoSuffixIterator<'_, P, V>
+where
+    P: FusedIterator<Item = u8>,
+    V: Copy,
+{
 }
 
 /// Iterator created by [`DoubleArrayAhoCorasick::leftmost_find_iter()`].
-pub struct LestmostFindIterator<'a, P, V>
+pub struct LeftmostFindIterator<'a, P, V, S = State>
 where
     P: AsRef<[u8]>,
 {
-    pub(crate) pma: &'a DoubleArrayAhoCorasick<V>,
+    pub(crate) pma: &'a DoubleArrayAhoCorasick<V, S>,
     pub(crate) haystack: P,
     pub(crate) pos: usize,
 }
 
-impl<P, V> Iterator for LestmostFindIterator<'_, P, V>
+impl<P, V, S> Clone for LeftmostFindIterator<'_, P, V, S>
+where
+    P: AsRef<[u8]> + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            pos: self.pos,
+        }
+    }
+}
+
+/// Deprecated alias of [`LeftmostFindIterator`], kept for backward compatibility.
+#[deprecated(note = "Use `LeftmostFindIterator` instead")]
+pub type LestmostFindIterator<'a, P, V> = LeftmostFindIterator<'a, P, V>;
+
+impl<P, V, S> Iterator for LeftmostFindIterator<'_, P, V, S>
 where
     P: AsRef<[u8]>,
     V: Copy,
+    S: DoubleArrayState,
 {
     type Item = Match<V>;
 
@@ -246,6 +1143,9 @@ where
             }
         }
 
+        if last_output_pos.is_none() {
+            self.pos = haystack.len();
+        }
         last_output_pos.map(|output_pos| {
             // last_output_pos is always smaller than self.pma.outputs.len() because
             // State::output_pos() ensures to return such a value when it is Some.
@@ -261,4 +1161,250 @@ where
             }
         })
     }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Each match consumes at least one byte.
+        (0, Some(self.haystack.as_ref().len() - self.pos))
+    }
+}
+
+impl<P, V, S> LeftmostFindIterator<'_, P, V, S>
+where
+    P: AsRef<[u8]>,
+    S: DoubleArrayState,
+{
+    /// Returns the number of bytes of the haystack consumed so far.
+    ///
+    /// This is the position from which the next search resumes, i.e., the end of the last
+    /// reported match, or the length of the haystack after the iterator is exhausted. Bytes
+    /// read ahead to find a longer match are not counted.
+    #[inline(always)]
+    #[must_use]
+    pub const fn bytes_scanned(&self) -> usize {
+        self.pos
+    }
+
+    /// Converts the iterator into one yielding each match together with `&table[m.value()]`.
+    ///
+    /// See [`FindIterator::map_values()`] for an example.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - Side table indexed by the values of matches.
+    ///
+    /// # Panics
+    ///
+    /// The iterator panics if the value of a match is out of the bounds of `table`.
+    #[must_use]
+    pub fn map_values<T>(self, table: &[T]) -> MapValues<'_, Self, T>
+    where
+        V: Copy + TryInto<usize>,
+    {
+        MapValues { inner: self, table }
+    }
+}
+
+impl<P, V, S> FusedIterator for LeftmostFindIterator<'_, P, V, S>
+where
+    P: AsRef<[u8]>,
+    V: Copy,
+    S: DoubleArrayState,
+{
+}
+
+/// Iterator created by `map_values()` of the search iterators.
+pub struct MapValues<'t, I, T> {
+    pub(crate) inner: I,
+    pub(crate) table: &'t [T],
+}
+
+impl<I, T> Clone for MapValues<'_, I, T>
+where
+    I: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            table: self.table,
+        }
+    }
+}
+
+impl<'t, I, V, T> Iterator for MapValues<'t, I, T>
+where
+    I: Iterator<Item = Match<V>>,
+    V: Copy + TryInto<usize>,
+{
+    type Item = (Match<V>, &'t T);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let m = self.inner.next()?;
+        let entry = m
+            .value()
+            .try_into()
+            .ok()
+            .and_then(|i| self.table.get(i))
+            .unwrap_or_else(|| {
+                panic!(
+                    "Error: value of the match at {}..{} is out of bounds of the table of length {}.",
+                    m.start(),
+                    m.end(),
+                    self.table.len()
+                )
+            });
+        Some((m, entry))
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<I, V, T> FusedIterator for MapValues<'_, I, T>
+where
+    I: FusedIterator<Item = Match<V>>,
+    V: Copy + TryInto<usize>,
+{
+}
+
+/// Iterator created by [`DoubleArrayAhoCorasick::find_anchored()`].
+pub struct FindAnchoredIterator<'a, P, V> {
+    pub(crate) pma: &'a DoubleArrayAhoCorasick<V>,
+    pub(crate) haystack: Enumerate<P>,
+    // None if the traversal has left the trie.
+    pub(crate) state_id: Option<u32>,
+}
+
+impl<P, V> Clone for FindAnchoredIterator<'_, P, V>
+where
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            state_id: self.state_id,
+        }
+    }
+}
+
+impl<P, V> Iterator for FindAnchoredIterator<'_, P, V>
+where
+    P: Iterator<Item = u8>,
+    V: Copy,
+{
+    type Item = Match<V>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut state_id = self.state_id?;
+        for (pos, c) in self.haystack.by_ref() {
+            // state_id is always smaller than self.pma.states.len() because
+            // self.pma.child_index_unchecked() ensures to return such a value.
+            if let Some(child_id) = unsafe { self.pma.child_index_unchecked(state_id, c) } {
+                state_id = child_id;
+            } else {
+                break;
+            }
+            if let Some(output_pos) = unsafe {
+                self.pma
+                    .states
+                    .get_unchecked(usize::from_u32(state_id))
+                    .output_pos()
+            } {
+                // output_pos is always smaller than self.pma.outputs.len() because
+                // State::output_pos() ensures to return such a value when it is Some.
+                let out = unsafe {
+                    self.pma
+                        .outputs
+                        .get_unchecked(usize::from_u32(output_pos.get() - 1))
+                };
+                // The output may be of a suffix of the prefix unless the automaton is built with
+                // DoubleArrayAhoCorasickBuilder::prefix_only().
+                if usize::from_u32(out.length()) == pos + 1 {
+                    self.state_id = Some(state_id);
+                    return Some(Match {
+                        length: pos + 1,
+                        end: pos + 1,
+                        value: out.value(),
+                    });
+                }
+            }
+        }
+        self.state_id = None;
+        None
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.state_id.is_none() {
+            return (0, Some(0));
+        }
+        (0, self.haystack.size_hint().1)
+    }
+}
+
+impl<P, V> FusedIterator for FindAnchoredIterator<'_, P, V>
+where
+    P: Iterator<Item = u8>,
+    V: Copy,
+{
+}
+
+/// Iterator created by [`DoubleArrayAhoCorasick::find_iter_graphemes()`].
+#[cfg(feature = "unicode")]
+#[cfg_attr(docsrs, doc(cfg(feature = "unicode")))]
+pub struct FindGraphemesIterator<'a, 'h, V> {
+    pub(crate) inner: FindIterator<'a, U8SliceIterator<&'h str>, V>,
+    pub(crate) haystack: &'h str,
+}
+
+#[cfg(feature = "unicode")]
+impl<V> Clone for FindGraphemesIterator<'_, '_, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            haystack: self.haystack,
+        }
+    }
+}
+
+#[cfg(feature = "unicode")]
+impl<V> Iterator for FindGraphemesIterator<'_, '_, V>
+where
+    V: Copy,
+{
+    type Item = Match<V>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let haystack = self.haystack;
+        self.inner.by_ref().find(|m| {
+            is_grapheme_boundary(haystack, m.start()) && is_grapheme_boundary(haystack, m.end())
+        })
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.inner.size_hint().1)
+    }
+}
+
+#[cfg(feature = "unicode")]
+impl<V> FusedIterator for FindGraphemesIterator<'_, '_, V> where V: Copy {}
+
+/// Checks if `pos` is on a boundary of extended grapheme clusters in `haystack`.
+#[cfg(feature = "unicode")]
+#[inline(always)]
+fn is_grapheme_boundary(haystack: &str, pos: usize) -> bool {
+    if !haystack.is_char_boundary(pos) {
+        return false;
+    }
+    // The whole haystack is given as the chunk, so the cursor never requests more context.
+    GraphemeCursor::new(pos, haystack.len(), true)
+        .is_boundary(haystack, 0)
+        .unwrap_or(false)
 }
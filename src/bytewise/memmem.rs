@@ -0,0 +1,185 @@
+//! Substring search with the two-way algorithm of Crochemore and Perrin.
+//!
+//! It runs in linear time with constant extra space, and is used for automata with a single
+//! pattern, where the Aho-Corasick machinery is overkill.
+
+use alloc::vec::Vec;
+
+/// Searcher of a needle precomputed by the critical factorization.
+#[derive(Clone, Eq, Hash, PartialEq)]
+pub struct TwoWay {
+    needle: Vec<u8>,
+    // Bit set of the lower 6 bits of the bytes in the needle.
+    byteset: u64,
+    // Position of the critical factorization.
+    crit_pos: usize,
+    // Period of the needle, or a lower bound of the shift if the needle has a long period.
+    period: usize,
+    // Whether the needle has a short period, i.e., the prefix remembered on a shift is valid.
+    short_period: bool,
+}
+
+impl TwoWay {
+    pub fn new(needle: Vec<u8>) -> Self {
+        let byteset = needle.iter().fold(0, |set, &b| set | 1 << (b & 0x3f));
+        let (crit_pos_false, period_false) = maximal_suffix(&needle, false);
+        let (crit_pos_true, period_true) = maximal_suffix(&needle, true);
+        let (crit_pos, period) = if crit_pos_false > crit_pos_true {
+            (crit_pos_false, period_false)
+        } else {
+            (crit_pos_true, period_true)
+        };
+        if period + crit_pos <= needle.len() && needle[..crit_pos] == needle[period..][..crit_pos] {
+            Self {
+                needle,
+                byteset,
+                crit_pos,
+                period,
+                short_period: true,
+            }
+        } else {
+            let period = crit_pos.max(needle.len() - crit_pos) + 1;
+            Self {
+                needle,
+                byteset,
+                crit_pos,
+                period,
+                short_period: false,
+            }
+        }
+    }
+
+    pub fn needle(&self) -> &[u8] {
+        &self.needle
+    }
+
+    /// Returns the start position of the first occurrence of the needle in the haystack.
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let needle = &self.needle[..];
+        let mut pos = 0;
+        // Length of the prefix of the needle known to match at pos.
+        let mut memory = 0;
+        'search: while pos + needle.len() <= haystack.len() {
+            let window = &haystack[pos..pos + needle.len()];
+
+            // No occurrence contains the last byte of the window if the needle does not.
+            if self.byteset >> (window[needle.len() - 1] & 0x3f) & 1 == 0 {
+                pos += needle.len();
+                memory = 0;
+                continue;
+            }
+
+            // Matches the right part.
+            let start = if self.short_period {
+                self.crit_pos.max(memory)
+            } else {
+                self.crit_pos
+            };
+            for i in start..needle.len() {
+                if needle[i] != window[i] {
+                    pos += i - self.crit_pos + 1;
+                    memory = 0;
+                    continue 'search;
+                }
+            }
+
+            // Matches the left part.
+            let start = if self.short_period { memory } else { 0 };
+            for i in (start..self.crit_pos).rev() {
+                if needle[i] != window[i] {
+                    pos += self.period;
+                    if self.short_period {
+                        memory = needle.len() - self.period;
+                    }
+                    continue 'search;
+                }
+            }
+            return Some(pos);
+        }
+        None
+    }
+}
+
+/// Computes the maximal suffix of `arr` for the lexicographic order, or its reverse if
+/// `reversed` is true, and returns its start position and period.
+fn maximal_suffix(arr: &[u8], reversed: bool) -> (usize, usize) {
+    let mut left = 0;
+    let mut right = 1;
+    let mut offset = 0;
+    let mut period = 1;
+    while let Some(&a) = arr.get(right + offset) {
+        let b = arr[left + offset];
+        if (a < b && !reversed) || (a > b && reversed) {
+            right += offset + 1;
+            offset = 0;
+            period = right - left;
+        } else if a == b {
+            if offset + 1 == period {
+                right += offset + 1;
+                offset = 0;
+            } else {
+                offset += 1;
+            }
+        } else {
+            left = right;
+            right += 1;
+            offset = 0;
+            period = 1;
+        }
+    }
+    (left, period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive_find(needle: &[u8], haystack: &[u8]) -> Option<usize> {
+        haystack.windows(needle.len()).position(|w| w == needle)
+    }
+
+    #[test]
+    fn test_find() {
+        let haystack = b"abaababaabaababaababa";
+        for needle in [
+            &b"a"[..],
+            b"ab",
+            b"aab",
+            b"abaababaab",
+            b"babaababa",
+            b"aabaababaababa",
+            b"bbb",
+            b"abaababaabaababaababa",
+            b"abaababaabaababaababaa",
+        ] {
+            assert_eq!(
+                naive_find(needle, haystack),
+                TwoWay::new(needle.to_vec()).find(haystack)
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_random() {
+        let mut seed = 2463534242u32;
+        let mut random_bytes = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 17;
+                    seed ^= seed << 5;
+                    b'a' + u8::try_from(seed % 3).unwrap()
+                })
+                .collect()
+        };
+        for i in 0..2000 {
+            let needle = random_bytes(i % 7 + 1);
+            let haystack = random_bytes(i % 50);
+            assert_eq!(
+                naive_find(&needle, &haystack),
+                TwoWay::new(needle.clone()).find(&haystack),
+                "needle={needle:?}, haystack={haystack:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! A byte-wise automaton using 16-bit indices for small pattern sets.
+
+use crate::bytewise::state::{DoubleArrayState, State16};
+use crate::bytewise::DoubleArrayAhoCorasick;
+use crate::MatchKind;
+
+/// A variant of [`DoubleArrayAhoCorasick`] storing indices of states and outputs in 16 bits.
+///
+/// Each state is represented in the space of 8 bytes instead of 12 bytes, which reduces
+/// [`heap_bytes()`](DoubleArrayAhoCorasick::heap_bytes) and improves cache efficiency for small
+/// dictionaries. The search methods are shared with [`DoubleArrayAhoCorasick`], and their results
+/// are the same as those of the original automaton.
+///
+/// This automaton is built by
+/// [`DoubleArrayAhoCorasickBuilder::build_adaptive()`](super::DoubleArrayAhoCorasickBuilder::build_adaptive)
+/// or [`DoubleArrayAhoCorasick16::from_wide()`].
+///
+/// # Limitations
+///
+/// The numbers of states and outputs must be at most 2^16 and 2^16-1, respectively.
+pub type DoubleArrayAhoCorasick16<V> = DoubleArrayAhoCorasick<V, State16>;
+
+impl<V> DoubleArrayAhoCorasick<V, State16> {
+    /// Converts an automaton into the 16-bit representation.
+    ///
+    /// # Arguments
+    ///
+    /// * `pma` - Automaton to be converted.
+    ///
+    /// # Errors
+    ///
+    /// The given automaton is returned as is if it is too large to be represented with 16-bit
+    /// indices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{bytewise::DoubleArrayAhoCorasick16, DoubleArrayAhoCorasick};
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = DoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+    /// let wide_bytes = pma.heap_bytes();
+    ///
+    /// let pma = DoubleArrayAhoCorasick16::from_wide(pma).ok().unwrap();
+    /// assert!(pma.heap_bytes() < wide_bytes);
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn from_wide(
+        pma: DoubleArrayAhoCorasick<V>,
+    ) -> core::result::Result<Self, DoubleArrayAhoCorasick<V>> {
+        if pma.states.len() > 1 << 16 || pma.outputs.len() > usize::from(u16::MAX) {
+            return Err(pma);
+        }
+        let DoubleArrayAhoCorasick {
+            states,
+            outputs,
+            match_kind,
+            num_states,
+            fail_ids,
+            fail_table,
+            fail_slots,
+            skip_bytes,
+            single_pattern,
+            prefix_only,
+            minimize_suffixes,
+        } = pma;
+
+        // The following conversions never fail because
+        //  - base and fail are smaller than states.len(), and
+        //  - output_pos is at most outputs.len().
+        let narrow = |x: u32| u16::try_from(x).unwrap();
+        let states = states
+            .iter()
+            .map(|state| State16 {
+                base: state.base().map_or(0, |x| narrow(x.get())),
+                fail: narrow(state.fail()),
+                output_pos: state.output_pos().map_or(0, |x| narrow(x.get())),
+                check: state.check(),
+            })
+            .collect();
+
+        Ok(Self {
+            states,
+            outputs,
+            match_kind,
+            num_states,
+            fail_ids,
+            fail_table,
+            fail_slots,
+            skip_bytes,
+            single_pattern,
+            prefix_only,
+            minimize_suffixes,
+        })
+    }
+}
+
+impl<V> core::fmt::Debug for DoubleArrayAhoCorasick<V, State16> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DoubleArrayAhoCorasick16")
+            .field("match_kind", &self.match_kind)
+            .field("num_states", &self.num_states)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Automaton created by
+/// [`DoubleArrayAhoCorasickBuilder::build_adaptive()`](super::DoubleArrayAhoCorasickBuilder::build_adaptive),
+/// using 16-bit indices if possible.
+#[derive(Clone, Eq, Hash, PartialEq)]
+pub enum AdaptiveDoubleArrayAhoCorasick<V> {
+    /// Automaton with 16-bit indices.
+    Narrow(DoubleArrayAhoCorasick16<V>),
+
+    /// Automaton with 32-bit indices, used if the pattern set is too large for 16-bit indices.
+    Wide(DoubleArrayAhoCorasick<V>),
+}
+
+impl<V> AdaptiveDoubleArrayAhoCorasick<V> {
+    /// Returns the total amount of heap used by this automaton in bytes.
+    #[must_use]
+    pub fn heap_bytes(&self) -> usize {
+        match self {
+            Self::Narrow(pma) => pma.heap_bytes(),
+            Self::Wide(pma) => pma.heap_bytes(),
+        }
+    }
+
+    /// Returns the total number of states this automaton has.
+    #[must_use]
+    pub fn num_states(&self) -> usize {
+        match self {
+            Self::Narrow(pma) => pma.num_states(),
+            Self::Wide(pma) => pma.num_states(),
+        }
+    }
+
+    /// Returns the [`MatchKind`] specified in the construction.
+    #[must_use]
+    pub const fn match_kind(&self) -> MatchKind {
+        match self {
+            Self::Narrow(pma) => pma.match_kind,
+            Self::Wide(pma) => pma.match_kind,
+        }
+    }
+}
+
+impl<V> From<DoubleArrayAhoCorasick<V>> for AdaptiveDoubleArrayAhoCorasick<V> {
+    fn from(pma: DoubleArrayAhoCorasick<V>) -> Self {
+        DoubleArrayAhoCorasick16::from_wide(pma).map_or_else(Self::Wide, Self::Narrow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec::Vec;
+
+    use crate::DoubleArrayAhoCorasickBuilder;
+
+    #[test]
+    fn test_search_consistency() {
+        let patterns = ["abba", "baaba", "ababa", "ba", "a", "bb", "aab"];
+        let haystack = "abbababaabaabbbaababab";
+
+        for compress_fail in [false, true] {
+            let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+                .compress_fail(compress_fail)
+                .build(patterns)
+                .unwrap();
+            let narrow_pma = DoubleArrayAhoCorasick16::from_wide(pma.clone())
+                .ok()
+                .unwrap();
+            assert!(narrow_pma.heap_bytes() < pma.heap_bytes());
+            assert_eq!(
+                pma.find_iter(haystack).collect::<Vec<_>>(),
+                narrow_pma.find_iter(haystack).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                pma.find_overlapping_iter(haystack).collect::<Vec<_>>(),
+                narrow_pma
+                    .find_overlapping_iter(haystack)
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        for match_kind in [MatchKind::LeftmostLongest, MatchKind::LeftmostFirst] {
+            let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+                .match_kind(match_kind)
+                .build(patterns)
+                .unwrap();
+            let narrow_pma = DoubleArrayAhoCorasick16::from_wide(pma.clone())
+                .ok()
+                .unwrap();
+            assert_eq!(
+                pma.leftmost_find_iter(haystack).collect::<Vec<_>>(),
+                narrow_pma.leftmost_find_iter(haystack).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_iter_skipping() {
+        let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .skip_bytes(b" -")
+            .build(["abc", "bc"])
+            .unwrap();
+        let narrow_pma = DoubleArrayAhoCorasick16::from_wide(pma.clone())
+            .ok()
+            .unwrap();
+        let haystack = "a-b c bc";
+        assert_eq!(
+            pma.find_iter_skipping(haystack).collect::<Vec<_>>(),
+            narrow_pma.find_iter_skipping(haystack).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_fallback() {
+        let patterns: Vec<_> = (0..70000).map(|i| format!("{i:x}")).collect();
+        let pma: AdaptiveDoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .build_adaptive(&patterns)
+            .unwrap();
+        assert!(matches!(pma, AdaptiveDoubleArrayAhoCorasick::Wide(_)));
+
+        let pma: AdaptiveDoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+            .build_adaptive(&patterns[..100])
+            .unwrap();
+        assert!(matches!(pma, AdaptiveDoubleArrayAhoCorasick::Narrow(_)));
+    }
+}
@@ -0,0 +1,607 @@
+//! A byte-wise automaton storing sorted values in a compressed form.
+
+use core::mem;
+use core::num::NonZeroU32;
+
+use alloc::vec::Vec;
+
+use crate::bytewise::state::DoubleArrayState;
+use crate::bytewise::{DoubleArrayAhoCorasick, ROOT_STATE_IDX};
+use crate::errors::Result;
+use crate::utils::FromU32;
+use crate::{Empty, Match, MatchKind, Output};
+
+// The number of values sharing a base value in `DeltaValues`.
+const DELTA_BLOCK_LEN: usize = 32;
+
+/// A variant of [`DoubleArrayAhoCorasick`] for values given in ascending order, such as dense
+/// sorted identifiers.
+///
+/// Instead of storing a value in each output, this automaton arranges outputs in the input order
+/// and stores the values in a side structure, in which each block of 32 values is represented by
+/// the first value and bit-packed differences from it. This reduces
+/// [`heap_bytes()`](SortedDoubleArrayAhoCorasick::heap_bytes) when the values are close to each
+/// other.
+///
+/// This automaton is built by
+/// [`DoubleArrayAhoCorasickBuilder::build_from_sorted_pairs()`](super::DoubleArrayAhoCorasickBuilder::build_from_sorted_pairs).
+#[derive(Clone)]
+pub struct SortedDoubleArrayAhoCorasick {
+    pma: DoubleArrayAhoCorasick<Empty>,
+    values: DeltaValues,
+}
+
+impl SortedDoubleArrayAhoCorasick {
+    /// Creates a new automaton from an automaton associating each pattern with its index in the
+    /// input, and the values sorted in ascending order.
+    pub(crate) fn new(pma: DoubleArrayAhoCorasick<u32>, values: &[u32]) -> Result<Self> {
+        let DoubleArrayAhoCorasick {
+            mut states,
+            outputs,
+            match_kind,
+            num_states,
+            fail_ids,
+            fail_table,
+            fail_slots,
+            skip_bytes,
+            single_pattern,
+            prefix_only,
+            minimize_suffixes,
+        } = pma;
+
+        // Rearranges outputs in the input order of the corresponding patterns.
+        let mut order: Vec<_> = (0..outputs.len()).collect();
+        order.sort_unstable_by_key(|&i| outputs[i].value());
+        let mut new_positions = vec![0; outputs.len()];
+        for (new_idx, &old_idx) in order.iter().enumerate() {
+            new_positions[old_idx] = u32::try_from(new_idx + 1).unwrap();
+        }
+        let remap = |pos: Option<NonZeroU32>| {
+            pos.and_then(|pos| NonZeroU32::new(new_positions[usize::from_u32(pos.get() - 1)]))
+        };
+
+        let new_outputs = order
+            .iter()
+            .map(|&i| Output::new(Empty, outputs[i].length(), remap(outputs[i].parent())))
+            .collect();
+        for state in &mut states {
+            state.set_output_pos(remap(state.output_pos()))?;
+        }
+        let new_values: Vec<_> = order
+            .iter()
+            .map(|&i| values[usize::from_u32(outputs[i].value())])
+            .collect();
+
+        Ok(Self {
+            pma: DoubleArrayAhoCorasick {
+                states,
+                outputs: new_outputs,
+                match_kind,
+                num_states,
+                fail_ids,
+                fail_table,
+                fail_slots,
+                skip_bytes,
+                single_pattern,
+                prefix_only,
+                minimize_suffixes,
+            },
+            values: DeltaValues::new(&new_values),
+        })
+    }
+
+    /// Returns an iterator of non-overlapping matches in the given haystack.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasickBuilder;
+    ///
+    /// let patvals = vec![("bcd", 100), ("ab", 101), ("a", 105)];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .build_from_sorted_pairs(patvals)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 1, 105), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 100), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_iter<P>(&self, haystack: P) -> FindIterator<'_, P>
+    where
+        P: AsRef<[u8]>,
+    {
+        assert!(
+            self.pma.match_kind.is_standard(),
+            "Error: match_kind must be standard."
+        );
+        FindIterator {
+            pma: self,
+            haystack,
+            pos: 0,
+        }
+    }
+
+    /// Returns an iterator of overlapping matches in the given haystack.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::DoubleArrayAhoCorasickBuilder;
+    ///
+    /// let patvals = vec![("bcd", 100), ("ab", 101), ("a", 105)];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .build_from_sorted_pairs(patvals)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_overlapping_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 1, 105), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 2, 101), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 4, 100), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_overlapping_iter<P>(&self, haystack: P) -> FindOverlappingIterator<'_, P>
+    where
+        P: AsRef<[u8]>,
+    {
+        assert!(
+            self.pma.match_kind.is_standard(),
+            "Error: match_kind must be standard."
+        );
+        FindOverlappingIterator {
+            pma: self,
+            haystack,
+            state_id: ROOT_STATE_IDX,
+            pos: 0,
+            output_pos: None,
+        }
+    }
+
+    /// Returns an iterator of leftmost matches in the given haystack.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - String to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::LeftmostFirst`] or [`MatchKind::LeftmostLongest`] in
+    /// the construction, the iterator is not supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasickBuilder, MatchKind};
+    ///
+    /// let patvals = vec![("ab", 1), ("a", 2), ("abcd", 3)];
+    /// let pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .match_kind(MatchKind::LeftmostLongest)
+    ///     .build_from_sorted_pairs(patvals)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.leftmost_find_iter("abcd");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 4, 3), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn leftmost_find_iter<P>(&self, haystack: P) -> LeftmostFindIterator<'_, P>
+    where
+        P: AsRef<[u8]>,
+    {
+        assert!(
+            self.pma.match_kind.is_leftmost(),
+            "Error: match_kind must be leftmost."
+        );
+        LeftmostFindIterator {
+            pma: self,
+            haystack,
+            pos: 0,
+        }
+    }
+
+    /// Returns the total amount of heap used by this automaton in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{DoubleArrayAhoCorasick, DoubleArrayAhoCorasickBuilder};
+    ///
+    /// let patvals: Vec<_> = (0..1000).map(|i| (format!("{i}"), i)).collect();
+    ///
+    /// let pma = DoubleArrayAhoCorasick::with_values(patvals.clone()).unwrap();
+    /// let sorted_pma = DoubleArrayAhoCorasickBuilder::new()
+    ///     .build_from_sorted_pairs(patvals)
+    ///     .unwrap();
+    ///
+    /// assert!(sorted_pma.heap_bytes() < pma.heap_bytes());
+    /// ```
+    #[must_use]
+    pub fn heap_bytes(&self) -> usize {
+        self.pma.heap_bytes() + self.values.heap_bytes()
+    }
+
+    /// Returns the total number of states this automaton has.
+    #[must_use]
+    pub fn num_states(&self) -> usize {
+        self.pma.num_states()
+    }
+
+    /// Returns the [`MatchKind`] specified in the construction.
+    #[inline(always)]
+    #[must_use]
+    pub fn match_kind(&self) -> MatchKind {
+        self.pma.match_kind
+    }
+
+    #[inline(always)]
+    fn new_match(&self, output_pos: NonZeroU32, end: usize) -> Match<u32> {
+        let idx = usize::from_u32(output_pos.get() - 1);
+        Match {
+            length: usize::from_u32(self.pma.outputs[idx].length()),
+            end,
+            value: self.values.get(idx),
+        }
+    }
+
+    #[inline(always)]
+    fn output_pos(&self, state_id: u32) -> Option<NonZeroU32> {
+        // state_id is always smaller than self.pma.states.len() because it is a value returned
+        // by self.pma.next_state_id_unchecked() or next_state_id_leftmost_unchecked().
+        unsafe {
+            self.pma
+                .states
+                .get_unchecked(usize::from_u32(state_id))
+                .output_pos()
+        }
+    }
+}
+
+impl core::fmt::Debug for SortedDoubleArrayAhoCorasick {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SortedDoubleArrayAhoCorasick")
+            .field("match_kind", &self.pma.match_kind)
+            .field("num_states", &self.pma.num_states)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Iterator created by [`SortedDoubleArrayAhoCorasick::find_iter()`].
+pub struct FindIterator<'a, P> {
+    pma: &'a SortedDoubleArrayAhoCorasick,
+    haystack: P,
+    pos: usize,
+}
+
+impl<P> Clone for FindIterator<'_, P>
+where
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            pos: self.pos,
+        }
+    }
+}
+
+impl<P> Iterator for FindIterator<'_, P>
+where
+    P: AsRef<[u8]>,
+{
+    type Item = Match<u32>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut state_id = ROOT_STATE_IDX;
+        let haystack = self.haystack.as_ref();
+        for (pos, &c) in haystack.iter().enumerate().skip(self.pos) {
+            // state_id is always smaller than self.pma.pma.states.len() because
+            // next_state_id_unchecked() ensures to return such a value.
+            state_id = unsafe { self.pma.pma.next_state_id_unchecked(state_id, c) };
+            if let Some(output_pos) = self.pma.output_pos(state_id) {
+                self.pos = pos + 1;
+                return Some(self.pma.new_match(output_pos, self.pos));
+            }
+        }
+        self.pos = haystack.len();
+        None
+    }
+}
+
+/// Iterator created by [`SortedDoubleArrayAhoCorasick::find_overlapping_iter()`].
+pub struct FindOverlappingIterator<'a, P> {
+    pma: &'a SortedDoubleArrayAhoCorasick,
+    haystack: P,
+    state_id: u32,
+    pos: usize,
+    output_pos: Option<NonZeroU32>,
+}
+
+impl<P> Clone for FindOverlappingIterator<'_, P>
+where
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            state_id: self.state_id,
+            pos: self.pos,
+            output_pos: self.output_pos,
+        }
+    }
+}
+
+impl<P> Iterator for FindOverlappingIterator<'_, P>
+where
+    P: AsRef<[u8]>,
+{
+    type Item = Match<u32>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(output_pos) = self.output_pos {
+            self.output_pos = self.pma.pma.outputs[usize::from_u32(output_pos.get() - 1)].parent();
+            return Some(self.pma.new_match(output_pos, self.pos));
+        }
+        let haystack = self.haystack.as_ref();
+        while self.pos < haystack.len() {
+            let c = haystack[self.pos];
+            self.pos += 1;
+            // self.state_id is always smaller than self.pma.pma.states.len() because
+            // next_state_id_unchecked() ensures to return such a value.
+            self.state_id = unsafe { self.pma.pma.next_state_id_unchecked(self.state_id, c) };
+            if let Some(output_pos) = self.pma.output_pos(self.state_id) {
+                self.output_pos =
+                    self.pma.pma.outputs[usize::from_u32(output_pos.get() - 1)].parent();
+                return Some(self.pma.new_match(output_pos, self.pos));
+            }
+        }
+        None
+    }
+}
+
+/// Iterator created by [`SortedDoubleArrayAhoCorasick::leftmost_find_iter()`].
+pub struct LeftmostFindIterator<'a, P> {
+    pma: &'a SortedDoubleArrayAhoCorasick,
+    haystack: P,
+    pos: usize,
+}
+
+impl<P> Clone for LeftmostFindIterator<'_, P>
+where
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            pos: self.pos,
+        }
+    }
+}
+
+impl<P> Iterator for LeftmostFindIterator<'_, P>
+where
+    P: AsRef<[u8]>,
+{
+    type Item = Match<u32>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut state_id = ROOT_STATE_IDX;
+        let mut last_output_pos: Option<NonZeroU32> = None;
+
+        let haystack = self.haystack.as_ref();
+        for (pos, &c) in haystack.iter().enumerate().skip(self.pos) {
+            // state_id is always smaller than self.pma.pma.states.len() because
+            // next_state_id_leftmost_unchecked() ensures to return such a value.
+            state_id = unsafe { self.pma.pma.next_state_id_leftmost_unchecked(state_id, c) };
+            if state_id == ROOT_STATE_IDX {
+                if let Some(output_pos) = last_output_pos {
+                    return Some(self.pma.new_match(output_pos, self.pos));
+                }
+            } else if let Some(output_pos) = self.pma.output_pos(state_id) {
+                last_output_pos.replace(output_pos);
+                self.pos = pos + 1;
+            }
+        }
+
+        last_output_pos.map(|output_pos| self.pma.new_match(output_pos, self.pos))
+    }
+}
+
+/// Sorted integers represented by the first value of each block and bit-packed differences from
+/// it.
+#[derive(Clone, Eq, Hash, PartialEq)]
+struct DeltaValues {
+    bases: Vec<u32>,
+    widths: Vec<u8>,
+    // Starting bit positions of blocks in `bits`.
+    starts: Vec<u32>,
+    bits: Vec<u64>,
+}
+
+impl DeltaValues {
+    /// Creates a new instance from values sorted in ascending order.
+    fn new(values: &[u32]) -> Self {
+        let mut bases = vec![];
+        let mut widths = vec![];
+        let mut starts = vec![];
+        let mut bits = vec![];
+        let mut num_bits = 0;
+        for block in values.chunks(DELTA_BLOCK_LEN) {
+            let base = block[0];
+            let width = 32 - (block[block.len() - 1] - base).leading_zeros();
+            bases.push(base);
+            widths.push(u8::try_from(width).unwrap());
+            starts.push(u32::try_from(num_bits).unwrap());
+            if width == 0 {
+                continue;
+            }
+            for &x in block {
+                let delta = u64::from(x - base);
+                let (word, shift) = (num_bits / 64, num_bits % 64);
+                if word >= bits.len() {
+                    bits.push(0);
+                }
+                bits[word] |= delta << shift;
+                if shift + usize::from_u32(width) > 64 {
+                    bits.push(delta >> (64 - shift));
+                }
+                num_bits += usize::from_u32(width);
+            }
+        }
+        bases.shrink_to_fit();
+        widths.shrink_to_fit();
+        starts.shrink_to_fit();
+        bits.shrink_to_fit();
+        Self {
+            bases,
+            widths,
+            starts,
+            bits,
+        }
+    }
+
+    #[inline(always)]
+    fn get(&self, i: usize) -> u32 {
+        let block = i / DELTA_BLOCK_LEN;
+        let width = usize::from(self.widths[block]);
+        if width == 0 {
+            return self.bases[block];
+        }
+        let pos = usize::from_u32(self.starts[block]) + (i % DELTA_BLOCK_LEN) * width;
+        let (word, shift) = (pos / 64, pos % 64);
+        let mut delta = self.bits[word] >> shift;
+        if shift + width > 64 {
+            delta |= self.bits[word + 1] << (64 - shift);
+        }
+        let delta = u32::try_from(delta & ((1 << width) - 1)).unwrap();
+        self.bases[block] + delta
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.bases.len() * mem::size_of::<u32>()
+            + self.widths.len() * mem::size_of::<u8>()
+            + self.starts.len() * mem::size_of::<u32>()
+            + self.bits.len() * mem::size_of::<u64>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::DoubleArrayAhoCorasickBuilder;
+
+    #[test]
+    fn test_delta_values() {
+        let mut values = vec![];
+        let mut x = 3;
+        for i in 0..1000u32 {
+            values.push(x);
+            x += (i * 7919) % 100;
+            if i % 97 == 0 {
+                x += 1 << 20;
+            }
+        }
+        values.push(u32::MAX);
+        let delta_values = DeltaValues::new(&values);
+        for (i, &x) in values.iter().enumerate() {
+            assert_eq!(x, delta_values.get(i));
+        }
+    }
+
+    #[test]
+    fn test_delta_values_constant() {
+        let values = vec![42; 100];
+        let delta_values = DeltaValues::new(&values);
+        assert!(delta_values.bits.is_empty());
+        for i in 0..values.len() {
+            assert_eq!(42, delta_values.get(i));
+        }
+    }
+
+    #[test]
+    fn test_search_consistency() {
+        let patterns = ["abba", "baaba", "ababa", "ba", "a", "bb", "aab"];
+        let patvals: Vec<_> = patterns
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| (p, u32::try_from(i * 3 + 10).unwrap()))
+            .collect();
+        let haystack = "abbababaabaabbbaababab";
+
+        let pma = DoubleArrayAhoCorasick::with_values(patvals.clone()).unwrap();
+        let sorted_pma = DoubleArrayAhoCorasickBuilder::new()
+            .build_from_sorted_pairs(patvals.clone())
+            .unwrap();
+        assert_eq!(
+            pma.find_iter(haystack).collect::<Vec<_>>(),
+            sorted_pma.find_iter(haystack).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            pma.find_overlapping_iter(haystack).collect::<Vec<_>>(),
+            sorted_pma
+                .find_overlapping_iter(haystack)
+                .collect::<Vec<_>>()
+        );
+
+        for match_kind in [MatchKind::LeftmostLongest, MatchKind::LeftmostFirst] {
+            let pma = DoubleArrayAhoCorasickBuilder::new()
+                .match_kind(match_kind)
+                .build_with_values(patvals.clone())
+                .unwrap();
+            let sorted_pma = DoubleArrayAhoCorasickBuilder::new()
+                .match_kind(match_kind)
+                .build_from_sorted_pairs(patvals.clone())
+                .unwrap();
+            assert_eq!(
+                pma.leftmost_find_iter(haystack).collect::<Vec<_>>(),
+                sorted_pma.leftmost_find_iter(haystack).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_unsorted_values() {
+        let patvals = vec![("a", 1), ("b", 0)];
+        assert!(DoubleArrayAhoCorasickBuilder::new()
+            .build_from_sorted_pairs(patvals)
+            .is_err());
+    }
+}
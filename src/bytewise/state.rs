@@ -0,0 +1,196 @@
+//! States of the double array.
+//!
+//! The items are public only to be used as the default type parameter of
+//! [`DoubleArrayAhoCorasick`](super::DoubleArrayAhoCorasick) and in
+//! [`DoubleArrayAhoCorasick16`](super::DoubleArrayAhoCorasick16). This module is private, so they
+//! cannot be named outside the crate, and [`DoubleArrayState`] cannot be implemented for other
+//! types.
+
+use core::num::NonZeroU32;
+
+use alloc::vec::Vec;
+
+use crate::errors::{DaachorseError, Result};
+use crate::intpack::{U24nU8, U24};
+use crate::serializer::Serializable;
+
+/// Supertrait preventing [`DoubleArrayState`] from being implemented outside the crate.
+pub trait Sealed {}
+
+/// Representation of a state of the double array, which the search is generic over.
+///
+/// Indices are returned as [`u32`] regardless of how they are stored.
+pub trait DoubleArrayState: Sealed + Copy {
+    /// Returns the base value, or `None` if the state has no children.
+    fn base(&self) -> Option<NonZeroU32>;
+
+    /// Returns the label of the transition entering the state.
+    fn check(&self) -> u8;
+
+    /// Returns the ID of the fail state.
+    fn fail(&self) -> u32;
+
+    /// Returns the position of the output plus one, or `None` if the state has no output.
+    fn output_pos(&self) -> Option<NonZeroU32>;
+}
+
+/// State of the double array, whose fields are represented in the space of 12 bytes.
+#[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
+pub struct State {
+    base: Option<NonZeroU32>,
+    fail: u32,
+    // 3 bytes for output_pos and 1 byte for check.
+    opos_ch: U24nU8,
+}
+
+impl State {
+    #[inline(always)]
+    pub(crate) fn set_base(&mut self, x: NonZeroU32) {
+        self.base = Some(x);
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_check(&mut self, x: u8) {
+        self.opos_ch.set_b(x);
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_fail(&mut self, x: u32) {
+        self.fail = x;
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_output_pos(&mut self, x: Option<NonZeroU32>) -> Result<()> {
+        let x = x.map_or(0, NonZeroU32::get);
+        if let Ok(x) = U24::try_from(x) {
+            self.opos_ch.set_a(x);
+            Ok(())
+        } else {
+            Err(DaachorseError::automaton_scale("output_pos", U24::MAX))
+        }
+    }
+}
+
+impl DoubleArrayState for State {
+    #[inline(always)]
+    fn base(&self) -> Option<NonZeroU32> {
+        self.base
+    }
+
+    #[inline(always)]
+    fn check(&self) -> u8 {
+        self.opos_ch.b()
+    }
+
+    #[inline(always)]
+    fn fail(&self) -> u32 {
+        self.fail
+    }
+
+    #[inline(always)]
+    fn output_pos(&self) -> Option<NonZeroU32> {
+        NonZeroU32::new(self.opos_ch.a().get())
+    }
+}
+
+impl Serializable for State {
+    #[inline(always)]
+    fn serialize_to_vec(&self, dst: &mut Vec<u8>) {
+        self.base.serialize_to_vec(dst);
+        self.fail.serialize_to_vec(dst);
+        self.opos_ch.serialize_to_vec(dst);
+    }
+
+    #[inline(always)]
+    fn deserialize_from_slice(src: &[u8]) -> (Self, &[u8]) {
+        let (base, src) = Option::<NonZeroU32>::deserialize_from_slice(src);
+        let (fail, src) = u32::deserialize_from_slice(src);
+        let (opos_ch, src) = U24nU8::deserialize_from_slice(src);
+        (
+            Self {
+                base,
+                fail,
+                opos_ch,
+            },
+            src,
+        )
+    }
+
+    #[inline(always)]
+    fn serialized_bytes() -> usize {
+        Option::<NonZeroU32>::serialized_bytes()
+            + u32::serialized_bytes()
+            + U24nU8::serialized_bytes()
+    }
+}
+
+impl core::fmt::Debug for State {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("State")
+            .field("base", &self.base())
+            .field("check", &self.check())
+            .field("fail", &self.fail())
+            .field("output_pos", &self.output_pos())
+            .finish()
+    }
+}
+
+impl Sealed for State {}
+
+/// State of the double array, whose fields are represented in the space of 8 bytes.
+#[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
+pub struct State16 {
+    // Zero if the state has no children.
+    pub(crate) base: u16,
+    pub(crate) fail: u16,
+    // Zero if the state has no output.
+    pub(crate) output_pos: u16,
+    pub(crate) check: u8,
+}
+
+impl DoubleArrayState for State16 {
+    #[inline(always)]
+    fn base(&self) -> Option<NonZeroU32> {
+        NonZeroU32::new(u32::from(self.base))
+    }
+
+    #[inline(always)]
+    fn check(&self) -> u8 {
+        self.check
+    }
+
+    #[inline(always)]
+    fn fail(&self) -> u32 {
+        u32::from(self.fail)
+    }
+
+    #[inline(always)]
+    fn output_pos(&self) -> Option<NonZeroU32> {
+        NonZeroU32::new(u32::from(self.output_pos))
+    }
+}
+
+impl Sealed for State16 {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_state() {
+        let mut opos_ch = U24nU8::default();
+        opos_ch.set_a(U24::try_from(57).unwrap());
+        opos_ch.set_b(77);
+        let x = State {
+            base: NonZeroU32::new(42),
+            fail: 13,
+            opos_ch,
+        };
+        let mut data = vec![];
+        x.serialize_to_vec(&mut data);
+        assert_eq!(data.len(), State::serialized_bytes());
+        let (y, rest) = State::deserialize_from_slice(&data);
+        assert!(rest.is_empty());
+        assert_eq!(x, y);
+    }
+}
@@ -0,0 +1,101 @@
+//! Expansion of patterns with wildcards into byte strings.
+
+use alloc::vec::Vec;
+
+/// Expands a pattern with wildcards into the byte strings it matches.
+///
+/// The following syntax is supported:
+///
+/// - `?` matches any single byte.
+/// - `[abc]` matches any single byte in the brackets.
+/// - `\x` matches the byte `x` literally, e.g., `\?`, `\[`, or `\\`.
+///
+/// The resulting byte strings are in lexicographic order of the alternatives, and duplicate
+/// bytes in a class are ignored.
+///
+/// # Errors
+///
+/// A description of the problem is returned if the pattern is malformed or if the number of
+/// byte strings exceeds `max_expansions`.
+pub fn expand(pattern: &[u8], max_expansions: usize) -> Result<Vec<Vec<u8>>, &'static str> {
+    let mut classes: Vec<Vec<u8>> = vec![];
+    let mut num_expansions = 1usize;
+    let mut bytes = pattern.iter().copied();
+    while let Some(c) = bytes.next() {
+        let class = match c {
+            b'?' => (0..=u8::MAX).collect(),
+            b'[' => {
+                let mut class = vec![];
+                loop {
+                    match bytes.next() {
+                        Some(b']') => break,
+                        Some(b'\\') => class.push(bytes.next().ok_or("trailing backslash")?),
+                        Some(c) => class.push(c),
+                        None => return Err("unterminated character class"),
+                    }
+                }
+                class.sort_unstable();
+                class.dedup();
+                if class.is_empty() {
+                    return Err("empty character class");
+                }
+                class
+            }
+            b'\\' => vec![bytes.next().ok_or("trailing backslash")?],
+            c => vec![c],
+        };
+        num_expansions = num_expansions
+            .checked_mul(class.len())
+            .filter(|&n| n <= max_expansions)
+            .ok_or("too many expansions")?;
+        classes.push(class);
+    }
+
+    let mut expansions = vec![vec![]];
+    for class in classes {
+        let mut next = Vec::with_capacity(expansions.len() * class.len());
+        for prefix in &expansions {
+            for &c in &class {
+                let mut pattern = Vec::with_capacity(prefix.len() + 1);
+                pattern.extend_from_slice(prefix);
+                pattern.push(c);
+                next.push(pattern);
+            }
+        }
+        expansions = next;
+    }
+    Ok(expansions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(Ok(vec![b"abc".to_vec()]), expand(b"abc", 10));
+        assert_eq!(
+            Ok(vec![b"xay".to_vec(), b"xby".to_vec(), b"xcy".to_vec()]),
+            expand(b"x[cab]y", 10)
+        );
+        assert_eq!(
+            Ok(vec![b"a?".to_vec(), b"a]".to_vec()]),
+            expand(b"a[\\?\\]?]", 10)
+        );
+        assert_eq!(Ok(vec![b"a[\\".to_vec()]), expand(b"a\\[\\\\", 10));
+
+        let expansions = expand(b"a?", 256).unwrap();
+        assert_eq!(256, expansions.len());
+        assert_eq!(b"a\x00".as_slice(), expansions[0]);
+        assert_eq!(b"a\xff".as_slice(), expansions[255]);
+    }
+
+    #[test]
+    fn test_expand_invalid() {
+        assert_eq!(Err("unterminated character class"), expand(b"a[bc", 10));
+        assert_eq!(Err("empty character class"), expand(b"a[]", 10));
+        assert_eq!(Err("trailing backslash"), expand(b"a\\", 10));
+        assert_eq!(Err("too many expansions"), expand(b"[ab][abc]", 5));
+        assert_eq!(Err("too many expansions"), expand(&[b'?'; 20], usize::MAX));
+    }
+}
@@ -16,12 +16,12 @@ use crate::{MatchKind, Output};
 pub use builder::CharwiseDoubleArrayAhoCorasickBuilder;
 use iter::{
     CharWithEndOffsetIterator, FindIterator, FindOverlappingIterator,
-    FindOverlappingNoSuffixIterator, LestmostFindIterator, StrIterator,
+    FindOverlappingNoSuffixIterator, LeftmostFindIterator, StrIterator,
 };
-use mapper::CodeMapper;
+pub(crate) use mapper::{CodeMapper, Haystack, Mapper, Sealed};
 
 // The root index position.
-const ROOT_STATE_IDX: u32 = 0;
+pub(crate) const ROOT_STATE_IDX: u32 = 0;
 // The dead index position.
 const DEAD_STATE_IDX: u32 = 1;
 
@@ -54,10 +54,22 @@ const DEAD_STATE_IDX: u32 = 1;
 ///
 /// - [`CharwiseDoubleArrayAhoCorasick::with_values`] builds an automaton
 ///   from a set of pairs of a UTF-8 string and a user-defined value.
+///
+/// # Thread safety
+///
+/// The automaton is immutable after the construction, and all search methods take `&self`.
+/// Thus, [`CharwiseDoubleArrayAhoCorasick`] is [`Send`] and [`Sync`] if `V` is, and an automaton
+/// can be shared among threads, e.g., with [`Arc`](alloc::sync::Arc), without any locking.
+///
+/// # Mapping of symbols
+///
+/// The type parameter `M` is the mapping from symbols to transition labels, which maps [`char`]
+/// by default. See [`DoubleArrayAhoCorasickGeneric`](crate::generic::DoubleArrayAhoCorasickGeneric)
+/// for the variant with integer symbols.
 #[derive(Clone, Eq, Hash, PartialEq)]
-pub struct CharwiseDoubleArrayAhoCorasick<V> {
+pub struct CharwiseDoubleArrayAhoCorasick<V, M = CodeMapper> {
     states: Vec<State>,
-    mapper: CodeMapper,
+    mapper: M,
     outputs: Vec<Output<V>>,
     match_kind: MatchKind,
     num_states: u32,
@@ -190,6 +202,7 @@ impl<V> CharwiseDoubleArrayAhoCorasick<V> {
         FindIterator {
             pma: self,
             haystack: unsafe { CharWithEndOffsetIterator::new(StrIterator::new(haystack)) },
+            pos: 0,
         }
     }
 
@@ -239,6 +252,7 @@ impl<V> CharwiseDoubleArrayAhoCorasick<V> {
         FindIterator {
             pma: self,
             haystack: CharWithEndOffsetIterator::new(haystack),
+            pos: 0,
         }
     }
 
@@ -402,6 +416,7 @@ impl<V> CharwiseDoubleArrayAhoCorasick<V> {
             pma: self,
             haystack: unsafe { CharWithEndOffsetIterator::new(StrIterator::new(haystack)) },
             state_id: ROOT_STATE_IDX,
+            pos: 0,
         }
     }
 
@@ -461,6 +476,7 @@ impl<V> CharwiseDoubleArrayAhoCorasick<V> {
             pma: self,
             haystack: CharWithEndOffsetIterator::new(haystack),
             state_id: ROOT_STATE_IDX,
+            pos: 0,
         }
     }
 
@@ -527,7 +543,7 @@ impl<V> CharwiseDoubleArrayAhoCorasick<V> {
     ///
     /// assert_eq!(None, it.next());
     /// ```
-    pub fn leftmost_find_iter<P>(&self, haystack: P) -> LestmostFindIterator<'_, P, V>
+    pub fn leftmost_find_iter<P>(&self, haystack: P) -> LeftmostFindIterator<'_, P, V>
     where
         P: AsRef<str>,
     {
@@ -535,66 +551,13 @@ impl<V> CharwiseDoubleArrayAhoCorasick<V> {
             self.match_kind.is_leftmost(),
             "Error: match_kind must be leftmost."
         );
-        LestmostFindIterator {
+        LeftmostFindIterator {
             pma: self,
             haystack,
             pos: 0,
         }
     }
 
-    /// Returns the total number of states this automaton has.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use daachorse::CharwiseDoubleArrayAhoCorasick;
-    ///
-    /// let patterns = vec!["bcd", "ab", "a"];
-    /// let pma = CharwiseDoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
-    ///
-    /// assert_eq!(pma.num_states(), 6);
-    /// ```
-    #[must_use]
-    pub fn num_states(&self) -> usize {
-        usize::from_u32(self.num_states)
-    }
-
-    /// Returns the total number of elements of the double array.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use daachorse::CharwiseDoubleArrayAhoCorasick;
-    ///
-    /// let patterns = vec!["bcd", "ab", "a"];
-    /// let pma = CharwiseDoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
-    ///
-    /// assert_eq!(pma.num_elements(), 8);
-    /// ```
-    #[must_use]
-    pub fn num_elements(&self) -> usize {
-        self.states.len()
-    }
-
-    /// Returns the total amount of heap used by this automaton in bytes.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use daachorse::CharwiseDoubleArrayAhoCorasick;
-    ///
-    /// let patterns = vec!["bcd", "ab", "a"];
-    /// let pma = CharwiseDoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
-    ///
-    /// assert_eq!(568, pma.heap_bytes());
-    /// ```
-    #[must_use]
-    pub fn heap_bytes(&self) -> usize {
-        self.states.len() * mem::size_of::<State>()
-            + self.mapper.heap_bytes()
-            + self.outputs.len() * mem::size_of::<Output<V>>()
-    }
-
     /// Serializes the automaton into a [`Vec`].
     ///
     /// # Examples
@@ -686,6 +649,127 @@ impl<V> CharwiseDoubleArrayAhoCorasick<V> {
             source,
         )
     }
+}
+
+impl<V, M> CharwiseDoubleArrayAhoCorasick<V, M>
+where
+    M: Mapper,
+{
+    /// Returns the total number of states this automaton has.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::CharwiseDoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = CharwiseDoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// assert_eq!(pma.num_states(), 6);
+    /// ```
+    #[must_use]
+    pub fn num_states(&self) -> usize {
+        usize::from_u32(self.num_states)
+    }
+
+    /// Returns the [`MatchKind`] specified in the construction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{CharwiseDoubleArrayAhoCorasick, MatchKind};
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = CharwiseDoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// assert_eq!(MatchKind::Standard, pma.match_kind());
+    /// ```
+    #[inline(always)]
+    #[must_use]
+    pub const fn match_kind(&self) -> MatchKind {
+        self.match_kind
+    }
+
+    /// Returns `true` if the automaton supports the searches requiring
+    /// [`MatchKind::Standard`], such as [`CharwiseDoubleArrayAhoCorasick::find_iter()`] and
+    /// [`CharwiseDoubleArrayAhoCorasick::find_overlapping_iter()`], which panic otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::{CharwiseDoubleArrayAhoCorasickBuilder, MatchKind};
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = CharwiseDoubleArrayAhoCorasickBuilder::new()
+    ///     .match_kind(MatchKind::LeftmostFirst)
+    ///     .build::<_, _, usize>(patterns)
+    ///     .unwrap();
+    ///
+    /// assert!(!pma.supports_overlapping());
+    /// assert!(pma.supports_leftmost());
+    /// ```
+    #[inline(always)]
+    #[must_use]
+    pub fn supports_overlapping(&self) -> bool {
+        self.match_kind.is_standard()
+    }
+
+    /// Returns `true` if the automaton supports the searches requiring
+    /// [`MatchKind::LeftmostFirst`] or [`MatchKind::LeftmostLongest`], such as
+    /// [`CharwiseDoubleArrayAhoCorasick::leftmost_find_iter()`], which panic otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::CharwiseDoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = CharwiseDoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// assert!(pma.supports_overlapping());
+    /// assert!(!pma.supports_leftmost());
+    /// ```
+    #[inline(always)]
+    #[must_use]
+    pub fn supports_leftmost(&self) -> bool {
+        self.match_kind.is_leftmost()
+    }
+
+    /// Returns the total number of elements of the double array.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::CharwiseDoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = CharwiseDoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// assert_eq!(pma.num_elements(), 8);
+    /// ```
+    #[must_use]
+    pub fn num_elements(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Returns the total amount of heap used by this automaton in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::CharwiseDoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["bcd", "ab", "a"];
+    /// let pma = CharwiseDoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+    ///
+    /// assert_eq!(568, pma.heap_bytes());
+    /// ```
+    #[must_use]
+    pub fn heap_bytes(&self) -> usize {
+        self.states.len() * mem::size_of::<State>()
+            + self.mapper.heap_bytes()
+            + self.outputs.len() * mem::size_of::<Output<V>>()
+    }
 
     /// # Safety
     ///
@@ -718,8 +802,8 @@ impl<V> CharwiseDoubleArrayAhoCorasick<V> {
     ///
     /// `state_id` must be smaller than the length of states.
     #[inline(always)]
-    unsafe fn next_state_id_unchecked(&self, mut state_id: u32, c: char) -> u32 {
-        if let Some(mapped_c) = self.mapper.get(c) {
+    unsafe fn next_state_id_unchecked(&self, mut state_id: u32, c: M::Symbol) -> u32 {
+        if let Some(mapped_c) = self.mapper.get(c.into()) {
             loop {
                 if let Some(state_id) = self.child_index_unchecked(state_id, mapped_c) {
                     return state_id;
@@ -738,8 +822,8 @@ impl<V> CharwiseDoubleArrayAhoCorasick<V> {
     ///
     /// `state_id` must be smaller than the length of states.
     #[inline(always)]
-    unsafe fn next_state_id_leftmost_unchecked(&self, mut state_id: u32, c: char) -> u32 {
-        if let Some(mapped_c) = self.mapper.get(c) {
+    unsafe fn next_state_id_leftmost_unchecked(&self, mut state_id: u32, c: M::Symbol) -> u32 {
+        if let Some(mapped_c) = self.mapper.get(c.into()) {
             loop {
                 if let Some(state_id) = self.child_index_unchecked(state_id, mapped_c) {
                     return state_id;
@@ -760,7 +844,7 @@ impl<V> CharwiseDoubleArrayAhoCorasick<V> {
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-struct State {
+pub(crate) struct State {
     base: Option<NonZeroU32>,
     check: u32,
     fail: u32,
@@ -1066,4 +1150,51 @@ mod tests {
         assert_eq!(pma.match_kind, other.match_kind);
         assert_eq!(pma.num_states, other.num_states);
     }
+
+    #[test]
+    fn test_is_primary() {
+        let patterns = vec!["全世界", "世界", "界", "に"];
+        let pma = CharwiseDoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        let mut it = pma.find_overlapping_iter("全世界に");
+        let mut matches = vec![];
+        while let Some(m) = it.next() {
+            matches.push((m.value(), it.is_primary()));
+        }
+        assert_eq!(vec![(0, true), (1, false), (2, false), (3, true)], matches);
+    }
+
+    #[test]
+    fn test_bytes_scanned() {
+        let patterns = vec!["世界", "に"];
+        let pma = CharwiseDoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+        let haystack = "全世界に";
+
+        let mut it = pma.find_iter(haystack);
+        it.next();
+        assert_eq!(9, it.bytes_scanned());
+        it.next();
+        assert_eq!(12, it.bytes_scanned());
+        assert_eq!(None, it.next());
+        assert_eq!(12, it.bytes_scanned());
+
+        let mut it = pma.find_overlapping_no_suffix_iter(haystack);
+        it.next();
+        assert_eq!(9, it.bytes_scanned());
+    }
+
+    #[test]
+    fn test_clone_iter() {
+        let patterns = vec!["全世界", "世界", "界", "に"];
+        let pma = CharwiseDoubleArrayAhoCorasick::<u32>::new(patterns).unwrap();
+
+        let mut it = pma.find_overlapping_iter("全世界に");
+        it.next();
+        let cloned = it.clone();
+        assert_eq!(it.collect::<Vec<_>>(), cloned.collect::<Vec<_>>());
+
+        let mut it = pma.find_iter("世界に界");
+        it.next();
+        let cloned = it.clone();
+        assert_eq!(it.collect::<Vec<_>>(), cloned.collect::<Vec<_>>());
+    }
 }
@@ -2,22 +2,22 @@ use core::num::NonZeroU32;
 
 use alloc::vec::Vec;
 
-use crate::charwise::{CharwiseDoubleArrayAhoCorasick, CodeMapper, MatchKind, State};
+use crate::charwise::{CharwiseDoubleArrayAhoCorasick, CodeMapper, Mapper, MatchKind, State};
 use crate::errors::{DaachorseError, Result};
-use crate::nfa_builder::NfaBuilder;
+use crate::nfa_builder::{EdgeLabel, NfaBuilder};
 use crate::utils::FromU32;
 use crate::BuildHelper;
 
 use crate::charwise::{DEAD_STATE_IDX, ROOT_STATE_IDX};
 use crate::nfa_builder::{DEAD_STATE_ID, ROOT_STATE_ID};
 
-// Specialized [`NfaBuilder`] handling labels of `char`.
-type CharwiseNfaBuilder<V> = NfaBuilder<char, V>;
-
 /// Builder for [`CharwiseDoubleArrayAhoCorasick`].
-pub struct CharwiseDoubleArrayAhoCorasickBuilder {
+///
+/// The type parameter `M` is the mapping from symbols to transition labels, which maps [`char`]
+/// by default.
+pub struct CharwiseDoubleArrayAhoCorasickBuilder<M = CodeMapper> {
     states: Vec<State>,
-    mapper: CodeMapper,
+    mapper: M,
     match_kind: MatchKind,
     block_len: u32,
     num_free_blocks: u32,
@@ -54,43 +54,7 @@ impl CharwiseDoubleArrayAhoCorasickBuilder {
     /// ```
     #[must_use]
     pub fn new() -> Self {
-        Self {
-            states: vec![],
-            mapper: CodeMapper::default(),
-            match_kind: MatchKind::Standard,
-            block_len: 0,
-            num_free_blocks: 16,
-        }
-    }
-
-    /// Specifies [`MatchKind`] to build.
-    ///
-    /// # Arguments
-    ///
-    /// * `kind` - Match kind.
-    #[must_use]
-    pub const fn match_kind(mut self, kind: MatchKind) -> Self {
-        self.match_kind = kind;
-        self
-    }
-
-    /// Specifies the number of last blocks to search bases.
-    ///
-    /// The smaller the number is, the faster the construction time will be; however, the memory
-    /// efficiency can be degraded.
-    ///
-    /// # Arguments
-    ///
-    /// * `n` - The number of last blocks.
-    ///
-    /// # Panics
-    ///
-    /// `n` must be greater than or equal to 1.
-    #[must_use]
-    pub const fn num_free_blocks(mut self, n: u32) -> Self {
-        assert!(n >= 1);
-        self.num_free_blocks = n;
-        self
+        Self::new_generic()
     }
 
     /// Builds and returns a new [`CharwiseDoubleArrayAhoCorasick`] from input patterns. The value
@@ -182,16 +146,77 @@ impl CharwiseDoubleArrayAhoCorasickBuilder {
     ///
     /// assert_eq!(None, it.next());
     /// ```
-    pub fn build_with_values<I, P, V>(
+    pub fn build_with_values<I, P, V>(self, patvals: I) -> Result<CharwiseDoubleArrayAhoCorasick<V>>
+    where
+        I: IntoIterator<Item = (P, V)>,
+        P: AsRef<str>,
+        V: Copy,
+    {
+        self.build_with_symbols(patvals, |pattern, symbols| {
+            symbols.extend(pattern.as_ref().chars());
+        })
+    }
+}
+
+impl<M> CharwiseDoubleArrayAhoCorasickBuilder<M>
+where
+    M: Mapper,
+{
+    /// Creates a new builder with the default options.
+    pub(crate) fn new_generic() -> Self {
+        Self {
+            states: vec![],
+            mapper: M::default(),
+            match_kind: MatchKind::Standard,
+            block_len: 0,
+            num_free_blocks: 16,
+        }
+    }
+
+    /// Specifies [`MatchKind`] to build.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - Match kind.
+    #[must_use]
+    pub const fn match_kind(mut self, kind: MatchKind) -> Self {
+        self.match_kind = kind;
+        self
+    }
+
+    /// Specifies the number of last blocks to search bases.
+    ///
+    /// The smaller the number is, the faster the construction time will be; however, the memory
+    /// efficiency can be degraded.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number of last blocks.
+    ///
+    /// # Panics
+    ///
+    /// `n` must be greater than or equal to 1.
+    #[must_use]
+    pub const fn num_free_blocks(mut self, n: u32) -> Self {
+        assert!(n >= 1);
+        self.num_free_blocks = n;
+        self
+    }
+
+    /// Builds an automaton from pattern-value pairs, where `to_symbols` appends the symbols of a
+    /// pattern to the given vector.
+    pub(crate) fn build_with_symbols<I, P, V, L, F>(
         mut self,
         patvals: I,
-    ) -> Result<CharwiseDoubleArrayAhoCorasick<V>>
+        to_symbols: F,
+    ) -> Result<CharwiseDoubleArrayAhoCorasick<V, M>>
     where
         I: IntoIterator<Item = (P, V)>,
-        P: AsRef<str>,
+        L: EdgeLabel + Into<u32>,
+        F: FnMut(P, &mut Vec<L>),
         V: Copy,
     {
-        let nfa = self.build_original_nfa_and_mapper(patvals)?;
+        let nfa = self.build_original_nfa_and_mapper(patvals, to_symbols)?;
 
         self.build_double_array(&nfa)?;
 
@@ -208,47 +233,50 @@ impl CharwiseDoubleArrayAhoCorasickBuilder {
         })
     }
 
-    fn build_original_nfa_and_mapper<I, P, V>(
+    fn build_original_nfa_and_mapper<I, P, V, L, F>(
         &mut self,
         patvals: I,
-    ) -> Result<CharwiseNfaBuilder<V>>
+        mut to_symbols: F,
+    ) -> Result<NfaBuilder<L, V>>
     where
         I: IntoIterator<Item = (P, V)>,
-        P: AsRef<str>,
+        L: EdgeLabel + Into<u32>,
+        F: FnMut(P, &mut Vec<L>),
         V: Copy,
     {
-        let mut nfa = CharwiseNfaBuilder::new(self.match_kind);
-        let mut freqs = vec![];
+        let mut nfa = NfaBuilder::new(self.match_kind);
+        let mut freqs = M::Freqs::default();
         {
-            let mut chars = vec![];
+            let mut symbols = vec![];
             for (pattern, value) in patvals {
-                chars.clear();
-                pattern.as_ref().chars().for_each(|c| chars.push(c));
-                nfa.add(&chars, value)?;
-
-                for &c in &chars {
-                    let c = usize::from_u32(u32::from(c));
-                    if freqs.len() <= c {
-                        freqs.resize(c + 1, 0);
-                    }
-                    freqs[c] += 1;
+                symbols.clear();
+                to_symbols(pattern, &mut symbols);
+                nfa.add(&symbols, value)?;
+
+                for &c in &symbols {
+                    M::count(&mut freqs, c.into());
                 }
             }
         }
-        self.mapper = CodeMapper::new(&freqs);
+        self.mapper = M::from_freqs(&freqs);
 
         if nfa.len == 0 {
             return Err(DaachorseError::invalid_argument("patvals.len()", ">=", 1));
         }
         let q = match self.match_kind {
-            MatchKind::Standard => nfa.build_fails(),
-            MatchKind::LeftmostLongest | MatchKind::LeftmostFirst => nfa.build_fails_leftmost(),
+            MatchKind::Standard => nfa.build_fails(|_| ()),
+            MatchKind::LeftmostLongest | MatchKind::LeftmostFirst => {
+                nfa.build_fails_leftmost(|_| ())
+            }
         };
-        nfa.build_outputs(&q);
+        nfa.build_outputs(&q, |_| ());
         Ok(nfa)
     }
 
-    fn build_double_array<V>(&mut self, nfa: &CharwiseNfaBuilder<V>) -> Result<()> {
+    fn build_double_array<L, V>(&mut self, nfa: &NfaBuilder<L, V>) -> Result<()>
+    where
+        L: EdgeLabel + Into<u32>,
+    {
         let mut helper = self.init_array()?;
 
         let mut state_id_map = vec![DEAD_STATE_IDX; nfa.states.len()];
@@ -272,7 +300,7 @@ impl CharwiseDoubleArrayAhoCorasickBuilder {
 
             mapped.clear();
             for (&label, &child_id) in &s.edges {
-                mapped.push((self.mapper.get(label).unwrap(), child_id));
+                mapped.push((self.mapper.get(label.into()).unwrap(), child_id));
             }
             mapped.sort_by(|(c1, _), (c2, _)| c1.cmp(c2));
 
@@ -318,7 +346,14 @@ impl CharwiseDoubleArrayAhoCorasickBuilder {
     }
 
     fn init_array(&mut self) -> Result<BuildHelper> {
-        self.block_len = self.mapper.alphabet_size().next_power_of_two().max(2);
+        // The fan-out of each state is bounded by the number of distinct symbols in the patterns,
+        // not by the range of symbol values, because symbols are mapped to dense codes.
+        self.block_len = self
+            .mapper
+            .alphabet_size()
+            .checked_next_power_of_two()
+            .ok_or_else(|| DaachorseError::automaton_scale("alphabet_size", 1 << 31))?
+            .max(2);
         self.states
             .resize(usize::from_u32(self.block_len), State::default());
         let mut helper = BuildHelper::new(self.block_len, self.num_free_blocks)?;
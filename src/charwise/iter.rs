@@ -1,16 +1,21 @@
 //! Iterators for [`CharwiseDoubleArrayAhoCorasick`].
 
-use core::iter::Enumerate;
+use core::iter::{Enumerate, FusedIterator};
 use core::num::NonZeroU32;
 
-use crate::charwise::CharwiseDoubleArrayAhoCorasick;
+use crate::charwise::{CharwiseDoubleArrayAhoCorasick, CodeMapper, Haystack, Mapper};
 
 use crate::charwise::ROOT_STATE_IDX;
 use crate::utils::FromU32;
 use crate::Match;
 
+impl<P> Haystack<P> for CodeMapper {
+    type Iter = CharWithEndOffsetIterator<P>;
+}
+
 /// Iterator for some struct that implements [`AsRef<str>`].
 #[doc(hidden)]
+#[derive(Clone)]
 pub struct StrIterator<P> {
     inner: P,
     pos: usize,
@@ -38,10 +43,19 @@ where
         self.pos += 1;
         Some(ret)
     }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let rest = self.inner.as_ref().len() - self.pos;
+        (rest, Some(rest))
+    }
 }
 
+impl<P> FusedIterator for StrIterator<P> where P: AsRef<str> {}
+
 /// Iterator for UTF-8 strings with end positions.
 #[doc(hidden)]
+#[derive(Clone)]
 pub struct CharWithEndOffsetIterator<I> {
     inner: Enumerate<I>,
 }
@@ -96,40 +110,123 @@ where
         };
         Some((end_offset, unsafe { char::from_u32_unchecked(c) }))
     }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Each character consists of one to four bytes.
+        let (lower, upper) = self.inner.size_hint();
+        ((lower + 3) / 4, upper)
+    }
 }
 
+impl<I> FusedIterator for CharWithEndOffsetIterator<I> where I: FusedIterator<Item = u8> {}
+
 /// Iterator created by [`CharwiseDoubleArrayAhoCorasick::find_iter()`].
-pub struct FindOverlappingIterator<'a, P, V> {
-    pub(crate) pma: &'a CharwiseDoubleArrayAhoCorasick<V>,
-    pub(crate) haystack: CharWithEndOffsetIterator<P>,
+pub struct FindOverlappingIterator<'a, P, V, M = CodeMapper>
+where
+    M: Haystack<P>,
+{
+    pub(crate) pma: &'a CharwiseDoubleArrayAhoCorasick<V, M>,
+    pub(crate) haystack: M::Iter,
     pub(crate) state_id: u32,
     pub(crate) pos: usize,
     pub(crate) output_pos: Option<NonZeroU32>,
 }
 
+impl<P, V, M> Clone for FindOverlappingIterator<'_, P, V, M>
+where
+    M: Haystack<P>,
+    M::Iter: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            state_id: self.state_id,
+            pos: self.pos,
+            output_pos: self.output_pos,
+        }
+    }
+}
+
 /// Iterator created by [`CharwiseDoubleArrayAhoCorasick::find_overlapping_iter()`].
-pub struct FindIterator<'a, P, V> {
-    pub(crate) pma: &'a CharwiseDoubleArrayAhoCorasick<V>,
-    pub(crate) haystack: CharWithEndOffsetIterator<P>,
+pub struct FindIterator<'a, P, V, M = CodeMapper>
+where
+    M: Haystack<P>,
+{
+    pub(crate) pma: &'a CharwiseDoubleArrayAhoCorasick<V, M>,
+    pub(crate) haystack: M::Iter,
+    pub(crate) pos: usize,
+}
+
+impl<P, V, M> Clone for FindIterator<'_, P, V, M>
+where
+    M: Haystack<P>,
+    M::Iter: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            pos: self.pos,
+        }
+    }
 }
 
 /// Iterator created by [`CharwiseDoubleArrayAhoCorasick::find_overlapping_no_suffix_iter()`].
-pub struct FindOverlappingNoSuffixIterator<'a, P, V> {
-    pub(crate) pma: &'a CharwiseDoubleArrayAhoCorasick<V>,
-    pub(crate) haystack: CharWithEndOffsetIterator<P>,
+pub struct FindOverlappingNoSuffixIterator<'a, P, V, M = CodeMapper>
+where
+    M: Haystack<P>,
+{
+    pub(crate) pma: &'a CharwiseDoubleArrayAhoCorasick<V, M>,
+    pub(crate) haystack: M::Iter,
     pub(crate) state_id: u32,
+    pub(crate) pos: usize,
+}
+
+impl<P, V, M> Clone for FindOverlappingNoSuffixIterator<'_, P, V, M>
+where
+    M: Haystack<P>,
+    M::Iter: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            state_id: self.state_id,
+            pos: self.pos,
+        }
+    }
 }
 
 /// Iterator created by [`CharwiseDoubleArrayAhoCorasick::leftmost_find_iter()`].
-pub struct LestmostFindIterator<'a, P, V> {
-    pub(crate) pma: &'a CharwiseDoubleArrayAhoCorasick<V>,
+pub struct LeftmostFindIterator<'a, P, V, M = CodeMapper> {
+    pub(crate) pma: &'a CharwiseDoubleArrayAhoCorasick<V, M>,
     pub(crate) haystack: P,
     pub(crate) pos: usize,
 }
 
-impl<P, V> Iterator for FindOverlappingIterator<'_, P, V>
+impl<P, V, M> Clone for LeftmostFindIterator<'_, P, V, M>
 where
-    P: Iterator<Item = u8>,
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pma: self.pma,
+            haystack: self.haystack.clone(),
+            pos: self.pos,
+        }
+    }
+}
+
+/// Deprecated alias of [`LeftmostFindIterator`], kept for backward compatibility.
+#[deprecated(note = "Use `LeftmostFindIterator` instead")]
+pub type LestmostFindIterator<'a, P, V> = LeftmostFindIterator<'a, P, V>;
+
+impl<P, V, M> Iterator for FindOverlappingIterator<'_, P, V, M>
+where
+    M: Haystack<P>,
+    M::Iter: Iterator<Item = (usize, M::Symbol)>,
     V: Copy,
 {
     type Item = Match<V>;
@@ -152,8 +249,9 @@ where
             });
         }
 
+        let mut scanned = self.pos;
         for (pos, c) in self.haystack.by_ref() {
-            self.pos = pos;
+            scanned = pos;
 
             // self.state_id is always smaller than self.pma.states.len() because
             // self.pma.next_state_id_unchecked() ensures to return such a value.
@@ -172,6 +270,7 @@ where
                         .get_unchecked(usize::from_u32(output_pos.get() - 1))
                 };
                 self.output_pos = out.parent();
+                self.pos = scanned;
                 return Some(Match {
                     length: usize::from_u32(out.length()),
                     end: pos,
@@ -179,13 +278,132 @@ where
                 });
             }
         }
+        self.pos = scanned;
         None
     }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // More than one match can be reported at each position, so there is no upper bound.
+        (usize::from(self.output_pos.is_some()), None)
+    }
 }
 
-impl<P, V> Iterator for FindIterator<'_, P, V>
+impl<P, V> FindOverlappingIterator<'_, P, V>
 where
     P: Iterator<Item = u8>,
+{
+    /// Returns the number of bytes of the haystack consumed so far.
+    #[inline(always)]
+    #[must_use]
+    pub const fn bytes_scanned(&self) -> usize {
+        self.pos
+    }
+}
+
+impl<P, V, M> FindOverlappingIterator<'_, P, V, M>
+where
+    M: Haystack<P>,
+{
+    /// Returns the number of patterns ending at the end position of the last reported match,
+    /// including ones already reported.
+    ///
+    /// The count is the length of the output chain of the current state, which can be used as a
+    /// cheap signal of the specificity of the position. Zero is returned before the first match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::CharwiseDoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["全世界", "世界", "界", "に"];
+    /// let pma = CharwiseDoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_overlapping_iter("全世界に");
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 9, 0), (m.start(), m.end(), m.value()));
+    /// assert_eq!(3, it.num_patterns_at_end());
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((3, 9, 1), (m.start(), m.end(), m.value()));
+    /// assert_eq!(3, it.num_patterns_at_end());
+    /// ```
+    #[must_use]
+    pub fn num_patterns_at_end(&self) -> usize
+    where
+        V: Copy,
+    {
+        // self.state_id is always smaller than self.pma.states.len() because it is ROOT_STATE_IDX
+        // or a value returned by self.pma.next_state_id_unchecked().
+        let mut output_pos = unsafe {
+            self.pma
+                .states
+                .get_unchecked(usize::from_u32(self.state_id))
+                .output_pos()
+        };
+        let mut count = 0;
+        while let Some(pos) = output_pos {
+            count += 1;
+            output_pos = self.pma.outputs[usize::from_u32(pos.get() - 1)].parent();
+        }
+        count
+    }
+
+    /// Returns whether the match last returned by [`Iterator::next()`] is the first one reported
+    /// at its ending position.
+    ///
+    /// The search reports all patterns ending at a position in descending order of length. The
+    /// longest one is the primary match, and the others, which are its suffixes, are
+    /// continuations. `false` is returned before the first match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::CharwiseDoubleArrayAhoCorasick;
+    ///
+    /// let patterns = vec!["全世界", "世界", "界"];
+    /// let pma = CharwiseDoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_overlapping_iter("全世界");
+    /// assert!(!it.is_primary());
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 9, true), (m.start(), m.end(), it.is_primary()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((3, 9, false), (m.start(), m.end(), it.is_primary()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((6, 9, false), (m.start(), m.end(), it.is_primary()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    #[must_use]
+    pub fn is_primary(&self) -> bool
+    where
+        V: Copy,
+    {
+        self.pma.states[usize::from_u32(self.state_id)]
+            .output_pos()
+            .map_or(false, |head| {
+                self.pma.outputs[usize::from_u32(head.get() - 1)].parent() == self.output_pos
+            })
+    }
+}
+
+impl<P, V, M> FusedIterator for FindOverlappingIterator<'_, P, V, M>
+where
+    M: Haystack<P>,
+    M::Iter: FusedIterator<Item = (usize, M::Symbol)>,
+    V: Copy,
+{
+}
+
+impl<P, V, M> Iterator for FindIterator<'_, P, V, M>
+where
+    M: Haystack<P>,
+    M::Iter: Iterator<Item = (usize, M::Symbol)>,
     V: Copy,
 {
     type Item = Match<V>;
@@ -193,7 +411,9 @@ where
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
         let mut state_id = ROOT_STATE_IDX;
+        let mut scanned = self.pos;
         for (pos, c) in self.haystack.by_ref() {
+            scanned = pos;
             // self.state_id is always smaller than self.pma.states.len() because
             // self.pma.next_state_id_unchecked() ensures to return such a value.
             state_id = unsafe { self.pma.next_state_id_unchecked(state_id, c) };
@@ -210,6 +430,7 @@ where
                         .outputs
                         .get_unchecked(usize::from_u32(output_pos.get() - 1))
                 };
+                self.pos = scanned;
                 return Some(Match {
                     length: usize::from_u32(out.length()),
                     end: pos,
@@ -217,20 +438,50 @@ where
                 });
             }
         }
+        self.pos = scanned;
         None
     }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Each match consumes at least one character.
+        (0, self.haystack.size_hint().1)
+    }
 }
 
-impl<P, V> Iterator for FindOverlappingNoSuffixIterator<'_, P, V>
+impl<P, V> FindIterator<'_, P, V>
 where
     P: Iterator<Item = u8>,
+{
+    /// Returns the number of bytes of the haystack consumed so far.
+    #[inline(always)]
+    #[must_use]
+    pub const fn bytes_scanned(&self) -> usize {
+        self.pos
+    }
+}
+
+impl<P, V, M> FusedIterator for FindIterator<'_, P, V, M>
+where
+    M: Haystack<P>,
+    M::Iter: FusedIterator<Item = (usize, M::Symbol)>,
+    V: Copy,
+{
+}
+
+impl<P, V, M> Iterator for FindOverlappingNoSuffixIterator<'_, P, V, M>
+where
+    M: Haystack<P>,
+    M::Iter: Iterator<Item = (usize, M::Symbol)>,
     V: Copy,
 {
     type Item = Match<V>;
 
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
+        let mut scanned = self.pos;
         for (pos, c) in self.haystack.by_ref() {
+            scanned = pos;
             // self.state_id is always smaller than self.pma.states.len() because
             // self.pma.next_state_id_unchecked() ensures to return such a value.
             self.state_id = unsafe { self.pma.next_state_id_unchecked(self.state_id, c) };
@@ -247,6 +498,7 @@ where
                         .outputs
                         .get_unchecked(usize::from_u32(output_pos.get() - 1))
                 };
+                self.pos = scanned;
                 return Some(Match {
                     length: usize::from_u32(out.length()),
                     end: pos,
@@ -254,11 +506,105 @@ where
                 });
             }
         }
+        self.pos = scanned;
         None
     }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Each match consumes at least one character.
+        (0, self.haystack.size_hint().1)
+    }
 }
 
-impl<P, V> Iterator for LestmostFindIterator<'_, P, V>
+impl<P, V> FindOverlappingNoSuffixIterator<'_, P, V>
+where
+    P: Iterator<Item = u8>,
+{
+    /// Returns the number of bytes of the haystack consumed so far.
+    #[inline(always)]
+    #[must_use]
+    pub const fn bytes_scanned(&self) -> usize {
+        self.pos
+    }
+}
+
+impl<P, V, M> FusedIterator for FindOverlappingNoSuffixIterator<'_, P, V, M>
+where
+    M: Haystack<P>,
+    M::Iter: FusedIterator<Item = (usize, M::Symbol)>,
+    V: Copy,
+{
+}
+
+/// Returns the next leftmost match in `haystack`, the symbols following `pos` with their end
+/// positions, and moves `pos` to the position from which the next search resumes.
+#[inline(always)]
+pub(crate) fn leftmost_find_next<I, V, M>(
+    pma: &CharwiseDoubleArrayAhoCorasick<V, M>,
+    haystack: I,
+    pos: &mut usize,
+) -> Option<Match<V>>
+where
+    I: Iterator<Item = (usize, M::Symbol)>,
+    M: Mapper,
+    V: Copy,
+{
+    let mut state_id = ROOT_STATE_IDX;
+    let mut last_output_pos: Option<NonZeroU32> = None;
+
+    let mut scanned = *pos;
+    for (end, c) in haystack {
+        scanned = end;
+
+        // state_id is always smaller than pma.states.len() because
+        // pma.next_state_id_leftmost_unchecked() ensures to return such a value.
+        state_id = unsafe { pma.next_state_id_leftmost_unchecked(state_id, c) };
+        if state_id == ROOT_STATE_IDX {
+            if let Some(output_pos) = last_output_pos {
+                // last_output_pos is always smaller than pma.outputs.len() because
+                // State::output_pos() ensures to return such a value when it is Some.
+                let out = unsafe {
+                    pma.outputs
+                        .get_unchecked(usize::from_u32(output_pos.get() - 1))
+                };
+                return Some(Match {
+                    length: usize::from_u32(out.length()),
+                    end: *pos,
+                    value: out.value(),
+                });
+            }
+        // state_id is always smaller than pma.states.len() because
+        // pma.next_state_id_leftmost_unchecked() ensures to return such a value.
+        } else if let Some(output_pos) = unsafe {
+            pma.states
+                .get_unchecked(usize::from_u32(state_id))
+                .output_pos()
+        } {
+            last_output_pos.replace(output_pos);
+            *pos = end;
+        }
+    }
+
+    if last_output_pos.is_none() {
+        *pos = scanned;
+    }
+    last_output_pos.map(|output_pos| {
+        // last_output_pos is always smaller than pma.outputs.len() because
+        // State::output_pos() ensures to return such a value when it is Some.
+        let out = unsafe {
+            pma.outputs
+                .get_unchecked(usize::from_u32(output_pos.get() - 1))
+        };
+        Match {
+            length: usize::from_u32(out.length()),
+            end: *pos,
+            value: out.value(),
+        }
+    })
+}
+
+impl<P, V> Iterator for LeftmostFindIterator<'_, P, V>
 where
     P: AsRef<str>,
     V: Copy,
@@ -267,62 +613,43 @@ where
 
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
-        let mut state_id = ROOT_STATE_IDX;
-        let mut last_output_pos: Option<NonZeroU32> = None;
-
-        let mut skips = 0;
-        for c in unsafe { self.haystack.as_ref().get_unchecked(self.pos..) }.chars() {
-            skips += c.len_utf8();
-
-            // state_id is always smaller than self.pma.states.len() because
-            // self.pma.next_state_id_leftmost_unchecked() ensures to return such a value.
-            state_id = unsafe { self.pma.next_state_id_leftmost_unchecked(state_id, c) };
-            if state_id == ROOT_STATE_IDX {
-                if let Some(output_pos) = last_output_pos {
-                    // last_output_pos is always smaller than self.pma.outputs.len() because
-                    // State::output_pos() ensures to return such a value when it is Some.
-                    let out = unsafe {
-                        self.pma
-                            .outputs
-                            .get_unchecked(usize::from_u32(output_pos.get() - 1))
-                    };
-                    return Some(Match {
-                        length: usize::from_u32(out.length()),
-                        end: self.pos,
-                        value: out.value(),
-                    });
-                }
-            // state_id is always smaller than self.pma.states.len() because
-            // self.pma.next_state_id_leftmost_unchecked() ensures to return such a value.
-            } else if let Some(output_pos) = unsafe {
-                self.pma
-                    .states
-                    .get_unchecked(usize::from_u32(state_id))
-                    .output_pos()
-            } {
-                last_output_pos.replace(output_pos);
-                self.pos += skips;
-                skips = 0;
-            }
-        }
+        let pos = self.pos;
+        let haystack = unsafe { self.haystack.as_ref().get_unchecked(pos..) }
+            .char_indices()
+            .map(|(i, c)| (pos + i + c.len_utf8(), c));
+        leftmost_find_next(self.pma, haystack, &mut self.pos)
+    }
 
-        last_output_pos.map(|output_pos| {
-            // last_output_pos is always smaller than self.pma.outputs.len() because
-            // State::output_pos() ensures to return such a value when it is Some.
-            let out = unsafe {
-                self.pma
-                    .outputs
-                    .get_unchecked(usize::from_u32(output_pos.get() - 1))
-            };
-            Match {
-                length: usize::from_u32(out.length()),
-                end: self.pos,
-                value: out.value(),
-            }
-        })
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Each match consumes at least one byte.
+        (0, Some(self.haystack.as_ref().len() - self.pos))
     }
 }
 
+impl<P, V> LeftmostFindIterator<'_, P, V>
+where
+    P: AsRef<str>,
+{
+    /// Returns the number of bytes of the haystack consumed so far.
+    ///
+    /// This is the position from which the next search resumes, i.e., the end of the last
+    /// reported match, or the length of the haystack after the iterator is exhausted. Bytes
+    /// read ahead to find a longer match are not counted.
+    #[inline(always)]
+    #[must_use]
+    pub const fn bytes_scanned(&self) -> usize {
+        self.pos
+    }
+}
+
+impl<P, V> FusedIterator for LeftmostFindIterator<'_, P, V>
+where
+    P: AsRef<str>,
+    V: Copy,
+{
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
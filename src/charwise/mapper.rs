@@ -1,3 +1,10 @@
+//! Mappings from symbols to dense codes.
+//!
+//! The items are public only to be used as the default type parameter of
+//! [`CharwiseDoubleArrayAhoCorasick`](super::CharwiseDoubleArrayAhoCorasick) and its iterators.
+//! This module is private, so they cannot be named outside the crate, and [`Mapper`] cannot be
+//! implemented for other types.
+
 use alloc::vec::Vec;
 
 use crate::serializer::{Serializable, SerializableVec};
@@ -6,6 +13,39 @@ use crate::utils::FromU32;
 
 pub const INVALID_CODE: u32 = u32::MAX;
 
+/// Supertrait preventing [`Mapper`] from being implemented outside the crate.
+pub trait Sealed {}
+
+/// Mapping from symbols to dense codes, over which the character-wise automaton is generic.
+pub trait Mapper: Sealed + Default {
+    /// Type of symbols of patterns and haystacks.
+    type Symbol: Copy + Into<u32>;
+
+    /// Frequencies of symbols in patterns.
+    type Freqs: Default;
+
+    /// Counts an occurrence of the symbol value `c`.
+    fn count(freqs: &mut Self::Freqs, c: u32);
+
+    /// Creates a mapping assigning codes in descending order of frequency.
+    fn from_freqs(freqs: &Self::Freqs) -> Self;
+
+    /// Returns the code of the symbol value `c`, or `None` if it does not appear in patterns.
+    fn get(&self, c: u32) -> Option<u32>;
+
+    /// Returns the number of distinct symbols in patterns.
+    fn alphabet_size(&self) -> u32;
+
+    /// Returns the total amount of heap used by the mapping in bytes.
+    fn heap_bytes(&self) -> usize;
+}
+
+/// Decoding of haystacks of type `P` into symbols, which the iterators are generic over.
+pub trait Haystack<P>: Mapper {
+    /// Iterator of symbols with their end positions.
+    type Iter;
+}
+
 #[derive(Default, Clone, Debug, Eq, Hash, PartialEq)]
 pub struct CodeMapper {
     table: Vec<u32>,
@@ -13,7 +53,7 @@ pub struct CodeMapper {
 }
 
 impl CodeMapper {
-    pub fn new(freqs: &[u32]) -> Self {
+    pub(crate) fn new(freqs: &[u32]) -> Self {
         let sorted = {
             let mut sorted = vec![];
             for (c, &f) in freqs.iter().enumerate().filter(|(_, &f)| f != 0) {
@@ -32,23 +72,42 @@ impl CodeMapper {
             alphabet_size: u32::try_from(sorted.len()).unwrap(),
         }
     }
+}
+
+impl Sealed for CodeMapper {}
+
+impl Mapper for CodeMapper {
+    type Symbol = char;
+    type Freqs = Vec<u32>;
+
+    #[inline(always)]
+    fn count(freqs: &mut Vec<u32>, c: u32) {
+        let c = usize::from_u32(c);
+        if freqs.len() <= c {
+            freqs.resize(c + 1, 0);
+        }
+        freqs[c] += 1;
+    }
+
+    fn from_freqs(freqs: &Vec<u32>) -> Self {
+        Self::new(freqs)
+    }
 
     #[inline(always)]
-    pub fn get(&self, c: char) -> Option<u32> {
+    fn get(&self, c: u32) -> Option<u32> {
         self.table
-            .get(usize::from_u32(u32::from(c)))
+            .get(usize::from_u32(c))
             .copied()
             .filter(|&code| code != INVALID_CODE)
     }
 
     #[inline(always)]
-    pub const fn alphabet_size(&self) -> u32 {
+    fn alphabet_size(&self) -> u32 {
         self.alphabet_size
     }
 
     #[inline]
-    #[allow(dead_code)]
-    pub fn heap_bytes(&self) -> usize {
+    fn heap_bytes(&self) -> usize {
         self.table.len() * core::mem::size_of::<u32>()
     }
 }
@@ -88,14 +147,14 @@ mod tests {
         let freqs = vec![3, 6, 0, 2, 3, 0, 3];
         let mapper = CodeMapper::new(&freqs);
 
-        assert_eq!(mapper.get(0 as char), Some(1));
-        assert_eq!(mapper.get(1 as char), Some(0));
-        assert_eq!(mapper.get(2 as char), None);
-        assert_eq!(mapper.get(3 as char), Some(4));
-        assert_eq!(mapper.get(4 as char), Some(2));
-        assert_eq!(mapper.get(5 as char), None);
-        assert_eq!(mapper.get(6 as char), Some(3));
-        assert_eq!(mapper.get(7 as char), None); // out-of-range
+        assert_eq!(mapper.get(0), Some(1));
+        assert_eq!(mapper.get(1), Some(0));
+        assert_eq!(mapper.get(2), None);
+        assert_eq!(mapper.get(3), Some(4));
+        assert_eq!(mapper.get(4), Some(2));
+        assert_eq!(mapper.get(5), None);
+        assert_eq!(mapper.get(6), Some(3));
+        assert_eq!(mapper.get(7), None); // out-of-range
     }
 
     #[test]
@@ -6,7 +6,10 @@ use alloc::fmt;
 use alloc::string::String;
 
 /// Errors in daachorse.
+///
+/// This enum is non-exhaustive, so new kinds of errors can be added without breaking changes.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum DaachorseError {
     /// Contains [`InvalidArgumentError`].
     InvalidArgument(InvalidArgumentError),
@@ -19,6 +22,15 @@ pub enum DaachorseError {
 
     /// Contains [`InvalidConversionError`].
     InvalidConversion(InvalidConversionError),
+
+    /// Contains [`InvalidAutomatonError`].
+    InvalidAutomaton(InvalidAutomatonError),
+
+    /// Contains [`InvalidWildcardError`].
+    InvalidWildcard(InvalidWildcardError),
+
+    /// Contains [`IoError`].
+    Io(IoError),
 }
 
 impl fmt::Display for DaachorseError {
@@ -28,13 +40,30 @@ impl fmt::Display for DaachorseError {
             Self::DuplicatePattern(e) => e.fmt(f),
             Self::AutomatonScale(e) => e.fmt(f),
             Self::InvalidConversion(e) => e.fmt(f),
+            Self::InvalidAutomaton(e) => e.fmt(f),
+            Self::InvalidWildcard(e) => e.fmt(f),
+            Self::Io(e) => e.fmt(f),
         }
     }
 }
 
 impl DaachorseError {
     pub(crate) const fn invalid_argument(arg: &'static str, op: &'static str, value: u32) -> Self {
-        Self::InvalidArgument(InvalidArgumentError { arg, op, value })
+        Self::InvalidArgument(InvalidArgumentError {
+            arg,
+            op,
+            value,
+            note: None,
+        })
+    }
+
+    pub(crate) const fn unsupported_argument(arg: &'static str, note: &'static str) -> Self {
+        Self::InvalidArgument(InvalidArgumentError {
+            arg,
+            op: "",
+            value: 0,
+            note: Some(note),
+        })
     }
 
     pub(crate) const fn duplicate_pattern(pattern: String) -> Self {
@@ -48,6 +77,19 @@ impl DaachorseError {
     pub(crate) const fn invalid_conversion(arg: &'static str, target: &'static str) -> Self {
         Self::InvalidConversion(InvalidConversionError { arg, target })
     }
+
+    pub(crate) const fn invalid_automaton(reason: &'static str) -> Self {
+        Self::InvalidAutomaton(InvalidAutomatonError { reason })
+    }
+
+    pub(crate) const fn invalid_wildcard(index: usize, reason: &'static str) -> Self {
+        Self::InvalidWildcard(InvalidWildcardError { index, reason })
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) const fn io(inner: std::io::Error) -> Self {
+        Self::Io(IoError { inner })
+    }
 }
 
 /// Error used when the argument is invalid.
@@ -61,10 +103,16 @@ pub struct InvalidArgumentError {
 
     /// Condition value.
     value: u32,
+
+    /// Description used instead of the condition if the argument is not supported.
+    note: Option<&'static str>,
 }
 
 impl fmt::Display for InvalidArgumentError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(note) = self.note {
+            return write!(f, "InvalidArgumentError: {} {}", self.arg, note);
+        }
         write!(
             f,
             "InvalidArgumentError: {} must be {} {}",
@@ -126,5 +174,67 @@ impl fmt::Display for InvalidConversionError {
     }
 }
 
+/// Error used when the automaton violates its internal invariants.
+#[derive(Debug)]
+pub struct InvalidAutomatonError {
+    /// Description of the violated invariant.
+    reason: &'static str,
+}
+
+impl fmt::Display for InvalidAutomatonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "InvalidAutomatonError: {}", self.reason)
+    }
+}
+
+/// Error used when a pattern with wildcards cannot be expanded.
+#[derive(Debug)]
+pub struct InvalidWildcardError {
+    /// Index of the pattern in the input.
+    index: usize,
+
+    /// Description of the problem.
+    reason: &'static str,
+}
+
+impl fmt::Display for InvalidWildcardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "InvalidWildcardError: patvals[{}] cannot be expanded: {}",
+            self.index, self.reason
+        )
+    }
+}
+
+/// Error used when reading patterns fails.
+///
+/// This error is raised only if the `std` feature is enabled.
+#[derive(Debug)]
+pub struct IoError {
+    /// The underlying error.
+    #[cfg(feature = "std")]
+    inner: std::io::Error,
+}
+
+impl IoError {
+    /// Returns the underlying error.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    #[must_use]
+    pub const fn get_ref(&self) -> &std::io::Error {
+        &self.inner
+    }
+}
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IoError")?;
+        #[cfg(feature = "std")]
+        write!(f, ": {}", self.inner)?;
+        Ok(())
+    }
+}
+
 /// A specialized Result type for Daachorse.
 pub type Result<T, E = DaachorseError> = result::Result<T, E>;
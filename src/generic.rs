@@ -0,0 +1,422 @@
+//! A symbol-wise version for matching on sequences of integer symbols such as token IDs.
+
+mod builder;
+pub mod iter;
+mod mapper;
+
+use crate::charwise::{CharwiseDoubleArrayAhoCorasick, ROOT_STATE_IDX};
+use crate::errors::Result;
+use crate::MatchKind;
+pub use builder::DoubleArrayAhoCorasickGenericBuilder;
+use iter::{
+    FindIterator, FindOverlappingIterator, LeftmostFindIterator, SymbolWithEndOffsetIterator,
+};
+pub(crate) use mapper::SymbolMapper;
+
+/// A fast multiple pattern match automaton implemented with the Aho-Corasick algorithm and
+/// symbol-wise double-array data structure.
+///
+/// [`DoubleArrayAhoCorasick`](super::DoubleArrayAhoCorasick) defines transition labels using
+/// byte integers. On the other hand, this version uses values of symbols of type `S`, such as
+/// [`u16`] or [`u32`], which is useful for matching on sequences of token IDs without encoding
+/// them into bytes. Positions of matches are also counted in symbols.
+///
+/// # Alphabet
+///
+/// A transition is computed on the symbol's integer value obtained by [`Into<u32>`]. Like
+/// [`CharwiseDoubleArrayAhoCorasick`], symbols are
+/// mapped to dense codes in descending order of frequency in the patterns, and the double array
+/// is arranged in blocks of the smallest power of two covering the codes. Thus, the fan-out of
+/// each state is bounded by the number of distinct symbols in the patterns rather than by the
+/// range of symbol values, and each state stores the ID of its parent in place of a byte label
+/// to verify a transition. Symbols not appearing in the patterns never match.
+///
+/// The double array and the search are shared with
+/// [`CharwiseDoubleArrayAhoCorasick`], which is generic over the mapping of symbols.
+///
+/// # Thread safety
+///
+/// The automaton is immutable after the construction, and all search methods take `&self`.
+/// Thus, [`DoubleArrayAhoCorasickGeneric`] is [`Send`] and [`Sync`] if `V` is, and an automaton
+/// can be shared among threads, e.g., with [`Arc`](alloc::sync::Arc), without any locking.
+#[derive(Clone, Eq, Hash, PartialEq)]
+pub struct DoubleArrayAhoCorasickGeneric<S, V> {
+    pma: CharwiseDoubleArrayAhoCorasick<V, SymbolMapper<S>>,
+}
+
+impl<S, V> DoubleArrayAhoCorasickGeneric<S, V>
+where
+    S: Into<u32> + Copy,
+{
+    /// Creates a new [`DoubleArrayAhoCorasickGeneric`] from input patterns. The value `i` is
+    /// automatically associated with `patterns[i]`.
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - List of patterns.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`](super::errors::DaachorseError) is returned when
+    ///   - `patterns` is empty,
+    ///   - `patterns` contains entries of length zero,
+    ///   - `patterns` contains duplicate entries,
+    ///   - the conversion from the index `i` to the specified type `V` fails,
+    ///   - the scale of `patterns` exceeds the expected one, or
+    ///   - the scale of the resulting automaton exceeds the expected one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::generic::DoubleArrayAhoCorasickGeneric;
+    ///
+    /// let patterns: Vec<&[u32]> = vec![&[7, 100_000], &[100_000], &[42]];
+    /// let pma = DoubleArrayAhoCorasickGeneric::<u32, usize>::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_iter([7, 100_000, 3, 42]);
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 2, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((3, 4, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn new<I, P>(patterns: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[S]>,
+        V: Copy + TryFrom<usize>,
+    {
+        DoubleArrayAhoCorasickGenericBuilder::new().build(patterns)
+    }
+
+    /// Creates a new [`DoubleArrayAhoCorasickGeneric`] from input pattern-value pairs.
+    ///
+    /// # Arguments
+    ///
+    /// * `patvals` - List of pattern-value pairs.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`](super::errors::DaachorseError) is returned when
+    ///   - `patvals` is empty,
+    ///   - `patvals` contains patterns of length zero,
+    ///   - `patvals` contains duplicate patterns,
+    ///   - the scale of `patvals` exceeds the expected one, or
+    ///   - the scale of the resulting automaton exceeds the expected one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::generic::DoubleArrayAhoCorasickGeneric;
+    ///
+    /// let patvals = vec![(vec![1u16, 2, 3], 10), (vec![2, 3], 20), (vec![5], 30)];
+    /// let pma = DoubleArrayAhoCorasickGeneric::with_values(patvals).unwrap();
+    ///
+    /// let mut it = pma.find_iter([1, 2, 3, 4, 5]);
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 3, 10), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((4, 5, 30), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn with_values<I, P>(patvals: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (P, V)>,
+        P: AsRef<[S]>,
+        V: Copy,
+    {
+        DoubleArrayAhoCorasickGenericBuilder::new().build_with_values(patvals)
+    }
+
+    /// Returns an iterator of non-overlapping matches in the given haystack.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - Symbol sequence to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::generic::DoubleArrayAhoCorasickGeneric;
+    ///
+    /// let patterns: Vec<&[u16]> = vec![&[1, 2, 3], &[2, 3], &[5]];
+    /// let pma = DoubleArrayAhoCorasickGeneric::<u16, usize>::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_iter([1, 2, 3, 4, 5]);
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 3, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((4, 5, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_iter<P>(&self, haystack: P) -> FindIterator<'_, P, S, V>
+    where
+        P: AsRef<[S]>,
+    {
+        assert!(
+            self.pma.match_kind().is_standard(),
+            "Error: match_kind must be standard."
+        );
+        FindIterator {
+            pma: &self.pma,
+            haystack: SymbolWithEndOffsetIterator::new(haystack),
+            pos: 0,
+        }
+    }
+
+    /// Returns an iterator of overlapping matches in the given haystack.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - Symbol sequence to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::Standard`] in the construction, the iterator is not
+    /// supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::generic::DoubleArrayAhoCorasickGeneric;
+    ///
+    /// let patterns: Vec<&[u16]> = vec![&[1, 2, 3], &[2, 3], &[5]];
+    /// let pma = DoubleArrayAhoCorasickGeneric::<u16, usize>::new(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_overlapping_iter([1, 2, 3, 4, 5]);
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 3, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((1, 3, 1), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((4, 5, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn find_overlapping_iter<P>(&self, haystack: P) -> FindOverlappingIterator<'_, P, S, V>
+    where
+        P: AsRef<[S]>,
+    {
+        assert!(
+            self.pma.match_kind().is_standard(),
+            "Error: match_kind must be standard."
+        );
+        FindOverlappingIterator {
+            pma: &self.pma,
+            haystack: SymbolWithEndOffsetIterator::new(haystack),
+            state_id: ROOT_STATE_IDX,
+            pos: 0,
+            output_pos: None,
+        }
+    }
+
+    /// Returns an iterator of leftmost matches in the given haystack.
+    ///
+    /// # Arguments
+    ///
+    /// * `haystack` - Symbol sequence to search for.
+    ///
+    /// # Panics
+    ///
+    /// If you do not specify [`MatchKind::LeftmostFirst`] or [`MatchKind::LeftmostLongest`] in
+    /// the construction, the iterator is not supported and the function will panic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::generic::DoubleArrayAhoCorasickGenericBuilder;
+    /// use daachorse::MatchKind;
+    ///
+    /// let patterns: Vec<&[u32]> = vec![&[1, 2], &[1], &[1, 2, 3, 4]];
+    /// let pma = DoubleArrayAhoCorasickGenericBuilder::new()
+    ///     .match_kind(MatchKind::LeftmostLongest)
+    ///     .build::<_, _, u32, usize>(patterns)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.leftmost_find_iter([1, 2, 3, 4]);
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 4, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn leftmost_find_iter<P>(&self, haystack: P) -> LeftmostFindIterator<'_, P, S, V>
+    where
+        P: AsRef<[S]>,
+    {
+        assert!(
+            self.pma.match_kind().is_leftmost(),
+            "Error: match_kind must be leftmost."
+        );
+        LeftmostFindIterator {
+            pma: &self.pma,
+            haystack,
+            pos: 0,
+        }
+    }
+}
+
+impl<S, V> DoubleArrayAhoCorasickGeneric<S, V>
+where
+    S: Into<u32> + Copy,
+{
+    /// Returns the total number of states this automaton has.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::generic::DoubleArrayAhoCorasickGeneric;
+    ///
+    /// let patterns: Vec<&[u16]> = vec![&[2, 3, 4], &[1, 2], &[1]];
+    /// let pma = DoubleArrayAhoCorasickGeneric::<u16, usize>::new(patterns).unwrap();
+    ///
+    /// assert_eq!(pma.num_states(), 6);
+    /// ```
+    #[must_use]
+    pub fn num_states(&self) -> usize {
+        self.pma.num_states()
+    }
+
+    /// Returns the total number of elements of the double array.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::generic::DoubleArrayAhoCorasickGeneric;
+    ///
+    /// let patterns: Vec<&[u16]> = vec![&[2, 3, 4], &[1, 2], &[1]];
+    /// let pma = DoubleArrayAhoCorasickGeneric::<u16, usize>::new(patterns).unwrap();
+    ///
+    /// assert_eq!(pma.num_elements(), 8);
+    /// ```
+    #[must_use]
+    pub fn num_elements(&self) -> usize {
+        self.pma.num_elements()
+    }
+
+    /// Returns the total amount of heap used by this automaton in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::generic::DoubleArrayAhoCorasickGeneric;
+    ///
+    /// let patterns: Vec<&[u16]> = vec![&[2, 3, 4], &[1, 2], &[1]];
+    /// let pma = DoubleArrayAhoCorasickGeneric::<u16, u32>::new(patterns).unwrap();
+    ///
+    /// assert_eq!(184, pma.heap_bytes());
+    /// ```
+    #[must_use]
+    pub fn heap_bytes(&self) -> usize {
+        self.pma.heap_bytes()
+    }
+
+    /// Returns the [`MatchKind`] specified in the construction.
+    #[inline(always)]
+    #[must_use]
+    pub const fn match_kind(&self) -> MatchKind {
+        self.pma.match_kind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec::Vec;
+
+    use crate::DoubleArrayAhoCorasickBuilder;
+
+    // Generates pseudo-random bytes with xorshift.
+    fn random_bytes(seed: &mut u32, len: usize, alphabet: u8) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                *seed ^= *seed << 13;
+                *seed ^= *seed >> 17;
+                *seed ^= *seed << 5;
+                b'a' + u8::try_from(*seed % u32::from(alphabet)).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_consistent_with_bytewise() {
+        let mut seed = 2463534242;
+        let mut patterns = vec![];
+        while patterns.len() < 50 {
+            let len = usize::try_from(seed % 4).unwrap() + 1;
+            let pattern = random_bytes(&mut seed, len, 4);
+            if !patterns.contains(&pattern) {
+                patterns.push(pattern);
+            }
+        }
+        let haystack = random_bytes(&mut seed, 300, 5);
+        // Shifts symbols to values that do not fit in a byte.
+        let to_symbols =
+            |bytes: &[u8]| -> Vec<u32> { bytes.iter().map(|&c| u32::from(c) * 100_003).collect() };
+        let sym_patterns: Vec<_> = patterns.iter().map(|p| to_symbols(p)).collect();
+        let sym_haystack = to_symbols(&haystack);
+
+        let pma = crate::DoubleArrayAhoCorasick::<u32>::new(&patterns).unwrap();
+        let sym_pma = DoubleArrayAhoCorasickGeneric::<u32, u32>::new(&sym_patterns).unwrap();
+        assert_eq!(
+            pma.find_iter(&haystack).collect::<Vec<_>>(),
+            sym_pma.find_iter(&sym_haystack).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            pma.find_overlapping_iter(&haystack).collect::<Vec<_>>(),
+            sym_pma
+                .find_overlapping_iter(&sym_haystack)
+                .collect::<Vec<_>>()
+        );
+
+        for match_kind in [MatchKind::LeftmostFirst, MatchKind::LeftmostLongest] {
+            let pma = DoubleArrayAhoCorasickBuilder::new()
+                .match_kind(match_kind)
+                .build::<_, _, u32>(&patterns)
+                .unwrap();
+            let sym_pma = DoubleArrayAhoCorasickGenericBuilder::new()
+                .match_kind(match_kind)
+                .build::<_, _, u32, u32>(&sym_patterns)
+                .unwrap();
+            assert_eq!(
+                pma.leftmost_find_iter(&haystack).collect::<Vec<_>>(),
+                sym_pma
+                    .leftmost_find_iter(&sym_haystack)
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_large_alphabet() {
+        // More distinct symbols than bytes.
+        let patterns: Vec<Vec<u16>> = (0..1000).map(|i| vec![i, i + 1]).collect();
+        let pma = DoubleArrayAhoCorasickGeneric::<u16, u32>::new(&patterns).unwrap();
+        let haystack: Vec<u16> = (0..1001).collect();
+        let matches: Vec<_> = pma
+            .find_iter(&haystack)
+            .map(|m| (m.start(), m.end(), m.value()))
+            .collect();
+        let expected: Vec<_> = (0..500).map(|i| (2 * i, 2 * i + 2, 2 * i as u32)).collect();
+        assert_eq!(expected, matches);
+        assert_eq!(None, pma.find_iter([1000, 1001]).next());
+    }
+}
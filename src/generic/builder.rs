@@ -0,0 +1,191 @@
+use alloc::vec::Vec;
+
+use crate::charwise::CharwiseDoubleArrayAhoCorasickBuilder;
+use crate::errors::{DaachorseError, Result};
+use crate::generic::{DoubleArrayAhoCorasickGeneric, MatchKind, SymbolMapper};
+
+/// Builder for [`DoubleArrayAhoCorasickGeneric`].
+///
+/// The construction is shared with
+/// [`CharwiseDoubleArrayAhoCorasickBuilder`](crate::CharwiseDoubleArrayAhoCorasickBuilder).
+pub struct DoubleArrayAhoCorasickGenericBuilder {
+    match_kind: MatchKind,
+    num_free_blocks: u32,
+}
+
+impl Default for DoubleArrayAhoCorasickGenericBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DoubleArrayAhoCorasickGenericBuilder {
+    /// Creates a new [`DoubleArrayAhoCorasickGenericBuilder`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::generic::DoubleArrayAhoCorasickGenericBuilder;
+    ///
+    /// let patterns: Vec<&[u32]> = vec![&[7, 100_000], &[100_000], &[42]];
+    ///
+    /// let builder = DoubleArrayAhoCorasickGenericBuilder::new();
+    /// let pma = builder.build::<_, _, u32, usize>(patterns).unwrap();
+    ///
+    /// let mut it = pma.find_iter([7, 100_000, 3, 42]);
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 2, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((3, 4, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            match_kind: MatchKind::Standard,
+            num_free_blocks: 16,
+        }
+    }
+
+    /// Specifies [`MatchKind`] to build.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - Match kind.
+    #[must_use]
+    pub const fn match_kind(mut self, kind: MatchKind) -> Self {
+        self.match_kind = kind;
+        self
+    }
+
+    /// Specifies the number of last blocks to search bases.
+    ///
+    /// The smaller the number is, the faster the construction time will be; however, the memory
+    /// efficiency can be degraded.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number of last blocks.
+    ///
+    /// # Panics
+    ///
+    /// `n` must be greater than or equal to 1.
+    #[must_use]
+    pub const fn num_free_blocks(mut self, n: u32) -> Self {
+        assert!(n >= 1);
+        self.num_free_blocks = n;
+        self
+    }
+
+    /// Builds and returns a new [`DoubleArrayAhoCorasickGeneric`] from input patterns. The value
+    /// `i` is automatically associated with `patterns[i]`.
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - List of patterns.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned when
+    ///   - `patterns` is empty,
+    ///   - `patterns` contains entries of length zero,
+    ///   - `patterns` contains duplicate entries,
+    ///   - the conversion from the index `i` to the specified type `V` fails,
+    ///   - the scale of `patterns` exceeds the expected one, or
+    ///   - the scale of the resulting automaton exceeds the expected one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::generic::DoubleArrayAhoCorasickGenericBuilder;
+    ///
+    /// let patterns: Vec<&[u16]> = vec![&[1, 2, 3], &[2, 3], &[5]];
+    /// let pma = DoubleArrayAhoCorasickGenericBuilder::new()
+    ///     .build::<_, _, u16, usize>(patterns)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_iter([1, 2, 3, 4, 5]);
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 3, 0), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((4, 5, 2), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn build<I, P, S, V>(self, patterns: I) -> Result<DoubleArrayAhoCorasickGeneric<S, V>>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[S]>,
+        S: Into<u32> + Copy,
+        V: Copy + TryFrom<usize>,
+    {
+        // The following code implicitly replaces large indices with 0,
+        // but build_with_values() returns an error variant for such iterators.
+        let patvals: Vec<_> = patterns
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| V::try_from(i).map(|i| (p, i)))
+            .collect::<Result<_, _>>()
+            .map_err(|_| DaachorseError::invalid_conversion("index", "V"))?;
+        self.build_with_values(patvals)
+    }
+
+    /// Builds and returns a new [`DoubleArrayAhoCorasickGeneric`] from input pattern-value pairs.
+    ///
+    /// # Arguments
+    ///
+    /// * `patvals` - List of pattern-value pairs.
+    ///
+    /// # Errors
+    ///
+    /// [`DaachorseError`] is returned when
+    ///   - `patvals` is empty,
+    ///   - `patvals` contains patterns of length zero,
+    ///   - `patvals` contains duplicate patterns,
+    ///   - the scale of `patvals` exceeds the expected one, or
+    ///   - the scale of the resulting automaton exceeds the expected one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use daachorse::generic::DoubleArrayAhoCorasickGenericBuilder;
+    ///
+    /// let patvals = vec![(vec![1u32, 2, 3], 10), (vec![2, 3], 20), (vec![5], 30)];
+    /// let pma = DoubleArrayAhoCorasickGenericBuilder::new()
+    ///     .build_with_values(patvals)
+    ///     .unwrap();
+    ///
+    /// let mut it = pma.find_iter([1, 2, 3, 4, 5]);
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((0, 3, 10), (m.start(), m.end(), m.value()));
+    ///
+    /// let m = it.next().unwrap();
+    /// assert_eq!((4, 5, 30), (m.start(), m.end(), m.value()));
+    ///
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn build_with_values<I, P, S, V>(
+        self,
+        patvals: I,
+    ) -> Result<DoubleArrayAhoCorasickGeneric<S, V>>
+    where
+        I: IntoIterator<Item = (P, V)>,
+        P: AsRef<[S]>,
+        S: Into<u32> + Copy,
+        V: Copy,
+    {
+        CharwiseDoubleArrayAhoCorasickBuilder::<SymbolMapper<S>>::new_generic()
+            .match_kind(self.match_kind)
+            .num_free_blocks(self.num_free_blocks)
+            .build_with_symbols(patvals, |pattern, symbols| {
+                symbols.extend(pattern.as_ref().iter().map(|&c| c.into()));
+            })
+            .map(|pma| DoubleArrayAhoCorasickGeneric { pma })
+    }
+}
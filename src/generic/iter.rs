@@ -0,0 +1,130 @@
+//! Iterators for [`DoubleArrayAhoCorasickGeneric`](super::DoubleArrayAhoCorasickGeneric).
+//!
+//! The iterators are shared with [`CharwiseDoubleArrayAhoCorasick`], and the haystack is read
+//! as a sequence of symbols instead of a UTF-8 string.
+//!
+//! [`CharwiseDoubleArrayAhoCorasick`]: crate::CharwiseDoubleArrayAhoCorasick
+
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+use crate::charwise::iter::{self, leftmost_find_next};
+use crate::charwise::Haystack;
+use crate::generic::SymbolMapper;
+use crate::Match;
+
+/// Iterator created by
+/// [`DoubleArrayAhoCorasickGeneric::find_iter()`](super::DoubleArrayAhoCorasickGeneric::find_iter).
+pub type FindIterator<'a, P, S, V> = iter::FindIterator<'a, P, V, SymbolMapper<S>>;
+
+/// Iterator created by
+/// [`DoubleArrayAhoCorasickGeneric::find_overlapping_iter()`](super::DoubleArrayAhoCorasickGeneric::find_overlapping_iter).
+pub type FindOverlappingIterator<'a, P, S, V> =
+    iter::FindOverlappingIterator<'a, P, V, SymbolMapper<S>>;
+
+/// Iterator created by
+/// [`DoubleArrayAhoCorasickGeneric::leftmost_find_iter()`](super::DoubleArrayAhoCorasickGeneric::leftmost_find_iter).
+pub type LeftmostFindIterator<'a, P, S, V> = iter::LeftmostFindIterator<'a, P, V, SymbolMapper<S>>;
+
+/// Iterator for symbol sequences with end positions.
+#[doc(hidden)]
+pub struct SymbolWithEndOffsetIterator<P, S> {
+    inner: P,
+    pos: usize,
+    symbol: PhantomData<fn() -> S>,
+}
+
+impl<P, S> SymbolWithEndOffsetIterator<P, S>
+where
+    P: AsRef<[S]>,
+{
+    #[allow(clippy::missing_const_for_fn)]
+    pub(crate) fn new(inner: P) -> Self {
+        Self {
+            inner,
+            pos: 0,
+            symbol: PhantomData,
+        }
+    }
+}
+
+impl<P, S> Clone for SymbolWithEndOffsetIterator<P, S>
+where
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            pos: self.pos,
+            symbol: PhantomData,
+        }
+    }
+}
+
+impl<P, S> Iterator for SymbolWithEndOffsetIterator<P, S>
+where
+    P: AsRef<[S]>,
+    S: Copy,
+{
+    type Item = (usize, S);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = *self.inner.as_ref().get(self.pos)?;
+        self.pos += 1;
+        Some((self.pos, c))
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let rest = self.inner.as_ref().len() - self.pos;
+        (rest, Some(rest))
+    }
+}
+
+impl<P, S> FusedIterator for SymbolWithEndOffsetIterator<P, S>
+where
+    P: AsRef<[S]>,
+    S: Copy,
+{
+}
+
+impl<P, S> Haystack<P> for SymbolMapper<S>
+where
+    S: Into<u32> + Copy,
+{
+    type Iter = SymbolWithEndOffsetIterator<P, S>;
+}
+
+impl<P, S, V> Iterator for LeftmostFindIterator<'_, P, S, V>
+where
+    P: AsRef<[S]>,
+    S: Into<u32> + Copy,
+    V: Copy,
+{
+    type Item = Match<V>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let pos = self.pos;
+        let haystack = self.haystack.as_ref()[pos..]
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| (pos + i + 1, c));
+        leftmost_find_next(self.pma, haystack, &mut self.pos)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Each match consumes at least one symbol.
+        (0, Some(self.haystack.as_ref().len() - self.pos))
+    }
+}
+
+impl<P, S, V> FusedIterator for LeftmostFindIterator<'_, P, S, V>
+where
+    P: AsRef<[S]>,
+    S: Into<u32> + Copy,
+    V: Copy,
+{
+}
@@ -0,0 +1,132 @@
+use core::marker::PhantomData;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::charwise::{Mapper, Sealed};
+use crate::utils::FromU32;
+
+const INVALID_CODE: u32 = u32::MAX;
+
+// Symbols smaller than this value are mapped with a direct table, and the others are mapped with
+// binary search, so that large symbol values do not blow up the table.
+const DENSE_LEN: u32 = 1 << 16;
+
+/// Mapping from symbols of type `S` to dense codes assigned in descending order of frequency.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SymbolMapper<S> {
+    dense: Vec<u32>,
+    // Pairs of a symbol >= DENSE_LEN and its code, sorted by symbols.
+    sparse: Vec<(u32, u32)>,
+    alphabet_size: u32,
+    symbol: PhantomData<fn(S)>,
+}
+
+impl<S> SymbolMapper<S> {
+    pub fn new(freqs: &BTreeMap<u32, u32>) -> Self {
+        let mut sorted: Vec<_> = freqs.iter().map(|(&c, &f)| (c, f)).collect();
+        // Note: `c1.cmp(c2)` is necessary to uniquely determine the sort result.
+        sorted.sort_unstable_by(|(c1, f1), (c2, f2)| f2.cmp(f1).then_with(|| c1.cmp(c2)));
+
+        let dense_len = freqs
+            .range(..DENSE_LEN)
+            .next_back()
+            .map_or(0, |(&c, _)| usize::from_u32(c) + 1);
+        let mut dense = vec![INVALID_CODE; dense_len];
+        let mut sparse = vec![];
+        for (i, &(c, _)) in sorted.iter().enumerate() {
+            let code = u32::try_from(i).unwrap();
+            if c < DENSE_LEN {
+                dense[usize::from_u32(c)] = code;
+            } else {
+                sparse.push((c, code));
+            }
+        }
+        sparse.sort_unstable();
+        Self {
+            dense,
+            sparse,
+            alphabet_size: u32::try_from(sorted.len()).unwrap(),
+            symbol: PhantomData,
+        }
+    }
+}
+
+// Implemented manually because deriving requires `S: Default`.
+impl<S> Default for SymbolMapper<S> {
+    fn default() -> Self {
+        Self {
+            dense: vec![],
+            sparse: vec![],
+            alphabet_size: 0,
+            symbol: PhantomData,
+        }
+    }
+}
+
+impl<S> Sealed for SymbolMapper<S> {}
+
+impl<S> Mapper for SymbolMapper<S>
+where
+    S: Into<u32> + Copy,
+{
+    type Symbol = S;
+    type Freqs = BTreeMap<u32, u32>;
+
+    #[inline(always)]
+    fn count(freqs: &mut BTreeMap<u32, u32>, c: u32) {
+        *freqs.entry(c).or_insert(0) += 1;
+    }
+
+    fn from_freqs(freqs: &BTreeMap<u32, u32>) -> Self {
+        Self::new(freqs)
+    }
+
+    #[inline(always)]
+    fn get(&self, c: u32) -> Option<u32> {
+        if let Some(&code) = self.dense.get(usize::from_u32(c)) {
+            return Some(code).filter(|&code| code != INVALID_CODE);
+        }
+        if c < DENSE_LEN {
+            return None;
+        }
+        self.sparse
+            .binary_search_by_key(&c, |&(c, _)| c)
+            .ok()
+            .map(|i| self.sparse[i].1)
+    }
+
+    #[inline(always)]
+    fn alphabet_size(&self) -> u32 {
+        self.alphabet_size
+    }
+
+    #[inline]
+    fn heap_bytes(&self) -> usize {
+        self.dense.len() * core::mem::size_of::<u32>()
+            + self.sparse.len() * core::mem::size_of::<(u32, u32)>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_mapper() {
+        let freqs: BTreeMap<_, _> = [(0, 3), (1, 6), (3, 2), (70000, 3), (u32::MAX, 3)]
+            .into_iter()
+            .collect();
+        let mapper = SymbolMapper::<u32>::new(&freqs);
+
+        assert_eq!(mapper.alphabet_size(), 5);
+        assert_eq!(mapper.get(0), Some(1));
+        assert_eq!(mapper.get(1), Some(0));
+        assert_eq!(mapper.get(2), None);
+        assert_eq!(mapper.get(3), Some(4));
+        assert_eq!(mapper.get(4), None); // out-of-range of the dense table
+        assert_eq!(mapper.get(70000), Some(2));
+        assert_eq!(mapper.get(70001), None);
+        assert_eq!(mapper.get(u32::MAX), Some(3));
+    }
+}
@@ -188,16 +188,21 @@ compile_error!("`target_pointer_width` must be 32 or 64");
 #[macro_use]
 extern crate alloc;
 
+#[cfg(feature = "std")]
+extern crate std;
+
 mod build_helper;
 pub mod bytewise;
 pub mod charwise;
 pub mod errors;
+pub mod generic;
 mod intpack;
 mod nfa_builder;
 mod serializer;
 mod utils;
 
 use core::num::NonZeroU32;
+use core::ops::ControlFlow;
 
 use alloc::vec::Vec;
 
@@ -206,6 +211,17 @@ pub use bytewise::{DoubleArrayAhoCorasick, DoubleArrayAhoCorasickBuilder};
 pub use charwise::{CharwiseDoubleArrayAhoCorasick, CharwiseDoubleArrayAhoCorasickBuilder};
 pub use serializer::Serializable;
 
+// Automata are shared among threads, so the following types must be Send and Sync.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<DoubleArrayAhoCorasick<u32>>();
+    assert_send_sync::<CharwiseDoubleArrayAhoCorasick<u32>>();
+    assert_send_sync::<bytewise::SortedDoubleArrayAhoCorasick>();
+    assert_send_sync::<bytewise::GroupedDoubleArrayAhoCorasick>();
+    assert_send_sync::<bytewise::narrow::DoubleArrayAhoCorasick16<u32>>();
+    assert_send_sync::<generic::DoubleArrayAhoCorasickGeneric<u32, u32>>();
+};
+
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 struct Output<V> {
     value: V,
@@ -315,6 +331,61 @@ where
     }
 }
 
+/// Consumer of matches pushed by [`DoubleArrayAhoCorasick::drive()`].
+///
+/// Implement this trait to count, collect, filter, or forward matches in a push-based pipeline.
+/// Unlike a closure, a sink is a named type, so it can carry its own state and be reused or
+/// composed with other sinks.
+///
+/// # Examples
+///
+/// ```
+/// use core::ops::ControlFlow;
+///
+/// use daachorse::{DoubleArrayAhoCorasick, Match, MatchSink};
+///
+/// // Counts matches up to the limit.
+/// struct Counter {
+///     count: usize,
+///     limit: usize,
+/// }
+///
+/// impl<V> MatchSink<V> for Counter {
+///     fn push(&mut self, _m: Match<V>) -> ControlFlow<()> {
+///         self.count += 1;
+///         if self.count == self.limit {
+///             ControlFlow::Break(())
+///         } else {
+///             ControlFlow::Continue(())
+///         }
+///     }
+/// }
+///
+/// let patterns = vec!["bcd", "ab", "a"];
+/// let pma = DoubleArrayAhoCorasick::<usize>::new(patterns).unwrap();
+///
+/// let mut counter = Counter { count: 0, limit: 2 };
+/// pma.drive("abcdabcd", &mut counter);
+/// assert_eq!(2, counter.count);
+/// ```
+pub trait MatchSink<V> {
+    /// Consumes a match. Returning [`ControlFlow::Break`] stops the search.
+    ///
+    /// # Arguments
+    ///
+    /// * `m` - Reported match.
+    fn push(&mut self, m: Match<V>) -> ControlFlow<()>;
+}
+
+/// Collects all matches.
+impl<V> MatchSink<V> for Vec<Match<V>> {
+    #[inline(always)]
+    fn push(&mut self, m: Match<V>) -> ControlFlow<()> {
+        Vec::push(self, m);
+        ControlFlow::Continue(())
+    }
+}
+
 /// A search option of the Aho-Corasick automaton
 /// specified in [`DoubleArrayAhoCorasickBuilder::match_kind`].
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -394,6 +465,47 @@ impl Serializable for MatchKind {
     }
 }
 
+/// A policy for choosing the single match reported per end position, specified in
+/// [`DoubleArrayAhoCorasick::find_overlapping_no_suffix_iter_with_policy`].
+///
+/// When several patterns end at the same position, e.g., `abc`, `bc`, and `c` over `abc`,
+/// only one of them is reported according to this policy.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SuffixPolicy {
+    /// Reports the longest pattern. This is the behavior of
+    /// [`find_overlapping_no_suffix_iter()`](DoubleArrayAhoCorasick::find_overlapping_no_suffix_iter()).
+    Longest,
+
+    /// Reports the shortest pattern.
+    Shortest,
+
+    /// Reports the pattern with the smallest value. If several such patterns exist, the longest
+    /// one is reported. When the automaton is built with [`DoubleArrayAhoCorasick::new()`] or
+    /// [`DoubleArrayAhoCorasickBuilder::build()`], values are pattern indices, so this reports
+    /// the pattern registered first.
+    SmallestValue,
+}
+
+/// Phase of the construction reported to the callback specified in
+/// [`DoubleArrayAhoCorasickBuilder::progress`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BuildPhase {
+    /// Inserting patterns into a trie. The total is the number of patterns. If it is unknown in
+    /// advance, the total is 0 except in the last report.
+    TrieInsertion,
+
+    /// Computing fail links. The total is the number of states except the root and dead states.
+    FailConstruction,
+
+    /// Linking each state to its outputs. The total is the number of states except the root and
+    /// dead states.
+    OutputLinking,
+
+    /// Arranging states in the double array. The total is the number of states except the dead
+    /// state.
+    BaseAssignment,
+}
+
 /// Empty value type.
 ///
 /// This is a unit struct with no field. This type can be used to reduce memory consumption when
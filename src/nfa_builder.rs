@@ -22,6 +22,13 @@ impl EdgeLabel for u8 {
     }
 }
 
+// Used for symbols of the generic automaton, whose lengths are counted in symbols.
+impl EdgeLabel for u32 {
+    fn num_bytes(&self) -> usize {
+        1
+    }
+}
+
 impl EdgeLabel for char {
     fn num_bytes(&self) -> usize {
         self.len_utf8()
@@ -56,6 +63,10 @@ pub struct NfaBuilder<L, V> {
     pub(crate) states: Vec<RefCell<NfaBuilderState<L, V>>>,
     pub(crate) outputs: Vec<Output<V>>, // in which common parts are merged.
     pub(crate) len: usize,
+    // The number of patterns given to add(), including ones skipped by leftmost-first.
+    num_inputs: usize,
+    // Indices of patterns skipped by leftmost-first because an earlier pattern is their prefix.
+    pub(crate) shadowed: Vec<usize>,
     pub(crate) match_kind: MatchKind,
 }
 
@@ -72,12 +83,17 @@ where
             ],
             outputs: vec![],
             len: 0,
+            num_inputs: 0,
+            shadowed: vec![],
             match_kind,
         }
     }
 
     #[inline(always)]
     pub(crate) fn add(&mut self, pattern: &[L], value: V) -> Result<()> {
+        let index = self.num_inputs;
+        self.num_inputs += 1;
+
         let pattern_len = pattern
             .iter()
             .fold(0, |acc, c| acc + c.num_bytes())
@@ -92,6 +108,7 @@ where
                 // If state_id has an output, the descendants will never searched.
                 let output = &self.states[usize::from_u32(state_id)].borrow().output;
                 if output.is_some() {
+                    self.shadowed.push(index);
                     return Ok(());
                 }
             }
@@ -120,7 +137,10 @@ where
         Ok(())
     }
 
-    pub(crate) fn build_fails(&self) -> Vec<u32> {
+    pub(crate) fn build_fails<F>(&self, mut progress: F) -> Vec<u32>
+    where
+        F: FnMut(usize),
+    {
         let mut q = Vec::with_capacity(self.states.len());
         for &child_id in self.states[usize::from_u32(ROOT_STATE_ID)]
             .borrow()
@@ -134,6 +154,7 @@ where
         while qi < q.len() {
             let state_id = usize::from_u32(q[qi]);
             qi += 1;
+            progress(qi);
 
             let s = &self.states[state_id].borrow();
             for (&c, &child_id) in &s.edges {
@@ -155,7 +176,10 @@ where
         q
     }
 
-    pub(crate) fn build_fails_leftmost(&self) -> Vec<u32> {
+    pub(crate) fn build_fails_leftmost<F>(&self, mut progress: F) -> Vec<u32>
+    where
+        F: FnMut(usize),
+    {
         let mut q = Vec::with_capacity(self.states.len());
         for &child_id in self.states[usize::from_u32(ROOT_STATE_ID)]
             .borrow()
@@ -169,6 +193,7 @@ where
         while qi < q.len() {
             let state_id = usize::from_u32(q[qi]);
             qi += 1;
+            progress(qi);
 
             let s = &mut self.states[state_id].borrow_mut();
 
@@ -206,13 +231,84 @@ where
         q
     }
 
-    pub(crate) fn build_outputs(&mut self, q: &[u32]) {
-        // The queue (built in build_fails or _leftmost) will not have the root state id,
+    /// Returns the states in breadth-first order without computing fail links, which are left
+    /// pointing to the root.
+    pub(crate) fn build_queue<F>(&self, mut progress: F) -> Vec<u32>
+    where
+        F: FnMut(usize),
+    {
+        let mut q = Vec::with_capacity(self.states.len());
+        for &child_id in self.states[usize::from_u32(ROOT_STATE_ID)]
+            .borrow()
+            .edges
+            .values()
+        {
+            q.push(child_id);
+        }
+
+        let mut qi = 0;
+        while qi < q.len() {
+            let state_id = usize::from_u32(q[qi]);
+            qi += 1;
+            progress(qi);
+            q.extend(self.states[state_id].borrow().edges.values());
+        }
+        q
+    }
+
+    /// Merges states that behave the same in the search into one representative, and returns
+    /// the queue without the merged states.
+    ///
+    /// Two states are merged if they have the same output value and length, the same fail link,
+    /// and the same labels leading to merged children. This is decided from the deepest states,
+    /// so common suffixes of patterns with the same values and lengths are shared. Edges and fail
+    /// links are redirected to the representatives, so it must be called before
+    /// [`NfaBuilder::build_outputs()`].
+    pub(crate) fn merge_equivalent_states(&mut self, q: &[u32]) -> Vec<u32>
+    where
+        V: Copy + Ord,
+    {
+        type Signature<L, V> = (Vec<(L, u32)>, u32, Option<(V, NonZeroU32)>);
+
+        let mut reps: Vec<u32> = (0..u32::try_from(self.states.len()).unwrap()).collect();
+        let mut classes = alloc::collections::BTreeMap::<Signature<L, V>, u32>::new();
+
+        // The queue is in the breadth-first order, so children are visited before parents.
+        for &state_id in q.iter().rev() {
+            let s = self.states[usize::from_u32(state_id)].borrow();
+            let edges = s
+                .edges
+                .iter()
+                .map(|(&c, &child_id)| (c, reps[usize::from_u32(child_id)]))
+                .collect();
+            let signature = (edges, s.fail, s.output);
+            reps[usize::from_u32(state_id)] = *classes.entry(signature).or_insert(state_id);
+        }
+
+        for state in &self.states {
+            let s = &mut state.borrow_mut();
+            for child_id in s.edges.values_mut() {
+                *child_id = reps[usize::from_u32(*child_id)];
+            }
+            s.fail = reps[usize::from_u32(s.fail)];
+        }
+        q.iter()
+            .copied()
+            .filter(|&state_id| reps[usize::from_u32(state_id)] == state_id)
+            .collect()
+    }
+
+    pub(crate) fn build_outputs<F>(&mut self, q: &[u32], mut progress: F)
+    where
+        F: FnMut(usize),
+    {
+        // The queue (built in build_fails, _leftmost, or build_queue) will not have the root state id,
         // so in the following processing the output of the root state will not be handled.
         // But, there is no problem since Daachorse does not allow an empty pattern.
         debug_assert_ne!(q[0], ROOT_STATE_ID);
 
-        for &state_id in q {
+        for (i, &state_id) in q.iter().enumerate() {
+            progress(i + 1);
             let s = &mut self.states[usize::from_u32(state_id)].borrow_mut();
             if let Some(output) = s.output {
                 s.output_pos = NonZeroU32::new(u32::try_from(self.outputs.len() + 1).unwrap());
@@ -126,6 +126,28 @@ where
     }
 }
 
+/// Returns the rest of the slice following a serialized value of `S`, or `None` if the slice is
+/// too short.
+#[inline(always)]
+pub fn skip_serialized<S>(src: &[u8]) -> Option<&[u8]>
+where
+    S: Serializable,
+{
+    src.get(S::serialized_bytes()..)
+}
+
+/// Returns the rest of the slice following a serialized vector of `S`, or `None` if the slice is
+/// too short.
+#[inline(always)]
+pub fn skip_serialized_vec<S>(src: &[u8]) -> Option<&[u8]>
+where
+    S: Serializable,
+{
+    let rest = skip_serialized::<u32>(src)?;
+    let (len, _) = u32::deserialize_from_slice(src);
+    rest.get(usize::from_u32(len).checked_mul(S::serialized_bytes())?..)
+}
+
 impl Serializable for Empty {
     #[inline(always)]
     fn serialize_to_vec(&self, _dst: &mut Vec<u8>) {}
@@ -171,6 +193,18 @@ mod tests {
         assert_eq!(x, y);
     }
 
+    #[test]
+    fn test_skip_serialized() {
+        let mut data = vec![];
+        vec![1u32, 2, 3].serialize_to_vec(&mut data);
+        data.push(42);
+        assert_eq!(Some(&data[4..]), skip_serialized::<u32>(&data));
+        assert_eq!(Some(&[42][..]), skip_serialized_vec::<u32>(&data));
+        assert_eq!(None, skip_serialized_vec::<u32>(&data[..12]));
+        assert_eq!(None, skip_serialized_vec::<u32>(&data[..3]));
+        assert_eq!(None, skip_serialized::<u32>(&data[..3]));
+    }
+
     #[test]
     fn test_vec_u32() {
         let x = vec![0x01234567u32, 0x89abcdefu32, 0x02468aceu32];
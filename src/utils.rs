@@ -11,3 +11,19 @@ impl FromU32 for usize {
         unsafe { Self::try_from(src).unwrap_unchecked() }
     }
 }
+
+/// Reads one element of the slice per cache line so that its pages are faulted in and its
+/// contents are loaded into caches.
+///
+/// Whole elements are read rather than bytes because padding bytes of `T` may be uninitialized.
+#[inline(never)]
+pub fn touch_slice<T>(slice: &[T]) {
+    const CACHE_LINE_LEN: usize = 64;
+    let step = core::cmp::max(1, CACHE_LINE_LEN / core::mem::size_of::<T>().max(1));
+    for elem in slice.iter().step_by(step) {
+        // elem is a reference to an initialized element of the slice, so it is valid for reads.
+        // The volatile read keeps the access from being optimized away, and the copy is
+        // forgotten so that the element is not dropped twice.
+        core::mem::forget(unsafe { core::ptr::read_volatile(elem) });
+    }
+}
@@ -429,10 +429,8 @@ macro_rules! testconfig {
         #[test]
         fn $name() {
             run_search_tests($collection, |test| {
-                let pma = $builder::new()
-                    .match_kind(MatchKind::$kind)
-                    .build(test.patterns)
-                    .unwrap();
+                let builder = $builder::new().match_kind(MatchKind::$kind);
+                let pma = ($with)(builder).build(test.patterns).unwrap();
                 pma.find_iter(test.haystack).collect()
             });
         }
@@ -441,10 +439,8 @@ macro_rules! testconfig {
         #[test]
         fn $name() {
             run_search_tests($collection, |test| {
-                let pma = $builder::new()
-                    .match_kind(MatchKind::$kind)
-                    .build(test.patterns)
-                    .unwrap();
+                let builder = $builder::new().match_kind(MatchKind::$kind);
+                let pma = ($with)(builder).build(test.patterns).unwrap();
                 pma.find_overlapping_iter(test.haystack).collect()
             });
         }
@@ -453,10 +449,8 @@ macro_rules! testconfig {
         #[test]
         fn $name() {
             run_search_tests($collection, |test| {
-                let pma = $builder::new()
-                    .match_kind(MatchKind::$kind)
-                    .build(test.patterns)
-                    .unwrap();
+                let builder = $builder::new().match_kind(MatchKind::$kind);
+                let pma = ($with)(builder).build(test.patterns).unwrap();
                 pma.leftmost_find_iter(test.haystack).collect()
             });
         }
@@ -470,7 +464,7 @@ testconfig!(
     DoubleArrayAhoCorasickBuilder,
     AC_STANDARD_NON_OVERLAPPING,
     Standard,
-    |_| ()
+    |b| b
 );
 
 testconfig!(
@@ -479,7 +473,7 @@ testconfig!(
     DoubleArrayAhoCorasickBuilder,
     AC_STANDARD_OVERLAPPING,
     Standard,
-    |_| ()
+    |b| b
 );
 
 testconfig!(
@@ -488,7 +482,7 @@ testconfig!(
     DoubleArrayAhoCorasickBuilder,
     AC_LEFTMOST_LONGEST,
     LeftmostLongest,
-    |_| ()
+    |b| b
 );
 
 testconfig!(
@@ -497,7 +491,44 @@ testconfig!(
     DoubleArrayAhoCorasickBuilder,
     AC_LEFTMOST_FIRST,
     LeftmostFirst,
-    |_| ()
+    |b| b
+);
+
+// Bytewise Daachorse tests with compressed fail links
+testconfig!(
+    non_overlapping,
+    search_standard_non_overlapping_compress_fail,
+    DoubleArrayAhoCorasickBuilder,
+    AC_STANDARD_NON_OVERLAPPING,
+    Standard,
+    |b: DoubleArrayAhoCorasickBuilder<'static>| b.compress_fail(true)
+);
+
+testconfig!(
+    overlapping,
+    search_standard_overlapping_compress_fail,
+    DoubleArrayAhoCorasickBuilder,
+    AC_STANDARD_OVERLAPPING,
+    Standard,
+    |b: DoubleArrayAhoCorasickBuilder<'static>| b.compress_fail(true)
+);
+
+testconfig!(
+    leftmost,
+    search_leftmost_longest_compress_fail,
+    DoubleArrayAhoCorasickBuilder,
+    AC_LEFTMOST_LONGEST,
+    LeftmostLongest,
+    |b: DoubleArrayAhoCorasickBuilder<'static>| b.compress_fail(true)
+);
+
+testconfig!(
+    leftmost,
+    search_leftmost_first_compress_fail,
+    DoubleArrayAhoCorasickBuilder,
+    AC_LEFTMOST_FIRST,
+    LeftmostFirst,
+    |b: DoubleArrayAhoCorasickBuilder<'static>| b.compress_fail(true)
 );
 
 // Charwise Daachorse tests
@@ -507,7 +538,7 @@ testconfig!(
     CharwiseDoubleArrayAhoCorasickBuilder,
     AC_STANDARD_NON_OVERLAPPING,
     Standard,
-    |_| ()
+    |b| b
 );
 
 testconfig!(
@@ -516,7 +547,7 @@ testconfig!(
     CharwiseDoubleArrayAhoCorasickBuilder,
     AC_STANDARD_OVERLAPPING,
     Standard,
-    |_| ()
+    |b| b
 );
 
 testconfig!(
@@ -525,7 +556,7 @@ testconfig!(
     CharwiseDoubleArrayAhoCorasickBuilder,
     AC_LEFTMOST_LONGEST,
     LeftmostLongest,
-    |_| ()
+    |b| b
 );
 
 testconfig!(
@@ -534,7 +565,7 @@ testconfig!(
     CharwiseDoubleArrayAhoCorasickBuilder,
     AC_LEFTMOST_FIRST,
     LeftmostFirst,
-    |_| ()
+    |b| b
 );
 
 fn run_search_tests<F: FnMut(&SearchTest) -> Vec<Match<usize>>>(which: TestCollection, mut f: F) {
@@ -548,7 +579,7 @@ fn run_search_tests<F: FnMut(&SearchTest) -> Vec<Match<usize>>>(which: TestColle
         for test in tests {
             assert_eq!(
                 test.matches,
-                get_match_triples(f(&test)).as_slice(),
+                get_match_triples(f(test)).as_slice(),
                 "test: {}, patterns: {:?}, haystack: {:?}",
                 test.name,
                 test.patterns,
@@ -0,0 +1,68 @@
+use std::sync::{Arc, Mutex};
+
+use daachorse::{BuildPhase, DoubleArrayAhoCorasick, DoubleArrayAhoCorasickBuilder};
+
+#[test]
+fn test_progress() {
+    let reports = Arc::new(Mutex::new(vec![]));
+    let reports_cloned = Arc::clone(&reports);
+
+    let patterns: Vec<_> = (0..100_000).map(|i| format!("{i:x}")).collect();
+    let pma: DoubleArrayAhoCorasick<u32> = DoubleArrayAhoCorasickBuilder::new()
+        .progress(move |phase, done, total| {
+            reports_cloned.lock().unwrap().push((phase, done, total));
+        })
+        .build(&patterns)
+        .unwrap();
+    assert_eq!(
+        DoubleArrayAhoCorasick::<u32>::new(&patterns)
+            .unwrap()
+            .find_iter("0123456789abcdef")
+            .collect::<Vec<_>>(),
+        pma.find_iter("0123456789abcdef").collect::<Vec<_>>()
+    );
+
+    let reports = reports.lock().unwrap();
+    for phase in [
+        BuildPhase::TrieInsertion,
+        BuildPhase::FailConstruction,
+        BuildPhase::OutputLinking,
+        BuildPhase::BaseAssignment,
+    ] {
+        let phase_reports: Vec<_> = reports.iter().filter(|r| r.0 == phase).collect();
+        let &&(_, first_done, total) = phase_reports.first().unwrap();
+        let &&(_, last_done, _) = phase_reports.last().unwrap();
+        assert_eq!(0, first_done);
+        assert_eq!(total, last_done);
+        assert!(phase_reports.len() >= 3);
+        assert!(phase_reports.windows(2).all(|w| w[0].1 < w[1].1));
+    }
+    assert!(reports.contains(&(BuildPhase::TrieInsertion, 100_000, 100_000)));
+    assert!(reports.contains(&(BuildPhase::TrieInsertion, 65536, 100_000)));
+}
+
+#[test]
+fn test_progress_unknown_total() {
+    let mut reports = vec![];
+
+    // The size of the iterator is unknown in advance because of filter().
+    let patvals = (0..100)
+        .map(|i| (format!("{i:x}"), i))
+        .filter(|(p, _)| p.len() == 2);
+    let _ = DoubleArrayAhoCorasickBuilder::new()
+        .progress(|phase, done, total| reports.push((phase, done, total)))
+        .build_with_values(patvals)
+        .unwrap();
+
+    let trie_reports: Vec<_> = reports
+        .iter()
+        .filter(|r| r.0 == BuildPhase::TrieInsertion)
+        .collect();
+    assert_eq!(
+        vec![
+            &(BuildPhase::TrieInsertion, 0, 0),
+            &(BuildPhase::TrieInsertion, 84, 84)
+        ],
+        trie_reports
+    );
+}
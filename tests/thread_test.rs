@@ -0,0 +1,50 @@
+use std::sync::Arc;
+use std::thread;
+
+use daachorse::{CharwiseDoubleArrayAhoCorasick, DoubleArrayAhoCorasick};
+
+#[test]
+fn test_shared_bytewise() {
+    let patterns: Vec<_> = (0..1000).map(|i| format!("{i:x}")).collect();
+    let pma = Arc::new(DoubleArrayAhoCorasick::<u32>::new(&patterns).unwrap());
+
+    let handles: Vec<_> = (0..8)
+        .map(|t| {
+            let pma = Arc::clone(&pma);
+            thread::spawn(move || {
+                let haystack: String = (0..1000).map(|i| format!("{:x}", i * (t + 1))).collect();
+                let matches: Vec<_> = pma.find_overlapping_iter(&haystack).collect();
+                (haystack, matches)
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        let (haystack, matches) = handle.join().unwrap();
+        assert_eq!(
+            pma.find_overlapping_iter(&haystack).collect::<Vec<_>>(),
+            matches
+        );
+        assert!(!matches.is_empty());
+    }
+}
+
+#[test]
+fn test_shared_charwise() {
+    let patterns = vec!["全世界", "世界", "に"];
+    let pma = Arc::new(CharwiseDoubleArrayAhoCorasick::<u32>::new(patterns).unwrap());
+
+    let handles: Vec<_> = (0..8)
+        .map(|t| {
+            let pma = Arc::clone(&pma);
+            thread::spawn(move || {
+                let haystack = "全世界中に".repeat(t + 1);
+                pma.find_iter(&haystack).count()
+            })
+        })
+        .collect();
+
+    for (t, handle) in handles.into_iter().enumerate() {
+        assert_eq!(2 * (t + 1), handle.join().unwrap());
+    }
+}